@@ -1,8 +1,93 @@
-use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
-use reqwest::Error as RError;
+use std::{
+    fmt,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use reqwest::header::{AUTHORIZATION, CONTENT_TYPE, RETRY_AFTER};
+use reqwest::{Error as RError, Response, StatusCode};
 
 use serde_derive::{Deserialize, Serialize};
 
+use crate::{due_parser::parse_duration, priority::DEFAULT_PRIORITY};
+
+/// A failed request, as reported back to the TUI.
+#[derive(Debug, Clone)]
+pub struct ApiError {
+    pub message: String,
+    /// The request never reached Todoist, like when the network is down.
+    pub offline: bool,
+    /// Seconds Todoist asked us to wait before trying again.
+    pub retry_after: Option<u64>,
+}
+
+/// How long to wait when Todoist rate limits without a `Retry-After`.
+const DEFAULT_RETRY_AFTER: u64 = 60;
+
+/// Todoist answered 429 Too Many Requests.
+#[derive(Debug)]
+pub struct RateLimited {
+    pub retry_after: u64,
+}
+
+impl fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Rate limited by Todoist for {}s", self.retry_after)
+    }
+}
+
+impl std::error::Error for RateLimited {}
+
+/// Like `error_for_status`, but a 429 becomes `RateLimited` with the wait
+/// from its `Retry-After` header.
+pub fn check_status(response: Response) -> Result<Response, anyhow::Error> {
+    if response.status() == StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse().ok())
+            .unwrap_or(DEFAULT_RETRY_AFTER);
+        return Err(RateLimited { retry_after }.into());
+    }
+    Ok(response.error_for_status()?)
+}
+
+impl From<RError> for ApiError {
+    fn from(err: RError) -> ApiError {
+        ApiError {
+            message: err.to_string(),
+            offline: err.is_connect() || err.is_timeout(),
+            retry_after: None,
+        }
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(err: anyhow::Error) -> ApiError {
+        if let Some(RateLimited { retry_after }) = err.downcast_ref::<RateLimited>() {
+            return ApiError {
+                message: "Rate limited by Todoist".to_owned(),
+                offline: false,
+                retry_after: Some(*retry_after),
+            };
+        }
+        match err.downcast::<RError>() {
+            Ok(err) => err.into(),
+            Err(err) => ApiError {
+                message: err.to_string(),
+                offline: false,
+                retry_after: None,
+            },
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
 #[derive(Deserialize, Debug, Serialize, Clone)]
 pub struct Project {
     pub id: String,
@@ -19,10 +104,20 @@ pub struct Project {
     pub url: String,
 }
 
+const TEMP_ID_PREFIX: &str = "temp-";
+
+/// A local id for a task or project until Todoist has given it one, unique
+/// within a run.
+fn temp_id() -> String {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    format!("{}{}", TEMP_ID_PREFIX, COUNTER.fetch_add(1, Ordering::Relaxed))
+}
+
 impl Project {
+    /// A project that is not on the server yet, with a temporary id.
     pub fn name(name: &str) -> Project {
         Project {
-            id: "".to_owned(),
+            id: temp_id(),
             name: name.to_owned(),
             comment_count: 0,
             order: 0,
@@ -36,6 +131,29 @@ impl Project {
             url: String::new(),
         }
     }
+
+    /// Whether the project is still being created, so there is nothing to
+    /// change on the server yet.
+    pub fn is_temp(&self) -> bool {
+        self.id.starts_with(TEMP_ID_PREFIX)
+    }
+}
+
+#[derive(Deserialize, Debug, Serialize, Clone)]
+pub struct Section {
+    pub id: String,
+    pub project_id: String,
+    pub order: usize,
+    pub name: String,
+}
+
+#[derive(Deserialize, Debug, Serialize, Clone)]
+pub struct Label {
+    pub id: String,
+    pub name: String,
+    pub color: String,
+    pub order: usize,
+    pub is_favorite: bool,
 }
 
 #[derive(Deserialize, Debug, Serialize, Clone)]
@@ -47,6 +165,26 @@ pub struct Due {
     pub string: Option<String>,
 }
 
+#[derive(Deserialize, Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DurationUnit {
+    Minute,
+    Day,
+}
+
+/// How long a task takes, counted in either minutes or days.
+#[derive(Deserialize, Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+pub struct TaskDuration {
+    pub amount: u32,
+    pub unit: DurationUnit,
+}
+
+/// The date a task has to be done by, unlike the due date when it is
+/// planned.
+#[derive(Deserialize, Debug, Serialize, Clone)]
+pub struct Deadline {
+    pub date: String,
+}
 
 #[derive(Deserialize, Debug, Serialize, Clone)]
 pub struct Task {
@@ -67,26 +205,41 @@ pub struct Task {
     pub section_id: Option<String>,
     pub parent_id: Option<String>,
     pub due_string: Option<String>,
+    pub duration: Option<TaskDuration>,
+    pub deadline: Option<Deadline>,
     pub url: String,
 }
 
 impl Task {
+    /// Whether the task is still being created, so there is nothing to
+    /// change on the server yet.
+    pub fn is_temp(&self) -> bool {
+        self.id.starts_with(TEMP_ID_PREFIX)
+    }
+
+    /// A task that is not on the server yet, with a temporary id.
     pub fn temp(task_content: TaskContent, project_id: String) -> Task {
         let labels: Vec<String> = task_content
             .labels
             .replace(' ', "")
             .split(',')
+            .filter(|s| !s.is_empty())
             .map(|s| s.to_owned())
             .collect();
 
-        let mut due = Due {
-                date: Default::default(),
-                is_recurring: Default::default(),
-                datetime: Default::default(),
-                timezone: Default::default(),
-                string: Default::default(),
-            };
-        due.datetime = Some(task_content.due_string.to_owned());
+        // the API parses the due string, until then only the string is known
+        let due = Some(Due {
+            date: None,
+            is_recurring: None,
+            datetime: None,
+            timezone: None,
+            string: Some(task_content.due_string.clone()),
+        })
+        .filter(|_| !task_content.due_string.is_empty());
+        let deadline = Some(Deadline {
+            date: task_content.deadline.clone(),
+        })
+        .filter(|_| !task_content.deadline.is_empty());
 
         Task {
             creator_id: String::new(),
@@ -97,15 +250,17 @@ impl Task {
             is_completed: false,
             content: task_content.content,
             description: task_content.description,
-            id: String::new(),
+            id: temp_id(),
             labels,
-            due: Some(due),
+            due,
             order: 0,
-            priority: task_content.priority.parse::<usize>().unwrap(),
+            priority: task_content.priority,
             project_id,
             section_id: None,
             parent_id: None,
             due_string: Some(task_content.due_string),
+            duration: parse_duration(&task_content.duration),
+            deadline,
             url: String::new(),
         }
     }
@@ -114,6 +269,46 @@ impl Task {
 #[derive(Default, Deserialize, Debug, Serialize, Clone)]
 pub struct PostProject {
     pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PostSection {
+    pub name: String,
+    pub project_id: String,
+}
+
+/// Fields of `POST /projects/{id}`, only the ones that are set get sent.
+#[derive(Default, Debug, Serialize, Clone)]
+pub struct ProjectUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub is_favorite: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub view_style: Option<String>,
+}
+
+/// Fields of `POST /tasks/{id}`, only the ones that are set get sent.
+#[derive(Default, Debug, Serialize, Clone)]
+pub struct TaskUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labels: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub priority: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due_string: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due_date: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due_datetime: Option<String>,
 }
 
 #[derive(Deserialize, Debug, Serialize, Clone)]
@@ -121,8 +316,15 @@ pub struct TaskContent {
     pub content: String,
     pub description: String,
     pub labels: String,
-    pub priority: String,
+    /// As the API counts it, see `priority::shown_priority`.
+    pub priority: usize,
     pub due_string: String,
+    /// A `YYYY-MM-DD` date, the form resolves what was typed on submit.
+    #[serde(default)]
+    pub deadline: String,
+    /// As typed, like `30m`, `1h30m` or `2d`.
+    #[serde(default)]
+    pub duration: String,
 }
 
 impl Default for TaskContent {
@@ -131,60 +333,90 @@ impl Default for TaskContent {
             content: String::new(),
             description: String::new(),
             labels: String::new(),
-            priority: String::from("1"),
+            priority: DEFAULT_PRIORITY,
             due_string: String::new(),
+            deadline: String::new(),
+            duration: String::new(),
         }
     }
 }
 
 #[allow(dead_code)]
-pub async fn get_projects(token: String) -> Result<Vec<Project>, RError> {
+pub async fn get_projects(token: String) -> Result<Vec<Project>, anyhow::Error> {
     let url = format!("Bearer {}", token);
     let client = reqwest::Client::new();
     let response = client
         .get("https://api.todoist.com/rest/v2/projects")
         .header(AUTHORIZATION, url)
         .send()
-        .await
-        .unwrap();
+        .await?;
+    let response = check_status(response)?;
     let projects: Vec<Project> = response.json().await?;
 
     Ok(projects)
 }
 
+pub async fn get_sections(token: String) -> Result<Vec<Section>, anyhow::Error> {
+    let url = format!("Bearer {}", token);
+    let client = reqwest::Client::new();
+    let response = client
+        .get("https://api.todoist.com/rest/v2/sections")
+        .header(AUTHORIZATION, url)
+        .send()
+        .await?;
+    let response = check_status(response)?;
+    let sections: Vec<Section> = response.json().await?;
+
+    Ok(sections)
+}
+
+pub async fn get_labels(token: String) -> Result<Vec<Label>, anyhow::Error> {
+    let url = format!("Bearer {}", token);
+    let client = reqwest::Client::new();
+    let response = client
+        .get("https://api.todoist.com/rest/v2/labels")
+        .header(AUTHORIZATION, url)
+        .send()
+        .await?;
+    let response = check_status(response)?;
+    let labels: Vec<Label> = response.json().await?;
+
+    Ok(labels)
+}
+
 #[allow(dead_code)]
-pub async fn delete_project(token: String, project_id: String) -> Result<(), RError> {
+pub async fn delete_project(token: String, project_id: String) -> Result<(), anyhow::Error> {
     let url = format!("Bearer {}", token);
     let client = reqwest::Client::new();
-    let _ = client
+    let response = client
         .delete(format!(
             "https://api.todoist.com/rest/v2/projects/{}",
             project_id
         ))
         .header(AUTHORIZATION, url)
         .send()
-        .await
-        .unwrap();
+        .await?;
+    check_status(response)?;
 
     Ok(())
 }
 
 #[allow(dead_code)]
-pub async fn delete_task(token: String, task_id: String) -> Result<(), RError> {
+pub async fn delete_task(token: String, task_id: String) -> Result<(), anyhow::Error> {
     let url = format!("Bearer {}", token);
     let client = reqwest::Client::new();
-    let _ = client
+    let response = client
         .delete(format!("https://api.todoist.com/rest/v2/tasks/{}", task_id))
         .header(AUTHORIZATION, url)
         .send()
-        .await
-        .unwrap();
+        .await?;
+    check_status(response)?;
 
     Ok(())
 }
 
 #[allow(dead_code)]
-pub async fn post_projects(token: String, project: PostProject) -> Result<Project, RError> {
+pub async fn post_projects(token: String, project: PostProject) -> Result<Project, anyhow::Error> {
     let autherization = format!("Bearer {}", token);
     let client = reqwest::Client::new();
     let response = client
@@ -193,41 +425,149 @@ pub async fn post_projects(token: String, project: PostProject) -> Result<Projec
         .header(AUTHORIZATION, autherization)
         .json(&project)
         .send()
-        .await
-        .unwrap();
+        .await?;
+    let response = check_status(response)?;
     let project: Project = response.json().await?;
 
     Ok(project)
 }
 
+pub async fn post_section(token: String, section: PostSection) -> Result<Section, anyhow::Error> {
+    let url = format!("Bearer {}", token);
+    let client = reqwest::Client::new();
+    let response = client
+        .post("https://api.todoist.com/rest/v2/sections")
+        .header(CONTENT_TYPE, "application/json")
+        .header(AUTHORIZATION, url)
+        .json(&section)
+        .send()
+        .await?;
+    let response = check_status(response)?;
+    let section: Section = response.json().await?;
+
+    Ok(section)
+}
+
 #[allow(dead_code)]
-pub async fn get_tasks(token: String) -> Result<Vec<Task>, RError> {
+pub async fn get_tasks(token: String) -> Result<Vec<Task>, anyhow::Error> {
     let url = format!("Bearer {}", token);
     let client = reqwest::Client::new();
     let response = client
         .get("https://api.todoist.com/rest/v2/tasks")
         .header(AUTHORIZATION, url)
         .send()
-        .await
-        .unwrap();
+        .await?;
+    let response = check_status(response)?;
     let tasks: Vec<Task> = response.json().await?;
 
     Ok(tasks)
 }
 
+/// The body of `POST /tasks`, which takes the duration and deadline as
+/// plain fields rather than the objects tasks come back with.
+#[derive(Serialize)]
+struct PostTask<'a> {
+    content: &'a str,
+    description: &'a str,
+    labels: &'a [String],
+    priority: usize,
+    project_id: &'a str,
+    section_id: Option<&'a str>,
+    parent_id: Option<&'a str>,
+    assignee_id: Option<&'a str>,
+    due_string: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_unit: Option<DurationUnit>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deadline_date: Option<&'a str>,
+}
+
+impl<'a> From<&'a Task> for PostTask<'a> {
+    fn from(task: &'a Task) -> PostTask<'a> {
+        PostTask {
+            content: &task.content,
+            description: &task.description,
+            labels: &task.labels,
+            priority: task.priority,
+            project_id: &task.project_id,
+            section_id: task.section_id.as_deref(),
+            parent_id: task.parent_id.as_deref(),
+            assignee_id: task.assignee_id.as_deref(),
+            due_string: task.due_string.as_deref(),
+            duration: task.duration.map(|duration| duration.amount),
+            duration_unit: task.duration.map(|duration| duration.unit),
+            deadline_date: task.deadline.as_ref().map(|deadline| deadline.date.as_str()),
+        }
+    }
+}
+
 #[allow(dead_code)]
-pub async fn post_task(token: String, task: Task) -> Result<Task, RError> {
+pub async fn post_task(token: String, task: Task) -> Result<Task, anyhow::Error> {
     let autherization = format!("Bearer {}", token);
     let client = reqwest::Client::new();
     let response = client
         .post("https://api.todoist.com/rest/v2/tasks")
         .header(CONTENT_TYPE, "application/json")
         .header(AUTHORIZATION, autherization)
-        .json(&task)
+        .json(&PostTask::from(&task))
         .send()
-        .await
-        .unwrap();
+        .await?;
+    let response = check_status(response)?;
     let tasks: Task = response.json().await?;
 
     Ok(tasks)
 }
+
+pub async fn update_project(
+    token: String,
+    project_id: String,
+    update: ProjectUpdate,
+) -> Result<Project, anyhow::Error> {
+    let autherization = format!("Bearer {}", token);
+    let client = reqwest::Client::new();
+    let response = client
+        .post(format!(
+            "https://api.todoist.com/rest/v2/projects/{}",
+            project_id
+        ))
+        .header(CONTENT_TYPE, "application/json")
+        .header(AUTHORIZATION, autherization)
+        .json(&update)
+        .send()
+        .await?;
+    let response = check_status(response)?;
+    let project: Project = response.json().await?;
+
+    Ok(project)
+}
+
+pub async fn update_task(token: String, task_id: String, update: TaskUpdate) -> Result<Task, anyhow::Error> {
+    let autherization = format!("Bearer {}", token);
+    let client = reqwest::Client::new();
+    let response = client
+        .post(format!("https://api.todoist.com/rest/v2/tasks/{}", task_id))
+        .header(CONTENT_TYPE, "application/json")
+        .header(AUTHORIZATION, autherization)
+        .json(&update)
+        .send()
+        .await?;
+    let response = check_status(response)?;
+    let task: Task = response.json().await?;
+
+    Ok(task)
+}
+
+pub async fn close_task(token: String, task_id: String) -> Result<(), anyhow::Error> {
+    let url = format!("Bearer {}", token);
+    let client = reqwest::Client::new();
+    let response = client
+        .post(format!("https://api.todoist.com/rest/v2/tasks/{}/close", task_id))
+        .header(AUTHORIZATION, url)
+        .send()
+        .await?;
+    check_status(response)?;
+
+    Ok(())
+}
@@ -0,0 +1,1881 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use tui::style::Color;
+
+use crate::{
+    api::{ApiError, Label, PostProject, Project, ProjectUpdate, Section, Task},
+    board::{board_columns, board_position},
+    calendar::Calendar,
+    config::Config,
+    database::Database,
+    date::DateConfig,
+    export::DEFAULT_EXPORT_DIR,
+    due_parser::{parse_deadline, parse_due_string, parse_duration},
+    history::{Change, History, TaskEdit},
+    import::{ImportPlan, ImportProgress, ImportState, ImportStatus},
+    key_events::help_entries,
+    input::{push_char_to_field, remove_char_from_field},
+    menu::{cleanup, MenuItem},
+    mouse::ClickMap,
+    navigation::{
+        change_active_add_task_input_field, focus_add_task_input_field, navigate_down_projects,
+        navigate_down_tasks, navigate_up_projects, navigate_up_tasks,
+    },
+    picker::Picker,
+    priority::{api_priority, priority_choices},
+    project::{ProjectItem, ProjectStatus, PROJECT_COLORS},
+    sort::{sort_projects, sort_tasks, sorted_project_tasks, SortMode},
+    status::SyncState,
+    sync::{ArchivedProject, Placement, SyncCommand, User},
+    task::{AddTaskHighlight, TaskItem, TaskStatus},
+};
+
+/// Everything that can change the state of the TUI, either a key press or
+/// the result of an effect.
+#[derive(Debug)]
+pub enum Action {
+    Quit,
+    /// Fetches everything again, unless requests are still on their way.
+    Refresh,
+    MenuLeft,
+    MenuRight,
+    SelectNext,
+    SelectPrevious,
+    OpenMenu(MenuItem),
+    ClickProject(String),
+    ClickTask(String),
+    FocusField(TaskItem),
+    OpenAddTask,
+    OpenAddProject,
+    OpenAddChildProject,
+    OpenRenameProject,
+    CloseForm,
+    InputChar(char),
+    InputPaste(String),
+    InputBackspace,
+    NextField,
+    PreviousField,
+    Submit,
+    DeleteSelected,
+    CloseSelected,
+    MoveSelectedUp,
+    MoveSelectedDown,
+    /// Moves the targeted cards of a board to the next or previous section.
+    MoveCardNext,
+    MoveCardPrevious,
+    CycleSortMode,
+    ToggleMark,
+    ToggleVisual,
+    ClearMarks,
+    /// Sets the priority as shown, where 1 is the highest.
+    SetPriority(usize),
+    OpenMovePicker,
+    OpenLabelPicker { add: bool },
+    OpenReschedulePicker,
+    OpenPriorityPicker,
+    OpenCalendar,
+    CalendarDays(i64),
+    CalendarMonths(i32),
+    CalendarConfirm,
+    /// Sets the due string of the add task form and closes the calendar.
+    CalendarQuick(&'static str),
+    CloseCalendar,
+    OpenColorPicker,
+    ToggleFavorite,
+    ToggleViewStyle,
+    ArchiveSelected,
+    OpenUnarchivePicker,
+    OpenHelp,
+    CloseHelp,
+    HelpChar(char),
+    HelpBackspace,
+    HelpScrollDown,
+    HelpScrollUp,
+    PickerChar(char),
+    PickerBackspace,
+    PickerNext,
+    PickerPrevious,
+    PickerConfirm,
+    PickerCancel,
+    Undo,
+    Redo,
+    ConfirmYes,
+    ConfirmNo,
+    DismissToast,
+    Export,
+    StartImport,
+    ScrollImportDown,
+    ScrollImportUp,
+    CloseImport,
+    ProjectsLoaded(Result<Vec<Project>, ApiError>),
+    TasksLoaded(Result<Vec<Task>, ApiError>),
+    SectionsLoaded(Result<Vec<Section>, ApiError>),
+    LabelsLoaded(Result<Vec<Label>, ApiError>),
+    TaskPosted(Result<Box<Task>, ApiError>),
+    ProjectPosted(Result<Project, ApiError>),
+    TasksSynced(Result<(), ApiError>),
+    Reordered(Result<(), ApiError>),
+    TaskRecreated {
+        old_id: String,
+        result: Result<Box<Task>, ApiError>,
+    },
+    ProjectDeleted(Result<(), ApiError>),
+    ProjectUpdated(Result<Project, ApiError>),
+    ProjectsSynced(Result<(), ApiError>),
+    ArchivedProjectsLoaded(Result<Vec<ArchivedProject>, ApiError>),
+    UserLoaded(Result<User, ApiError>),
+    ImportProgressed(ImportProgress),
+    /// The files an export wrote, or why it failed.
+    Exported(Result<Vec<PathBuf>, String>),
+    /// Whether the calendar file of `ics_path` could be written.
+    CalendarSynced(Result<(), String>),
+    /// Todoist rate limited a request, which is retried after this many seconds.
+    RateLimited(u64),
+}
+
+/// Side effects requested by the reducer, run by `effects::run_effect`.
+#[derive(Debug)]
+pub enum Effect {
+    FetchProjects,
+    FetchTasks,
+    FetchSections,
+    FetchLabels,
+    PostTask(Box<Task>),
+    PostProject(PostProject),
+    RecreateTask(Box<Task>),
+    /// Task commands sent together in one Sync API request.
+    SyncTasks(Vec<SyncCommand>),
+    ReorderTasks(Vec<(String, usize)>),
+    ReorderProjects(Vec<(String, usize)>),
+    DeleteProject(String),
+    UpdateProject(String, ProjectUpdate),
+    /// Project commands sent together in one Sync API request.
+    SyncProjects(Vec<SyncCommand>),
+    FetchArchivedProjects,
+    FetchUser,
+    Export(Box<Database>),
+    SyncCalendar(Vec<Task>),
+    RunImport(ImportPlan, ImportState),
+}
+
+impl Action {
+    /// How the request behind a result action went, None for other actions.
+    fn response(&self) -> Option<Result<(), &ApiError>> {
+        fn outcome<T>(result: &Result<T, ApiError>) -> Option<Result<(), &ApiError>> {
+            Some(result.as_ref().map(|_| ()))
+        }
+        match self {
+            Action::ProjectsLoaded(result) => outcome(result),
+            Action::TasksLoaded(result) => outcome(result),
+            Action::SectionsLoaded(result) => outcome(result),
+            Action::LabelsLoaded(result) => outcome(result),
+            Action::TaskPosted(result) => outcome(result),
+            Action::ProjectPosted(result) => outcome(result),
+            Action::TasksSynced(result) => outcome(result),
+            Action::Reordered(result) => outcome(result),
+            Action::TaskRecreated { result, .. } => outcome(result),
+            Action::ProjectDeleted(result) => outcome(result),
+            Action::ProjectUpdated(result) => outcome(result),
+            Action::ProjectsSynced(result) => outcome(result),
+            Action::ArchivedProjectsLoaded(result) => outcome(result),
+            Action::UserLoaded(result) => outcome(result),
+            _ => None,
+        }
+    }
+}
+
+impl Effect {
+    /// Whether the effect sends a request whose result comes back as an
+    /// action, see `Action::response`.
+    fn is_request(&self) -> bool {
+        !matches!(
+            self,
+            Effect::Export(_) | Effect::SyncCalendar(_) | Effect::RunImport(..)
+        )
+    }
+}
+
+const TOAST_DURATION: Duration = Duration::from_secs(5);
+
+/// A short message in the corner of the screen that hides itself.
+pub struct Toast {
+    pub message: String,
+    pub shown_at: Instant,
+}
+
+/// Questions that need a yes or no before anything happens.
+pub enum Confirm {
+    DeleteProject {
+        id: String,
+        name: String,
+        task_count: usize,
+    },
+}
+
+/// The searchable list of key bindings shown over the current screen.
+#[derive(Default)]
+pub struct Help {
+    pub query: String,
+    pub offset: usize,
+}
+
+/// Pickers that open over the lists and act on the targeted tasks or the
+/// selected project.
+pub enum Popup {
+    Move(Picker<Placement>),
+    AddLabel(Picker<String>),
+    RemoveLabel(Picker<String>),
+    Reschedule(Picker<String>),
+    ProjectColor(Picker<String>),
+    /// Choices are API priorities, for the targeted tasks or the add task
+    /// form.
+    Priority(Picker<usize>),
+    /// Choices are the ids of archived projects.
+    Unarchive(Picker<String>),
+}
+
+impl Popup {
+    fn push(&mut self, c: char) {
+        match self {
+            Popup::Move(picker) => picker.push(c),
+            Popup::Priority(picker) => picker.push(c),
+            Popup::AddLabel(picker)
+            | Popup::RemoveLabel(picker)
+            | Popup::Reschedule(picker)
+            | Popup::ProjectColor(picker)
+            | Popup::Unarchive(picker) => {
+                picker.push(c)
+            }
+        }
+    }
+
+    fn pop(&mut self) {
+        match self {
+            Popup::Move(picker) => picker.pop(),
+            Popup::Priority(picker) => picker.pop(),
+            Popup::AddLabel(picker)
+            | Popup::RemoveLabel(picker)
+            | Popup::Reschedule(picker)
+            | Popup::ProjectColor(picker)
+            | Popup::Unarchive(picker) => {
+                picker.pop()
+            }
+        }
+    }
+
+    fn next(&mut self) {
+        match self {
+            Popup::Move(picker) => picker.next(),
+            Popup::Priority(picker) => picker.next(),
+            Popup::AddLabel(picker)
+            | Popup::RemoveLabel(picker)
+            | Popup::Reschedule(picker)
+            | Popup::ProjectColor(picker)
+            | Popup::Unarchive(picker) => {
+                picker.next()
+            }
+        }
+    }
+
+    fn previous(&mut self) {
+        match self {
+            Popup::Move(picker) => picker.previous(),
+            Popup::Priority(picker) => picker.previous(),
+            Popup::AddLabel(picker)
+            | Popup::RemoveLabel(picker)
+            | Popup::Reschedule(picker)
+            | Popup::ProjectColor(picker)
+            | Popup::Unarchive(picker) => {
+                picker.previous()
+            }
+        }
+    }
+}
+
+/// Due strings offered by the reschedule picker; empty removes the date.
+const RESCHEDULE_CHOICES: [(&str, &str); 6] = [
+    ("Today", "today"),
+    ("Tomorrow", "tomorrow"),
+    ("Next week", "next week"),
+    ("Next weekend", "next weekend"),
+    ("Next month", "next month"),
+    ("No date", ""),
+];
+
+pub struct App {
+    pub active_menu_item: MenuItem,
+    pub task_status: TaskStatus,
+    pub project_status: ProjectStatus,
+    pub database: Database,
+    /// Selection is tracked by id; the table states are derived from it.
+    pub selected_project_id: Option<String>,
+    pub selected_task_id: Option<String>,
+    pub import_status: Option<ImportStatus>,
+    pub history: History,
+    /// Subtasks undo is creating again, waiting for the new id of their
+    /// parent.
+    pub awaiting_parent: Vec<Task>,
+    pub toast: Option<Toast>,
+    pub confirm: Option<Confirm>,
+    pub popup: Option<Popup>,
+    pub help: Option<Help>,
+    pub calendar: Option<Calendar>,
+    /// Ids of the tasks marked for a bulk action.
+    pub marked: HashSet<String>,
+    /// Where a range selection started, when one is in progress.
+    pub visual_anchor: Option<String>,
+    /// What the last frame drew where, for mouse clicks.
+    pub click_map: ClickMap,
+    pub sort_modes: HashMap<String, SortMode>,
+    pub color: Color,
+    pub dates: DateConfig,
+    /// Requests sent that have not come back yet.
+    pub pending: usize,
+    /// A refresh asked for while requests were pending, run once they are done.
+    pub refresh_queued: bool,
+    /// When a request last succeeded, shown in `dates.zone`.
+    pub last_sync: Option<DateTime<Utc>>,
+    /// Why the last request failed, cleared by the next one that succeeds.
+    pub error: Option<ApiError>,
+    pub user: Option<User>,
+    pub should_quit: bool,
+}
+
+impl App {
+    pub fn new(config: &Config, import_status: Option<ImportStatus>) -> App {
+        let mut task_status = TaskStatus::default();
+        task_status.task_table_state.select(Some(0));
+        let mut project_status = ProjectStatus::default();
+        project_status.project_table_state.select(Some(0));
+
+        App {
+            active_menu_item: match import_status {
+                Some(_) => MenuItem::Import,
+                None => MenuItem::Home,
+            },
+            task_status,
+            project_status,
+            database: Database::default(),
+            selected_project_id: None,
+            selected_task_id: None,
+            import_status,
+            history: History::default(),
+            awaiting_parent: vec![],
+            toast: None,
+            confirm: None,
+            popup: None,
+            help: None,
+            calendar: None,
+            marked: HashSet::new(),
+            visual_anchor: None,
+            click_map: ClickMap::default(),
+            sort_modes: HashMap::new(),
+            color: config.color,
+            dates: config.dates.clone(),
+            pending: 0,
+            refresh_queued: false,
+            last_sync: None,
+            error: None,
+            user: None,
+            should_quit: false,
+        }
+    }
+
+    /// Effects that load the database when the TUI starts.
+    pub fn init(&mut self) -> Vec<Effect> {
+        let effects = vec![
+            Effect::FetchProjects,
+            Effect::FetchTasks,
+            Effect::FetchSections,
+            Effect::FetchLabels,
+            Effect::FetchUser,
+        ];
+        self.pending += effects.len();
+        effects
+    }
+
+    /// The selected project, or the first one if it is gone.
+    pub fn selected_project(&self) -> Option<&Project> {
+        self.selected_project_id
+            .as_deref()
+            .and_then(|id| self.database.project(id))
+            .or_else(|| self.database.projects().first())
+    }
+
+    fn selected_project_position(&self) -> Option<usize> {
+        self.selected_project()
+            .and_then(|project| self.database.project_position(&project.id))
+    }
+
+    fn selected_task_position(&self) -> Option<usize> {
+        let id = self.selected_task_id.as_deref()?;
+        self.selected_project_tasks()
+            .iter()
+            .position(|task| task.id == id)
+    }
+
+    /// The selected task of the selected project, or its first task.
+    pub fn selected_task(&self) -> Option<&Task> {
+        let position = self.selected_task_position().unwrap_or(0);
+        self.selected_project_tasks().get(position).copied()
+    }
+
+    fn select_project_at(&mut self, position: usize) {
+        let projects = self.database.projects();
+        let position = position.min(projects.len().saturating_sub(1));
+        self.selected_project_id = projects.get(position).map(|project| project.id.clone());
+    }
+
+    fn select_task_at(&mut self, position: usize) {
+        let count = self.selected_project_task_count();
+        let position = position.min(count.saturating_sub(1));
+        let id = self
+            .selected_project_tasks()
+            .get(position)
+            .map(|task| task.id.clone());
+        self.selected_task_id = id;
+    }
+
+    /// Points the table states at the selected ids.
+    fn sync_table_states(&mut self) {
+        let project_position = self.selected_project_position();
+        let task_position = self.selected_task_position();
+        self.project_status
+            .project_table_state
+            .select(project_position.or(Some(0)));
+        self.task_status
+            .task_table_state
+            .select(task_position.or(Some(0)));
+    }
+
+    /// The selected project when it can be edited, which the inbox and
+    /// projects that are still being created cannot.
+    fn selected_user_project(&self) -> Option<Project> {
+        self.selected_project()
+            .filter(|project| !project.is_inbox_project && !project.is_temp())
+            .cloned()
+    }
+
+    /// Whether the selected project shows its sections as board columns.
+    pub fn is_board(&self) -> bool {
+        self.selected_project()
+            .is_some_and(|project| project.view_style == "board")
+    }
+
+    /// Selects the card at about the same row in the nearest column with
+    /// cards to the left or right. Returns false when there is none.
+    fn select_board_column(&mut self, right: bool) -> bool {
+        let tasks = self.selected_project_tasks();
+        let columns = board_columns(&self.database, self.selected_project_id(), &tasks);
+        let position = self
+            .selected_task()
+            .and_then(|task| board_position(&columns, &task.id));
+        let (column, row) = match position {
+            Some(position) => position,
+            None => return false,
+        };
+        let target = if right {
+            columns[column + 1..].iter().find(|c| !c.tasks.is_empty())
+        } else {
+            columns[..column].iter().rev().find(|c| !c.tasks.is_empty())
+        };
+        let id = target.map(|c| c.tasks[row.min(c.tasks.len() - 1)].id.clone());
+        match id {
+            Some(id) => {
+                self.selected_task_id = Some(id);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Moves the selection down or up within its board column, wrapping
+    /// around like the task table.
+    fn select_in_board_column(&mut self, down: bool) {
+        let tasks = self.selected_project_tasks();
+        let columns = board_columns(&self.database, self.selected_project_id(), &tasks);
+        let position = self
+            .selected_task()
+            .and_then(|task| board_position(&columns, &task.id));
+        let id = position.map(|(column, row)| {
+            let cards = &columns[column].tasks;
+            let row = if down {
+                (row + 1) % cards.len()
+            } else {
+                (row + cards.len() - 1) % cards.len()
+            };
+            cards[row].id.clone()
+        });
+        if id.is_some() {
+            self.selected_task_id = id;
+        }
+    }
+
+    /// Moves the targeted cards into the section of the next or previous
+    /// board column.
+    fn move_to_adjacent_section(&mut self, right: bool) -> Vec<Effect> {
+        let project_id = self.selected_project_id().to_owned();
+        let section_id = {
+            let tasks = self.selected_project_tasks();
+            let columns = board_columns(&self.database, &project_id, &tasks);
+            let position = self
+                .selected_task()
+                .and_then(|task| board_position(&columns, &task.id));
+            let target = match (position, right) {
+                (Some((column, _)), true) => columns.get(column + 1),
+                (Some((column, _)), false) => column.checked_sub(1).and_then(|c| columns.get(c)),
+                (None, _) => None,
+            };
+            match target {
+                Some(target) => target.section_id.clone(),
+                None => return vec![],
+            }
+        };
+        let tasks = self.outermost(self.target_tasks());
+        if tasks.is_empty() {
+            return vec![];
+        }
+        let to = Placement {
+            project_id,
+            section_id,
+            parent_id: None,
+        };
+        self.change_tasks(Change::MovedTasks { tasks, to })
+    }
+
+    /// Changes a project locally and keeps the list sorted.
+    fn edit_project(&mut self, id: &str, edit: impl FnOnce(&mut Project)) {
+        let mut projects = self.database.projects().to_vec();
+        if let Some(project) = projects.iter_mut().find(|project| project.id == id) {
+            edit(project);
+        }
+        sort_projects(&mut projects);
+        self.database.set_projects(projects);
+    }
+
+    fn selected_project_id(&self) -> &str {
+        self.selected_project()
+            .map_or("", |project| project.id.as_str())
+    }
+
+    /// Tasks of the selected project, in the order they are rendered.
+    pub fn selected_project_tasks(&self) -> Vec<&Task> {
+        let project_id = self.selected_project_id();
+        sorted_project_tasks(&self.database, project_id, self.sort_mode(project_id))
+    }
+
+    pub fn sort_mode(&self, project_id: &str) -> SortMode {
+        self.sort_modes.get(project_id).copied().unwrap_or_default()
+    }
+
+    pub fn selected_project_task_count(&self) -> usize {
+        self.database.project_task_count(self.selected_project_id())
+    }
+
+    /// Ids of the marked tasks, including the range being selected.
+    pub fn marked_task_ids(&self) -> HashSet<String> {
+        let mut marked = self.marked.clone();
+        let anchor = self.visual_anchor.as_deref();
+        let tasks = self.selected_project_tasks();
+        let anchor = anchor.and_then(|id| tasks.iter().position(|task| task.id == id));
+        if let (Some(anchor), Some(cursor)) = (anchor, self.selected_task_position()) {
+            let range = anchor.min(cursor)..=anchor.max(cursor);
+            marked.extend(tasks[range].iter().map(|task| task.id.clone()));
+        }
+        marked
+    }
+
+    /// The marked tasks of the selected project, or else the selected task.
+    /// Tasks still being created are left out until they have their id.
+    fn target_tasks(&self) -> Vec<Task> {
+        let marked = self.marked_task_ids();
+        let tasks = self.selected_project_tasks();
+        let targets: Vec<Task> = tasks
+            .iter()
+            .filter(|task| marked.contains(&task.id))
+            .map(|&task| task.clone())
+            .collect();
+        let targets = if targets.is_empty() {
+            self.selected_task().cloned().into_iter().collect()
+        } else {
+            targets
+        };
+        targets.into_iter().filter(|task| !task.is_temp()).collect()
+    }
+
+    /// Drops tasks below another one of `tasks`, as they follow their
+    /// parent when it is deleted, closed or moved.
+    fn outermost(&self, tasks: Vec<Task>) -> Vec<Task> {
+        let ids: HashSet<String> = tasks.iter().map(|task| task.id.clone()).collect();
+        tasks
+            .into_iter()
+            .filter(|task| {
+                let mut parent = task.parent_id.clone();
+                while let Some(id) = parent {
+                    if ids.contains(&id) {
+                        return false;
+                    }
+                    parent = self.database.task(&id).and_then(|t| t.parent_id.clone());
+                }
+                true
+            })
+            .collect()
+    }
+
+    /// `tasks` each followed by their subtasks, depth first, as deleting or
+    /// completing a task takes its subtasks along on the server.
+    fn with_subtasks(&self, tasks: Vec<Task>) -> Vec<Task> {
+        let mut all = vec![];
+        let mut stack: Vec<Task> = tasks.into_iter().rev().collect();
+        while let Some(task) = stack.pop() {
+            let subtasks: Vec<Task> = self.database.subtasks(&task.id).cloned().collect();
+            stack.extend(subtasks.into_iter().rev());
+            all.push(task);
+        }
+        all
+    }
+
+    fn clear_marks(&mut self) {
+        self.marked.clear();
+        self.visual_anchor = None;
+    }
+
+    pub fn toast_expired(&self, now: Instant) -> bool {
+        self.toast
+            .as_ref()
+            .is_some_and(|toast| now.duration_since(toast.shown_at) >= TOAST_DURATION)
+    }
+
+    fn show_toast(&mut self, message: impl Into<String>) {
+        self.toast = Some(Toast {
+            message: message.into(),
+            shown_at: Instant::now(),
+        });
+    }
+
+    fn editing_priority(&self) -> bool {
+        self.active_menu_item == MenuItem::AddTask
+            && self.task_status.active_task_item == TaskItem::Prio
+    }
+
+    fn in_form(&self) -> bool {
+        matches!(
+            self.active_menu_item,
+            MenuItem::AddTask | MenuItem::AddProject
+        )
+    }
+
+    /// Whether the project list has the focus, which decides the pane a
+    /// collapsed layout shows.
+    pub fn sidebar_focused(&self) -> bool {
+        matches!(
+            self.active_menu_item,
+            MenuItem::Projects | MenuItem::AddProject
+        )
+    }
+
+    pub fn in_lists(&self) -> bool {
+        matches!(self.active_menu_item, MenuItem::Projects | MenuItem::Tasks)
+    }
+
+    /// Applies `action` to the state and returns the effects it asks for.
+    pub fn update(&mut self, action: Action) -> Vec<Effect> {
+        match action.response() {
+            Some(Ok(())) => {
+                self.pending = self.pending.saturating_sub(1);
+                self.last_sync = Some(Utc::now());
+                self.error = None;
+            }
+            Some(Err(err)) => {
+                self.pending = self.pending.saturating_sub(1);
+                self.error = Some(err.clone());
+            }
+            None => {}
+        }
+        let mut effects = self.reduce(action);
+        self.pending += effects.iter().filter(|effect| effect.is_request()).count();
+        self.sync_table_states();
+        if self.refresh_queued && self.pending == 0 {
+            self.refresh_queued = false;
+            effects.extend(self.update(Action::Refresh));
+        }
+        effects
+    }
+
+    pub fn sync_state(&self) -> SyncState {
+        match &self.error {
+            _ if self.pending > 0 => SyncState::Syncing,
+            Some(err) if err.offline => SyncState::Offline,
+            Some(_) => SyncState::Error,
+            None => SyncState::Idle,
+        }
+    }
+
+    fn reduce(&mut self, action: Action) -> Vec<Effect> {
+        match action {
+            Action::Quit => self.should_quit = true,
+            Action::Refresh if self.pending == 0 => {
+                return vec![
+                    Effect::FetchProjects,
+                    Effect::FetchTasks,
+                    Effect::FetchSections,
+                    Effect::FetchLabels,
+                ];
+            }
+            // fetching now could be overtaken by the pending requests
+            Action::Refresh => self.refresh_queued = true,
+            Action::MenuLeft => match self.active_menu_item {
+                MenuItem::Home => self.active_menu_item = MenuItem::Projects,
+                MenuItem::Projects => {
+                    self.active_menu_item = MenuItem::Home;
+                    self.select_project_at(0);
+                }
+                MenuItem::Tasks => {
+                    // boards step through their columns before leaving
+                    let moved = self.is_board() && self.select_board_column(false);
+                    if !moved {
+                        self.clear_marks();
+                        self.active_menu_item = MenuItem::Projects;
+                    }
+                }
+                _ => {}
+            },
+            Action::MenuRight => match self.active_menu_item {
+                MenuItem::Home => self.active_menu_item = MenuItem::Projects,
+                MenuItem::Projects if self.selected_project().is_some() => {
+                    self.select_task_at(0);
+                    if self.selected_project_task_count() > 0 {
+                        self.active_menu_item = MenuItem::Tasks;
+                    }
+                }
+                MenuItem::Tasks if self.is_board() => {
+                    self.select_board_column(true);
+                }
+                MenuItem::Tasks => {
+                    self.clear_marks();
+                    self.active_menu_item = MenuItem::Projects;
+                }
+                _ => {}
+            },
+            Action::SelectNext | Action::SelectPrevious => {
+                let next = matches!(action, Action::SelectNext);
+                match self.active_menu_item {
+                    MenuItem::Projects => {
+                        let amount = self.database.projects().len();
+                        let state = &mut self.project_status.project_table_state;
+                        if next {
+                            navigate_down_projects(state, amount)
+                        } else {
+                            navigate_up_projects(state, amount)
+                        }
+                        let selected = state.selected().unwrap_or(0);
+                        self.select_project_at(selected);
+                    }
+                    MenuItem::Tasks if self.is_board() => self.select_in_board_column(next),
+                    MenuItem::Tasks => {
+                        let amount = self.selected_project_task_count();
+                        let state = &mut self.task_status.task_table_state;
+                        if next {
+                            navigate_down_tasks(state, amount)
+                        } else {
+                            navigate_up_tasks(state, amount)
+                        }
+                        let selected = state.selected().unwrap_or(0);
+                        self.select_task_at(selected);
+                    }
+                    _ => {}
+                }
+            }
+            Action::OpenMenu(item) if self.in_lists() || self.active_menu_item == MenuItem::Home => {
+                self.clear_marks();
+                match item {
+                    MenuItem::Tasks if self.selected_project_task_count() > 0 => {
+                        if self.selected_task_position().is_none() {
+                            self.select_task_at(0);
+                        }
+                        self.active_menu_item = MenuItem::Tasks;
+                    }
+                    MenuItem::Home | MenuItem::Projects => self.active_menu_item = item,
+                    _ => {}
+                }
+            }
+            Action::ClickProject(id) => {
+                if self.active_menu_item == MenuItem::Tasks {
+                    self.clear_marks();
+                }
+                self.selected_project_id = Some(id);
+                self.active_menu_item = MenuItem::Projects;
+            }
+            Action::ClickTask(id) => {
+                self.selected_task_id = Some(id);
+                self.active_menu_item = MenuItem::Tasks;
+            }
+            Action::FocusField(item) if self.active_menu_item == MenuItem::AddTask => {
+                focus_add_task_input_field(&mut self.task_status, item, self.color)
+            }
+            Action::OpenAddTask if self.in_lists() => {
+                self.active_menu_item = MenuItem::AddTask;
+                self.task_status.active_task_item = TaskItem::Name;
+                self.task_status.add_task_highlight.name = self.color;
+            }
+            Action::OpenAddProject if self.in_lists() => {
+                self.project_status.active_project_item = ProjectItem::Name;
+                self.active_menu_item = MenuItem::AddProject;
+            }
+            Action::OpenAddChildProject if self.active_menu_item == MenuItem::Projects => {
+                if let Some(project) = self.selected_project().filter(|p| !p.is_temp()) {
+                    self.project_status.project_item.parent_id = Some(project.id.clone());
+                    self.project_status.active_project_item = ProjectItem::Name;
+                    self.active_menu_item = MenuItem::AddProject;
+                }
+            }
+            Action::OpenRenameProject if self.active_menu_item == MenuItem::Projects => {
+                if let Some(project) = self.selected_user_project() {
+                    self.project_status.project_item.name = project.name;
+                    self.project_status.editing_id = Some(project.id);
+                    self.project_status.active_project_item = ProjectItem::Name;
+                    self.active_menu_item = MenuItem::AddProject;
+                }
+            }
+            Action::CloseForm if self.in_form() => cleanup(
+                &mut self.active_menu_item,
+                &mut self.task_status,
+                &mut self.project_status,
+            ),
+            // the priority is picked rather than typed, the typed character
+            // starts the search
+            Action::InputChar(c) if self.editing_priority() => {
+                let mut picker = Picker::new("Priority", priority_choices());
+                picker.push(c);
+                self.popup = Some(Popup::Priority(picker));
+            }
+            Action::OpenPriorityPicker
+                if self.editing_priority()
+                    || self.active_menu_item == MenuItem::Tasks && self.selected_task().is_some() =>
+            {
+                self.popup = Some(Popup::Priority(Picker::new("Priority", priority_choices())));
+            }
+            Action::OpenCalendar
+                if self.active_menu_item == MenuItem::AddTask
+                    && self.task_status.active_task_item == TaskItem::Due =>
+            {
+                let now = self.dates.zone.now();
+                let cursor = parse_due_string(&self.task_status.task_content.due_string, now)
+                    .map_or(now.date(), |parsed| parsed.due_at().date());
+                self.calendar = Some(Calendar::new(cursor));
+            }
+            Action::CalendarDays(days) => {
+                if let Some(calendar) = &mut self.calendar {
+                    calendar.move_days(days);
+                }
+            }
+            Action::CalendarMonths(months) => {
+                if let Some(calendar) = &mut self.calendar {
+                    calendar.move_months(months);
+                }
+            }
+            Action::CalendarConfirm => {
+                if let Some(calendar) = self.calendar.take() {
+                    self.task_status.task_content.due_string = calendar.due_string();
+                }
+            }
+            Action::CalendarQuick(due_string) if self.calendar.is_some() => {
+                self.calendar = None;
+                self.task_status.task_content.due_string = due_string.to_owned();
+            }
+            Action::CloseCalendar => self.calendar = None,
+            Action::InputChar(c) if self.in_form() => {
+                push_char_to_field(c, &mut self.task_status, &mut self.project_status)
+            }
+            Action::InputPaste(text) if self.in_form() => {
+                for c in text.chars().filter(|c| !c.is_control()) {
+                    push_char_to_field(c, &mut self.task_status, &mut self.project_status)
+                }
+            }
+            Action::InputBackspace if self.in_form() => {
+                remove_char_from_field(&mut self.task_status, &mut self.project_status)
+            }
+            Action::NextField if self.active_menu_item == MenuItem::AddTask => {
+                change_active_add_task_input_field(&mut self.task_status, 1, self.color)
+            }
+            Action::PreviousField if self.active_menu_item == MenuItem::AddTask => {
+                change_active_add_task_input_field(&mut self.task_status, -1, self.color)
+            }
+            Action::Submit if self.active_menu_item == MenuItem::AddTask => {
+                let content = &mut self.task_status.task_content;
+                let duration = content.duration.trim();
+                if !duration.is_empty() && parse_duration(duration).is_none() {
+                    self.show_toast("Duration not understood, try 30m, 1h30m or 2d");
+                    return vec![];
+                }
+                if !content.deadline.trim().is_empty() {
+                    match parse_deadline(&content.deadline, self.dates.zone.now()) {
+                        Some(date) => content.deadline = date.format("%Y-%m-%d").to_string(),
+                        None => {
+                            self.show_toast("Deadline not understood, it has to be a single day");
+                            return vec![];
+                        }
+                    }
+                }
+                if let Some(project_id) = self.selected_project().map(|p| p.id.clone()) {
+                    let task = Task::temp(
+                        std::mem::take(&mut self.task_status.task_content),
+                        project_id,
+                    );
+                    self.database.push_task(task.clone());
+                    self.task_status.add_task_highlight = AddTaskHighlight::default();
+                    self.task_status.active_task_item = TaskItem::Empty;
+                    self.active_menu_item = MenuItem::Projects;
+                    return vec![Effect::PostTask(Box::new(task))];
+                }
+            }
+            Action::Submit if self.active_menu_item == MenuItem::AddProject => {
+                let project = std::mem::take(&mut self.project_status.project_item);
+                let editing_id = self.project_status.editing_id.take();
+                self.project_status.active_project_item = ProjectItem::Empty;
+                self.active_menu_item = MenuItem::Projects;
+                if let Some(id) = editing_id {
+                    let name = project.name;
+                    self.edit_project(&id, |project| project.name = name.clone());
+                    let update = ProjectUpdate {
+                        name: Some(name),
+                        ..Default::default()
+                    };
+                    return vec![Effect::UpdateProject(id, update)];
+                }
+                let mut local = Project::name(&project.name);
+                local.parent_id = project.parent_id.clone();
+                self.database.push_project(local);
+                return vec![Effect::PostProject(project)];
+            }
+            Action::DeleteSelected => return self.delete_selected(),
+            Action::CloseSelected if self.active_menu_item == MenuItem::Tasks => {
+                let tasks = self.with_subtasks(self.outermost(self.target_tasks()));
+                if !tasks.is_empty() {
+                    return self.change_tasks(Change::ClosedTasks(tasks));
+                }
+            }
+            Action::ToggleMark if self.active_menu_item == MenuItem::Tasks => {
+                if let Some(id) = self.selected_task().map(|task| task.id.clone()) {
+                    if !self.marked.remove(&id) {
+                        self.marked.insert(id);
+                    }
+                    let next = self.selected_task_position().unwrap_or(0) + 1;
+                    self.select_task_at(next);
+                }
+            }
+            Action::ToggleVisual if self.active_menu_item == MenuItem::Tasks => {
+                if self.visual_anchor.is_some() {
+                    self.marked = self.marked_task_ids();
+                    self.visual_anchor = None;
+                } else {
+                    self.visual_anchor = self.selected_task().map(|task| task.id.clone());
+                }
+            }
+            Action::ClearMarks => self.clear_marks(),
+            Action::SetPriority(priority @ 1..=4) if self.active_menu_item == MenuItem::Tasks => {
+                let tasks = self.target_tasks();
+                if !tasks.is_empty() {
+                    let edit = TaskEdit::Priority(api_priority(priority));
+                    return self.change_tasks(Change::EditedTasks { tasks, edit });
+                }
+            }
+            Action::MoveSelectedUp | Action::MoveSelectedDown if self.in_lists() => {
+                let up = matches!(action, Action::MoveSelectedUp);
+                return match self.active_menu_item {
+                    MenuItem::Tasks => self.reorder_selected_task(up),
+                    _ => self.reorder_selected_project(up),
+                };
+            }
+            Action::MoveCardNext | Action::MoveCardPrevious
+                if self.active_menu_item == MenuItem::Tasks && self.is_board() =>
+            {
+                return self.move_to_adjacent_section(matches!(action, Action::MoveCardNext));
+            }
+            Action::CycleSortMode if self.in_lists() => {
+                if let Some(project) = self.selected_project() {
+                    let id = project.id.clone();
+                    let mode = self.sort_mode(&id).next();
+                    self.sort_modes.insert(id, mode);
+                    self.show_toast(format!("Sorting by {}", mode.label()));
+                }
+            }
+            Action::Reordered(Ok(())) => {}
+            Action::Reordered(Err(_)) => {
+                // put back the order the server has
+                return vec![Effect::FetchProjects, Effect::FetchTasks];
+            }
+            Action::OpenMovePicker
+                if self.active_menu_item == MenuItem::Tasks && self.selected_task().is_some() =>
+            {
+                self.popup = Some(Popup::Move(Picker::new("Move to", self.move_targets())));
+            }
+            Action::OpenLabelPicker { add }
+                if self.active_menu_item == MenuItem::Tasks && self.selected_task().is_some() =>
+            {
+                self.popup = Some(self.label_picker(add));
+            }
+            Action::OpenReschedulePicker
+                if self.active_menu_item == MenuItem::Tasks && self.selected_task().is_some() =>
+            {
+                let choices = RESCHEDULE_CHOICES
+                    .iter()
+                    .map(|&(label, due)| (label.to_owned(), due.to_owned()))
+                    .collect();
+                let picker = Picker::new("Reschedule to", choices).with_custom("Due");
+                self.popup = Some(Popup::Reschedule(picker));
+            }
+            Action::OpenHelp => self.help = Some(Help::default()),
+            Action::CloseHelp => self.help = None,
+            Action::HelpChar(c) => {
+                if let Some(help) = &mut self.help {
+                    help.query.push(c);
+                    help.offset = 0;
+                }
+            }
+            Action::HelpBackspace => {
+                if let Some(help) = &mut self.help {
+                    help.query.pop();
+                    help.offset = 0;
+                }
+            }
+            Action::HelpScrollDown => {
+                if let Some(help) = &self.help {
+                    let last = help_entries(self, &help.query).len().saturating_sub(1);
+                    let offset = (help.offset + 1).min(last);
+                    self.help.as_mut().unwrap().offset = offset;
+                }
+            }
+            Action::HelpScrollUp => {
+                if let Some(help) = &mut self.help {
+                    help.offset = help.offset.saturating_sub(1);
+                }
+            }
+            Action::PickerChar(c) => {
+                if let Some(popup) = &mut self.popup {
+                    popup.push(c);
+                }
+            }
+            Action::PickerBackspace => {
+                if let Some(popup) = &mut self.popup {
+                    popup.pop();
+                }
+            }
+            Action::PickerNext => {
+                if let Some(popup) = &mut self.popup {
+                    popup.next();
+                }
+            }
+            Action::PickerPrevious => {
+                if let Some(popup) = &mut self.popup {
+                    popup.previous();
+                }
+            }
+            Action::PickerConfirm => {
+                let change = match self.popup.take() {
+                    Some(Popup::Move(picker)) => picker.chosen().cloned().map(|to| {
+                        let tasks = self.outermost(self.target_tasks());
+                        Change::MovedTasks { tasks, to }
+                    }),
+                    Some(Popup::AddLabel(picker)) => {
+                        picker.chosen_or_query().map(TaskEdit::AddLabel).map(|edit| {
+                            let tasks = self.target_tasks();
+                            Change::EditedTasks { tasks, edit }
+                        })
+                    }
+                    Some(Popup::RemoveLabel(picker)) => {
+                        picker.chosen().cloned().map(TaskEdit::RemoveLabel).map(|edit| {
+                            let tasks = self.target_tasks();
+                            Change::EditedTasks { tasks, edit }
+                        })
+                    }
+                    Some(Popup::Reschedule(picker)) => {
+                        picker.chosen_or_query().map(TaskEdit::Reschedule).map(|edit| {
+                            let tasks = self.target_tasks();
+                            Change::EditedTasks { tasks, edit }
+                        })
+                    }
+                    Some(Popup::Priority(picker)) if self.editing_priority() => {
+                        if let Some(&priority) = picker.chosen() {
+                            self.task_status.task_content.priority = priority;
+                        }
+                        None
+                    }
+                    Some(Popup::Priority(picker)) => {
+                        picker.chosen().copied().map(TaskEdit::Priority).map(|edit| {
+                            let tasks = self.target_tasks();
+                            Change::EditedTasks { tasks, edit }
+                        })
+                    }
+                    Some(Popup::ProjectColor(picker)) => {
+                        return match (picker.chosen().cloned(), self.selected_user_project()) {
+                            (Some(color), Some(project)) => {
+                                self.edit_project(&project.id, |p| p.color = color.clone());
+                                let update = ProjectUpdate {
+                                    color: Some(color),
+                                    ..Default::default()
+                                };
+                                vec![Effect::UpdateProject(project.id, update)]
+                            }
+                            _ => vec![],
+                        };
+                    }
+                    Some(Popup::Unarchive(picker)) => {
+                        return match picker.chosen() {
+                            Some(id) => {
+                                self.show_toast("Unarchiving project");
+                                vec![Effect::SyncProjects(vec![SyncCommand::project_unarchive(id)])]
+                            }
+                            None => vec![],
+                        };
+                    }
+                    None => None,
+                };
+                if let Some(change) = change.filter(|change| !change.tasks().is_empty()) {
+                    return self.change_tasks(change);
+                }
+            }
+            Action::OpenColorPicker
+                if self.active_menu_item == MenuItem::Projects
+                    && self.selected_user_project().is_some() =>
+            {
+                let colors = PROJECT_COLORS
+                    .iter()
+                    .map(|(name, _)| (name.replace('_', " "), name.to_string()))
+                    .collect();
+                self.popup = Some(Popup::ProjectColor(Picker::new("Color", colors)));
+            }
+            Action::ToggleFavorite if self.active_menu_item == MenuItem::Projects => {
+                if let Some(project) = self.selected_user_project() {
+                    let is_favorite = !project.is_favorite;
+                    self.edit_project(&project.id, |project| project.is_favorite = is_favorite);
+                    let update = ProjectUpdate {
+                        is_favorite: Some(is_favorite),
+                        ..Default::default()
+                    };
+                    return vec![Effect::UpdateProject(project.id, update)];
+                }
+            }
+            Action::ToggleViewStyle if self.active_menu_item == MenuItem::Projects => {
+                if let Some(project) = self.selected_user_project() {
+                    let view_style = match project.view_style.as_str() {
+                        "board" => "list",
+                        _ => "board",
+                    };
+                    self.edit_project(&project.id, |project| {
+                        project.view_style = view_style.to_owned()
+                    });
+                    self.show_toast(format!("\"{}\" shows as a {}", project.name, view_style));
+                    let update = ProjectUpdate {
+                        view_style: Some(view_style.to_owned()),
+                        ..Default::default()
+                    };
+                    return vec![Effect::UpdateProject(project.id, update)];
+                }
+            }
+            Action::ArchiveSelected if self.active_menu_item == MenuItem::Projects => {
+                if let Some(project) = self.selected_user_project() {
+                    let position = self.selected_project_position().unwrap_or(0);
+                    // child projects are archived along with their parent
+                    let mut archived = vec![project.id.clone()];
+                    let mut i = 0;
+                    while i < archived.len() {
+                        let children = self.database.projects().iter().filter(|p| {
+                            p.parent_id.as_deref() == Some(archived[i].as_str())
+                        });
+                        let children: Vec<String> = children.map(|p| p.id.clone()).collect();
+                        archived.extend(children);
+                        i += 1;
+                    }
+                    for id in &archived {
+                        self.database.remove_project(id);
+                    }
+                    self.select_project_at(position.saturating_sub(1));
+                    self.show_toast(format!("Archived \"{}\" · U to unarchive", project.name));
+                    return vec![Effect::SyncProjects(vec![SyncCommand::project_archive(
+                        &project.id,
+                    )])];
+                }
+            }
+            Action::OpenUnarchivePicker if self.active_menu_item == MenuItem::Projects => {
+                return vec![Effect::FetchArchivedProjects];
+            }
+            Action::ArchivedProjectsLoaded(Ok(projects)) => {
+                if projects.is_empty() {
+                    self.show_toast("No archived projects");
+                } else {
+                    let items = projects.into_iter().map(|p| (p.name, p.id)).collect();
+                    self.popup = Some(Popup::Unarchive(Picker::new("Unarchive", items)));
+                }
+            }
+            Action::PickerCancel => self.popup = None,
+            Action::Undo if self.in_lists() => match self.history.undo() {
+                Some(change) => {
+                    self.show_toast(format!("Undone: {}", change.describe()));
+                    return self.revert(change);
+                }
+                None => self.show_toast("Nothing to undo"),
+            },
+            Action::Redo if self.in_lists() => match self.history.redo() {
+                Some(change) => {
+                    self.show_toast(format!("Redone: {}", change.describe()));
+                    return self.apply(change);
+                }
+                None => self.show_toast("Nothing to redo"),
+            },
+            Action::ConfirmYes => match self.confirm.take() {
+                Some(Confirm::DeleteProject { id, name, .. }) => {
+                    let position = self.database.project_position(&id).unwrap_or(0);
+                    self.database.remove_project(&id);
+                    self.select_project_at(position.saturating_sub(1));
+                    self.show_toast(format!("Deleted project \"{}\"", name));
+                    return vec![Effect::DeleteProject(id)];
+                }
+                None => {}
+            },
+            Action::ConfirmNo => self.confirm = None,
+            Action::DismissToast => self.toast = None,
+            Action::Export if self.in_lists() => {
+                return vec![Effect::Export(Box::new(self.database.clone()))];
+            }
+            Action::Exported(Ok(paths)) => {
+                self.show_toast(format!("Exported {} files to {}", paths.len(), DEFAULT_EXPORT_DIR))
+            }
+            Action::Exported(Err(err)) => self.show_toast(format!("Export failed: {}", err)),
+            Action::CalendarSynced(Err(err)) => {
+                self.show_toast(format!("Calendar not updated: {}", err))
+            }
+            Action::RateLimited(seconds) => {
+                self.show_toast(format!("Rate limited, retrying in {}s", seconds))
+            }
+            Action::StartImport => {
+                if let Some(status) = &mut self.import_status {
+                    if status.progress.started && status.progress.error.is_none() {
+                        return vec![];
+                    }
+                    status.progress = ImportProgress {
+                        started: true,
+                        ..Default::default()
+                    };
+                    return vec![Effect::RunImport(status.plan.clone(), status.state.clone())];
+                }
+            }
+            Action::ScrollImportDown => {
+                if let Some(status) = &mut self.import_status {
+                    status.scroll = status.scroll.saturating_add(1);
+                }
+            }
+            Action::ScrollImportUp => {
+                if let Some(status) = &mut self.import_status {
+                    status.scroll = status.scroll.saturating_sub(1);
+                }
+            }
+            Action::CloseImport => {
+                if let Some(status) = &self.import_status {
+                    if status.progress.is_running() {
+                        return vec![];
+                    }
+                }
+                self.import_status = None;
+                self.active_menu_item = MenuItem::Projects;
+            }
+            Action::ProjectsLoaded(Ok(mut projects)) => {
+                let previous = self.selected_project_position().unwrap_or(0);
+                sort_projects(&mut projects);
+                self.database.set_projects(projects);
+                // keep the position when the selected project is gone
+                let selected = self.selected_project_id.as_deref();
+                if selected.and_then(|id| self.database.project(id)).is_none() {
+                    self.select_project_at(previous);
+                }
+            }
+            Action::TasksLoaded(Ok(mut tasks)) => {
+                sort_tasks(&mut tasks);
+                let previous = self.selected_task_position().unwrap_or(0);
+                self.database.set_tasks(tasks);
+                if self.selected_task_position().is_none() {
+                    self.select_task_at(previous);
+                }
+                // tasks done or deleted elsewhere drop out of the marks
+                let database = &self.database;
+                self.marked.retain(|id| database.task(id).is_some());
+                return vec![Effect::SyncCalendar(self.database.tasks().to_vec())];
+            }
+            Action::SectionsLoaded(Ok(sections)) => self.database.set_sections(sections),
+            Action::LabelsLoaded(Ok(labels)) => self.database.set_labels(labels),
+            Action::TaskPosted(result) => {
+                if result.is_ok() {
+                    self.show_toast("Task added");
+                }
+                return vec![Effect::FetchTasks];
+            }
+            Action::TasksSynced(_) => {
+                return vec![Effect::FetchTasks];
+            }
+            Action::TaskRecreated { old_id, result } => {
+                let awaiting = std::mem::take(&mut self.awaiting_parent);
+                let (subtasks, rest): (Vec<Task>, Vec<Task>) = awaiting
+                    .into_iter()
+                    .partition(|task| task.parent_id.as_deref() == Some(old_id.as_str()));
+                self.awaiting_parent = rest;
+                let mut effects = vec![Effect::FetchTasks];
+                match result {
+                    Ok(task) => {
+                        self.history.rename_task(&old_id, &task.id);
+                        if self.selected_task_id.as_deref() == Some(old_id.as_str()) {
+                            self.selected_task_id = Some(task.id.clone());
+                        }
+                        for mut subtask in subtasks {
+                            subtask.parent_id = Some(task.id.clone());
+                            effects.push(Effect::RecreateTask(Box::new(subtask)));
+                        }
+                    }
+                    // the subtasks of a task that could not be created again
+                    // have nothing to go under
+                    Err(_) => {
+                        let mut lost: Vec<String> = subtasks.into_iter().map(|t| t.id).collect();
+                        while let Some(id) = lost.pop() {
+                            self.awaiting_parent.retain(|task| {
+                                let below = task.parent_id.as_deref() == Some(id.as_str());
+                                if below {
+                                    lost.push(task.id.clone());
+                                }
+                                !below
+                            });
+                        }
+                    }
+                }
+                return effects;
+            }
+            Action::ProjectPosted(result) => {
+                if result.is_ok() {
+                    self.show_toast("Project added");
+                }
+                return vec![Effect::FetchProjects];
+            }
+            Action::UserLoaded(Ok(user)) => self.user = Some(user),
+            Action::ProjectUpdated(result) => match result {
+                Ok(project) => {
+                    let id = project.id.clone();
+                    self.edit_project(&id, |local| *local = project);
+                }
+                Err(_) => return vec![Effect::FetchProjects],
+            },
+            Action::ProjectsSynced(_) => {
+                return vec![Effect::FetchProjects, Effect::FetchTasks];
+            }
+            Action::ImportProgressed(progress) => {
+                let finished = progress.finished;
+                if let Some(status) = &mut self.import_status {
+                    status.update(progress);
+                }
+                if finished {
+                    return vec![Effect::FetchProjects, Effect::FetchTasks];
+                }
+            }
+            _ => {}
+        }
+        vec![]
+    }
+
+    fn delete_selected(&mut self) -> Vec<Effect> {
+        match self.active_menu_item {
+            MenuItem::Tasks => {
+                let tasks = self.with_subtasks(self.outermost(self.target_tasks()));
+                if tasks.is_empty() {
+                    return vec![];
+                }
+                self.change_tasks(Change::DeletedTasks(tasks))
+            }
+            MenuItem::Projects => {
+                if let Some(project) = self.selected_user_project() {
+                    self.confirm = Some(Confirm::DeleteProject {
+                        task_count: self.database.project_task_count(&project.id),
+                        id: project.id,
+                        name: project.name,
+                    });
+                }
+                vec![]
+            }
+            _ => vec![],
+        }
+    }
+
+    /// Swaps the selected task with its previous or next sibling.
+    fn reorder_selected_task(&mut self, up: bool) -> Vec<Effect> {
+        let task = match self.selected_task() {
+            Some(task) if !task.is_temp() => task.clone(),
+            _ => return vec![],
+        };
+        if self.sort_mode(&task.project_id) != SortMode::Manual {
+            self.show_toast("Switch to manual sorting with s to reorder");
+            return vec![];
+        }
+        let mut siblings: Vec<String> = self
+            .selected_project_tasks()
+            .into_iter()
+            .filter(|t| t.section_id == task.section_id && t.parent_id == task.parent_id)
+            .filter(|t| !t.is_temp())
+            .map(|t| t.id.clone())
+            .collect();
+        if !swap_with_neighbour(&mut siblings, &task.id, up) {
+            return vec![];
+        }
+
+        let orders = renumber(siblings);
+        for (id, order) in &orders {
+            self.database.update_task(id, |task| task.order = *order);
+        }
+        vec![Effect::ReorderTasks(orders)]
+    }
+
+    /// Swaps the selected project with its previous or next sibling. The
+    /// inbox always stays first.
+    fn reorder_selected_project(&mut self, up: bool) -> Vec<Effect> {
+        let project = match self.selected_project() {
+            Some(project) if !project.is_inbox_project && !project.is_temp() => project.clone(),
+            _ => return vec![],
+        };
+        let mut siblings: Vec<String> = self
+            .database
+            .projects()
+            .iter()
+            .filter(|p| {
+                p.parent_id == project.parent_id
+                    && p.is_favorite == project.is_favorite
+                    && !p.is_inbox_project
+            })
+            .map(|p| p.id.clone())
+            .collect();
+        if !swap_with_neighbour(&mut siblings, &project.id, up) {
+            return vec![];
+        }
+
+        let orders = renumber(siblings);
+        let mut projects = self.database.projects().to_vec();
+        for project in projects.iter_mut() {
+            if let Some((_, order)) = orders.iter().find(|(id, _)| *id == project.id) {
+                project.order = *order;
+            }
+        }
+        sort_projects(&mut projects);
+        self.database.set_projects(projects);
+        vec![Effect::ReorderProjects(orders)]
+    }
+
+    /// Projects and their sections, as choices of the move picker.
+    fn move_targets(&self) -> Vec<(String, Placement)> {
+        let mut targets = vec![];
+        for project in self.database.projects().iter().filter(|p| !p.is_temp()) {
+            targets.push((
+                project.name.clone(),
+                Placement {
+                    project_id: project.id.clone(),
+                    section_id: None,
+                    parent_id: None,
+                },
+            ));
+            for section in self.database.project_sections(&project.id) {
+                targets.push((
+                    format!("{} / {}", project.name, section.name),
+                    Placement {
+                        project_id: project.id.clone(),
+                        section_id: Some(section.id.clone()),
+                        parent_id: None,
+                    },
+                ));
+            }
+        }
+        targets
+    }
+
+    /// Labels for the label picker: every label when adding, otherwise
+    /// only those on the targeted tasks.
+    fn label_picker(&self, add: bool) -> Popup {
+        let mut labels: Vec<String> = if add {
+            self.database.labels().iter().map(|label| label.name.clone()).collect()
+        } else {
+            let tasks = self.target_tasks();
+            tasks.into_iter().flat_map(|task| task.labels).collect()
+        };
+        labels.sort();
+        labels.dedup();
+        let items = labels.into_iter().map(|label| (label.clone(), label)).collect();
+        if add {
+            Popup::AddLabel(Picker::new("Add label", items).with_custom("New label"))
+        } else {
+            Popup::RemoveLabel(Picker::new("Remove label", items))
+        }
+    }
+
+    /// Makes a change to the targeted tasks, clears the marks and keeps the
+    /// selection near where it was when tasks went away.
+    fn change_tasks(&mut self, change: Change) -> Vec<Effect> {
+        let position = self.selected_task_position().unwrap_or(0);
+        let removes = !matches!(change, Change::EditedTasks { .. });
+        self.show_toast(format!("{} · u to undo", change.describe()));
+        self.history.record(change.clone());
+        let effects = self.apply(change);
+        self.clear_marks();
+
+        if self.selected_project_task_count() == 0 {
+            self.active_menu_item = MenuItem::Projects;
+        }
+        if removes && self.selected_task_position().is_none() {
+            self.select_task_at(position.saturating_sub(1));
+        }
+        effects
+    }
+
+    /// Makes `change` locally and asks the API to make it.
+    fn apply(&mut self, change: Change) -> Vec<Effect> {
+        let commands = match change {
+            Change::DeletedTasks(tasks) => tasks
+                .iter()
+                .filter_map(|task| {
+                    self.database.remove_task(&task.id);
+                    // subtasks are deleted along with their parent
+                    outside_parent(task, &tasks).then(|| SyncCommand::item_delete(&task.id))
+                })
+                .collect(),
+            Change::ClosedTasks(tasks) => tasks
+                .iter()
+                .filter_map(|task| {
+                    self.database.remove_task(&task.id);
+                    outside_parent(task, &tasks).then(|| SyncCommand::item_close(&task.id))
+                })
+                .collect(),
+            Change::MovedTasks { tasks, to } => tasks
+                .iter()
+                .map(|task| {
+                    self.database.move_task(&task.id, &to);
+                    SyncCommand::item_move(&task.id, &to)
+                })
+                .collect(),
+            Change::EditedTasks { tasks, edit } => tasks
+                .into_iter()
+                .map(|mut task| {
+                    edit.apply(&mut task);
+                    let fields = edit.fields(&task);
+                    self.database.update_task(&task.id, |edited| edit.apply(edited));
+                    SyncCommand::item_update(&task.id, fields)
+                })
+                .collect(),
+        };
+        vec![Effect::SyncTasks(commands)]
+    }
+
+    /// Reverts `change` locally and asks the API to revert it.
+    fn revert(&mut self, change: Change) -> Vec<Effect> {
+        match change {
+            Change::DeletedTasks(tasks) => {
+                let mut effects = vec![];
+                for mut task in tasks.iter().cloned() {
+                    if let Some(string) = task.due.as_ref().and_then(|due| due.string.clone()) {
+                        task.due_string = Some(string);
+                    }
+                    self.database.push_task(task.clone());
+                    // subtasks follow once their parent has its new id
+                    if outside_parent(&task, &tasks) {
+                        effects.push(Effect::RecreateTask(Box::new(task)));
+                    } else {
+                        self.awaiting_parent.push(task);
+                    }
+                }
+                effects
+            }
+            Change::ClosedTasks(tasks) => {
+                let commands = tasks
+                    .into_iter()
+                    .map(|task| {
+                        let command = SyncCommand::item_uncomplete(&task.id);
+                        self.database.push_task(task);
+                        command
+                    })
+                    .collect();
+                vec![Effect::SyncTasks(commands)]
+            }
+            Change::MovedTasks { tasks, .. } => {
+                let commands = tasks
+                    .iter()
+                    .map(|task| {
+                        let placement = Placement::of(task);
+                        self.database.move_task(&task.id, &placement);
+                        SyncCommand::item_move(&task.id, &placement)
+                    })
+                    .collect();
+                vec![Effect::SyncTasks(commands)]
+            }
+            Change::EditedTasks { tasks, edit } => {
+                let commands = tasks
+                    .into_iter()
+                    .map(|task| {
+                        let command = SyncCommand::item_update(&task.id, edit.fields(&task));
+                        self.database
+                            .update_task(&task.id, |edited| edit.restore(edited, &task));
+                        command
+                    })
+                    .collect();
+                vec![Effect::SyncTasks(commands)]
+            }
+        }
+    }
+}
+
+/// Whether the parent of `task` is not one of `tasks`.
+fn outside_parent(task: &Task, tasks: &[Task]) -> bool {
+    match &task.parent_id {
+        Some(parent_id) => !tasks.iter().any(|other| &other.id == parent_id),
+        None => true,
+    }
+}
+
+fn swap_with_neighbour(ids: &mut [String], id: &str, up: bool) -> bool {
+    let position = match ids.iter().position(|other| other == id) {
+        Some(position) => position,
+        None => return false,
+    };
+    let neighbour = if up {
+        position.checked_sub(1)
+    } else {
+        Some(position + 1).filter(|&i| i < ids.len())
+    };
+    match neighbour {
+        Some(neighbour) => {
+            ids.swap(position, neighbour);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Gives `ids` consecutive orders starting at 1.
+fn renumber(ids: Vec<String>) -> Vec<(String, usize)> {
+    ids.into_iter().zip(1..).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use tui::style::Color;
+
+    use super::*;
+    use crate::{api::TaskContent, config::LayoutConfig, task::TASK_FIELDS};
+
+    fn app() -> App {
+        let config = Config {
+            token: String::new(),
+            color: Color::Blue,
+            ics_path: None,
+            layout: LayoutConfig::default(),
+            refresh_interval: None,
+            dates: DateConfig::default(),
+        };
+        let mut app = App::new(&config, None);
+        let mut inbox = Project::name("Inbox");
+        inbox.id = "inbox".to_owned();
+        inbox.is_inbox_project = true;
+        app.update(Action::ProjectsLoaded(Ok(vec![inbox])));
+        app
+    }
+
+    fn task(id: &str, order: usize, parent_id: Option<&str>) -> Task {
+        let content = TaskContent {
+            content: format!("Task {}", id),
+            ..Default::default()
+        };
+        let mut task = Task::temp(content, "inbox".to_owned());
+        task.id = id.to_owned();
+        task.order = order;
+        task.parent_id = parent_id.map(str::to_owned);
+        task
+    }
+
+    /// An app showing the tasks of the inbox, with `selected` selected.
+    fn app_with_tasks(tasks: Vec<Task>, selected: &str) -> App {
+        let mut app = app();
+        app.update(Action::TasksLoaded(Ok(tasks)));
+        app.active_menu_item = MenuItem::Tasks;
+        app.selected_task_id = Some(selected.to_owned());
+        app
+    }
+
+    #[test]
+    fn submit_posts_the_new_task() {
+        let mut app = app();
+        app.active_menu_item = MenuItem::AddTask;
+        app.task_status.task_content.content = "Buy milk".to_owned();
+
+        let effects = app.update(Action::Submit);
+
+        match effects.as_slice() {
+            [Effect::PostTask(task)] => {
+                assert_eq!(task.content, "Buy milk");
+                assert_eq!(task.project_id, "inbox");
+                assert!(task.is_temp());
+            }
+            effects => panic!("{:?}", effects),
+        }
+        assert_eq!(app.database.tasks().len(), 1);
+        assert_eq!(app.active_menu_item, MenuItem::Projects);
+        assert_eq!(app.pending, 1);
+    }
+
+    #[test]
+    fn fields_step_through_the_form_and_wrap_around() {
+        let mut app = app();
+        app.active_menu_item = MenuItem::Projects;
+        app.update(Action::OpenAddTask);
+        for expected in TASK_FIELDS.iter().skip(1).chain(&TASK_FIELDS[..1]) {
+            app.update(Action::NextField);
+            assert_eq!(app.task_status.active_task_item, *expected);
+        }
+
+        app.update(Action::PreviousField);
+        assert_eq!(app.task_status.active_task_item, TaskItem::Prio);
+        assert_eq!(app.task_status.add_task_highlight.prio, Color::Blue);
+        assert_eq!(app.task_status.add_task_highlight.name, Color::White);
+    }
+
+    #[test]
+    fn tasks_being_created_get_their_own_ids_and_cannot_be_changed() {
+        let mut app = app();
+        for content in ["Buy milk", "Buy bread"] {
+            app.active_menu_item = MenuItem::AddTask;
+            app.task_status.task_content.content = content.to_owned();
+            app.update(Action::Submit);
+        }
+        let ids: HashSet<String> = app.database.tasks().iter().map(|t| t.id.clone()).collect();
+        assert_eq!(ids.len(), 2);
+
+        app.active_menu_item = MenuItem::Tasks;
+        app.selected_task_id = ids.into_iter().next();
+        assert!(app.update(Action::DeleteSelected).is_empty());
+        assert!(app.update(Action::CloseSelected).is_empty());
+        assert_eq!(app.database.tasks().len(), 2);
+    }
+
+    #[test]
+    fn tasks_loaded_keeps_the_selection() {
+        let tasks = vec![task("a", 1, None), task("b", 2, None), task("c", 3, None)];
+        let mut app = app_with_tasks(tasks, "b");
+
+        let reordered = vec![task("c", 1, None), task("a", 2, None), task("b", 3, None)];
+        app.update(Action::TasksLoaded(Ok(reordered)));
+        assert_eq!(app.selected_task_id.as_deref(), Some("b"));
+        assert_eq!(app.task_status.task_table_state.selected(), Some(2));
+
+        // gone elsewhere, so the task now at its position is selected
+        let without_b = vec![task("c", 1, None), task("a", 2, None)];
+        app.update(Action::TasksLoaded(Ok(without_b)));
+        assert_eq!(app.selected_task_id.as_deref(), Some("a"));
+    }
+
+    #[test]
+    fn undo_of_a_delete_recreates_the_task() {
+        let mut app = app_with_tasks(vec![task("a", 1, None), task("b", 2, None)], "a");
+
+        let effects = app.update(Action::DeleteSelected);
+        match effects.as_slice() {
+            [Effect::SyncTasks(commands)] => {
+                assert_eq!(commands.len(), 1);
+                assert_eq!(commands[0].kind, "item_delete");
+                assert_eq!(commands[0].args["id"], "a");
+            }
+            effects => panic!("{:?}", effects),
+        }
+        assert!(app.database.task("a").is_none());
+
+        let effects = app.update(Action::Undo);
+        match effects.as_slice() {
+            [Effect::RecreateTask(task)] => assert_eq!(task.id, "a"),
+            effects => panic!("{:?}", effects),
+        }
+        assert!(app.database.task("a").is_some());
+    }
+
+    #[test]
+    fn deleting_a_task_takes_its_subtasks_and_undo_restores_them() {
+        let tasks = vec![
+            task("p", 1, None),
+            task("c", 1, Some("p")),
+            task("g", 1, Some("c")),
+            task("other", 2, None),
+        ];
+        let mut app = app_with_tasks(tasks, "p");
+
+        let effects = app.update(Action::DeleteSelected);
+        match effects.as_slice() {
+            // the server deletes the subtasks along with their parent
+            [Effect::SyncTasks(commands)] => assert_eq!(commands.len(), 1),
+            effects => panic!("{:?}", effects),
+        }
+        for id in ["p", "c", "g"] {
+            assert!(app.database.task(id).is_none(), "{}", id);
+        }
+
+        let effects = app.update(Action::Undo);
+        match effects.as_slice() {
+            [Effect::RecreateTask(task)] => assert_eq!(task.id, "p"),
+            effects => panic!("{:?}", effects),
+        }
+        assert_eq!(app.awaiting_parent.len(), 2);
+
+        let recreated = |old_id: &str, new_id: &str| Action::TaskRecreated {
+            old_id: old_id.to_owned(),
+            result: Ok(Box::new(task(new_id, 1, None))),
+        };
+        let effects = app.update(recreated("p", "p2"));
+        match effects.as_slice() {
+            [Effect::FetchTasks, Effect::RecreateTask(task)] => {
+                assert_eq!(task.id, "c");
+                assert_eq!(task.parent_id.as_deref(), Some("p2"));
+            }
+            effects => panic!("{:?}", effects),
+        }
+        let effects = app.update(recreated("c", "c2"));
+        match effects.as_slice() {
+            [Effect::FetchTasks, Effect::RecreateTask(task)] => {
+                assert_eq!(task.id, "g");
+                assert_eq!(task.parent_id.as_deref(), Some("c2"));
+            }
+            effects => panic!("{:?}", effects),
+        }
+        assert!(app.awaiting_parent.is_empty());
+    }
+
+    #[test]
+    fn refresh_waits_for_pending_requests() {
+        let mut app = app();
+        app.init();
+        assert!(app.update(Action::Refresh).is_empty());
+        assert!(app.update(Action::Refresh).is_empty());
+        assert!(app.refresh_queued);
+
+        // the last response runs the queued refresh, only once
+        app.pending = 1;
+        let effects = app.update(Action::LabelsLoaded(Ok(vec![])));
+        assert_eq!(effects.len(), 4);
+        assert_eq!(app.pending, 4);
+        assert!(!app.refresh_queued);
+        assert_eq!(app.sync_state(), SyncState::Syncing);
+
+        app.pending = 1;
+        assert!(app.update(Action::LabelsLoaded(Ok(vec![]))).is_empty());
+    }
+
+    #[test]
+    fn rate_limits_are_shown_while_the_request_waits() {
+        let mut app = app();
+        app.init();
+        let pending = app.pending;
+
+        assert!(app.update(Action::RateLimited(5)).is_empty());
+        assert_eq!(app.toast.as_ref().unwrap().message, "Rate limited, retrying in 5s");
+        assert_eq!(app.pending, pending);
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::{HashMap, HashSet};
+
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{List, ListItem, ListState},
+    Frame,
+};
+
+use crate::{
+    api::Task, database::Database, date::DateConfig, handler::create_advanced_block, mouse::row_regions,
+    navigation::scroll_offset, priority::priority_flag, task::task_details,
+};
+
+/// Narrower columns are scrolled out of view instead.
+const MIN_COLUMN_WIDTH: u16 = 24;
+
+/// A section of a board project with its task cards.
+pub struct BoardColumn<'a> {
+    pub section_id: Option<String>,
+    pub name: String,
+    pub tasks: Vec<&'a Task>,
+}
+
+/// Splits `tasks` of a project into one column per section, in section
+/// order. Tasks outside of any section come first when there are some.
+pub fn board_columns<'a>(
+    database: &Database,
+    project_id: &str,
+    tasks: &[&'a Task],
+) -> Vec<BoardColumn<'a>> {
+    let sections: Vec<_> = database.project_sections(project_id).collect();
+
+    // positions in `tasks`, to keep its order within each column
+    let shown: HashMap<&str, usize> = tasks
+        .iter()
+        .enumerate()
+        .map(|(i, task)| (task.id.as_str(), i))
+        .collect();
+    let in_section = |section_id: &str| -> Vec<&'a Task> {
+        let mut positions: Vec<usize> = database
+            .section_tasks(section_id)
+            .filter_map(|task| shown.get(task.id.as_str()).copied())
+            .collect();
+        positions.sort_unstable();
+        positions.into_iter().map(|i| tasks[i]).collect()
+    };
+
+    let mut columns = vec![];
+    let unsectioned: Vec<&'a Task> = tasks
+        .iter()
+        .filter(|task| task.section_id.is_none())
+        .copied()
+        .collect();
+    if !unsectioned.is_empty() || sections.is_empty() {
+        columns.push(BoardColumn {
+            section_id: None,
+            name: "(No section)".to_owned(),
+            tasks: unsectioned,
+        });
+    }
+    for section in sections {
+        columns.push(BoardColumn {
+            section_id: Some(section.id.clone()),
+            name: section.name.clone(),
+            tasks: in_section(&section.id),
+        });
+    }
+    columns
+}
+
+/// Where `task_id` is on the board, as column and row.
+pub fn board_position(columns: &[BoardColumn], task_id: &str) -> Option<(usize, usize)> {
+    columns
+        .iter()
+        .enumerate()
+        .find_map(|(column, board_column)| {
+            let row = board_column
+                .tasks
+                .iter()
+                .position(|task| task.id == task_id)?;
+            Some((column, row))
+        })
+}
+
+fn card(task: &Task, marked: bool, width: usize, dates: &DateConfig) -> ListItem<'static> {
+    let mark = if marked { "* " } else { "" };
+    let mut lines = vec![Spans::from(vec![
+        priority_flag(task.priority),
+        Span::styled(
+            format!(" {}{}", mark, task.content),
+            Style::default().add_modifier(Modifier::BOLD),
+        ),
+    ])];
+    let mut details = task_details(task, dates);
+    if !task.labels.is_empty() {
+        if !details.is_empty() {
+            details.push(Span::raw(" · "));
+        }
+        details.push(Span::raw(task.labels.join(", ")));
+    }
+    if !details.is_empty() {
+        lines.push(Spans::from(details));
+    }
+    lines.push(Spans::from("─".repeat(width)));
+    ListItem::new(lines)
+}
+
+pub fn render_board<B: Backend>(
+    rect: &mut Frame<B>,
+    columns: &[BoardColumn],
+    selected_id: Option<&str>,
+    marked: &HashSet<String>,
+    // the highlight color, when the board has the focus
+    focus: Option<Color>,
+    dates: &DateConfig,
+    area: Rect,
+) -> Vec<(Rect, String)> {
+    let mut cards = vec![];
+    if columns.is_empty() {
+        return cards;
+    }
+    let selected = selected_id.and_then(|id| board_position(columns, id));
+    let color = focus.unwrap_or(Color::White);
+    let visible = ((area.width / MIN_COLUMN_WIDTH) as usize).clamp(1, columns.len());
+    // scroll just far enough to keep the selected column in view
+    let first = selected.map_or(0, |(column, _)| (column + 1).saturating_sub(visible));
+    let constraints = vec![Constraint::Ratio(1, visible as u32); visible];
+    let areas = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(constraints)
+        .split(area);
+
+    for (i, column_area) in areas.into_iter().enumerate() {
+        let column = first + i;
+        let board_column = &columns[column];
+        let width = column_area.width.saturating_sub(3) as usize;
+        let items: Vec<ListItem> = board_column
+            .tasks
+            .iter()
+            .map(|task| card(task, marked.contains(&task.id), width, dates))
+            .collect();
+        let heights: Vec<u16> = items.iter().map(|item| item.height() as u16).collect();
+        let is_selected = focus.is_some() && selected.is_some_and(|(c, _)| c == column);
+        let row = selected
+            .filter(|&(c, _)| c == column)
+            .map_or(0, |(_, row)| row);
+        let max_height = column_area.height.saturating_sub(2);
+        let offset = scroll_offset(&heights, row, 0, max_height);
+        for (region, i) in row_regions(column_area, &heights, offset) {
+            cards.push((region, board_column.tasks[i].id.clone()));
+        }
+        let items: Vec<ListItem> = items.into_iter().skip(offset).collect();
+        let border = if is_selected { color } else { Color::White };
+        let title = format!("{} ({})", board_column.name, board_column.tasks.len());
+        let list = List::new(items)
+            .block(create_advanced_block(title, border, Alignment::Left))
+            .highlight_style(Style::default().fg(color).add_modifier(Modifier::BOLD))
+            .highlight_symbol(">");
+        let mut state = ListState::default();
+        if is_selected {
+            state.select(Some(row - offset));
+        }
+        rect.render_stateful_widget(list, column_area, &mut state);
+    }
+    cards
+}
@@ -0,0 +1,143 @@
+use chrono::{Datelike, Months, NaiveDate, TimeDelta};
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Clear, Paragraph},
+    Frame,
+};
+
+use crate::{handler::create_advanced_block, popup::centered_rect};
+
+/// Due strings of the calendar's quick keys besides picking a day.
+pub const TODAY: &str = "today";
+pub const TOMORROW: &str = "tomorrow";
+pub const NEXT_WEEK: &str = "next week";
+pub const NO_DATE: &str = "";
+pub const EVERY_DAY: &str = "every day";
+pub const EVERY_WEEKDAY: &str = "every weekday";
+pub const EVERY_WEEK: &str = "every week";
+pub const EVERY_MONTH: &str = "every month";
+
+const HINTS: [&str; 4] = [
+    "Enter pick · H/L month",
+    "t today · m tomorrow",
+    "w next week · x no date",
+    "1-4 every day/weekday/week/month",
+];
+
+/// A month grid to pick the due date of the add task form from.
+pub struct Calendar {
+    pub cursor: NaiveDate,
+}
+
+impl Calendar {
+    pub fn new(cursor: NaiveDate) -> Calendar {
+        Calendar { cursor }
+    }
+
+    pub fn move_days(&mut self, days: i64) {
+        if let Some(date) = self.cursor.checked_add_signed(TimeDelta::days(days)) {
+            self.cursor = date;
+        }
+    }
+
+    /// Moves to the same day of another month, or its last day when the
+    /// month is shorter.
+    pub fn move_months(&mut self, months: i32) {
+        let moved = if months < 0 {
+            self.cursor
+                .checked_sub_months(Months::new(months.unsigned_abs()))
+        } else {
+            self.cursor.checked_add_months(Months::new(months as u32))
+        };
+        if let Some(date) = moved {
+            self.cursor = date;
+        }
+    }
+
+    /// The due string for the day under the cursor.
+    pub fn due_string(&self) -> String {
+        self.cursor.format("%Y-%m-%d").to_string()
+    }
+}
+
+/// Days of the month of `cursor` by week, Monday first, None for the days
+/// of the neighbouring months.
+fn month_weeks(cursor: NaiveDate) -> Vec<[Option<NaiveDate>; 7]> {
+    let first = cursor.with_day(1).unwrap_or(cursor);
+    let mut weeks = vec![];
+    let mut week = [None; 7];
+    let mut day = first;
+    let mut weekday = first.weekday().num_days_from_monday() as usize;
+    while day.month() == first.month() {
+        week[weekday] = Some(day);
+        weekday += 1;
+        if weekday == 7 {
+            weeks.push(week);
+            week = [None; 7];
+            weekday = 0;
+        }
+        match day.succ_opt() {
+            Some(next) => day = next,
+            None => break,
+        }
+    }
+    if weekday > 0 {
+        weeks.push(week);
+    }
+    weeks
+}
+
+pub fn render_calendar<B: Backend>(
+    rect: &mut Frame<B>,
+    calendar: &Calendar,
+    today: NaiveDate,
+    color: Color,
+    area: Rect,
+) {
+    let mut lines = vec![Spans::from(Span::styled(
+        "Mo Tu We Th Fr Sa Su",
+        Style::default().add_modifier(Modifier::BOLD),
+    ))];
+    for week in month_weeks(calendar.cursor) {
+        let mut spans = vec![];
+        for (i, day) in week.iter().enumerate() {
+            if i > 0 {
+                spans.push(Span::raw(" "));
+            }
+            let day = match day {
+                Some(day) => *day,
+                None => {
+                    spans.push(Span::raw("  "));
+                    continue;
+                }
+            };
+            let mut style = Style::default();
+            if day == today {
+                style = style.fg(color).add_modifier(Modifier::BOLD);
+            }
+            if day == calendar.cursor {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+            spans.push(Span::styled(format!("{:>2}", day.day()), style));
+        }
+        lines.push(Spans::from(spans));
+    }
+    lines.push(Spans::from(""));
+    for hint in HINTS {
+        lines.push(Spans::from(Span::styled(
+            hint,
+            Style::default().fg(Color::Gray),
+        )));
+    }
+
+    let title = calendar.cursor.format("%B %Y").to_string();
+    let popup_area = centered_rect(36, lines.len() as u16 + 2, area);
+    let paragraph = Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .block(create_advanced_block(title, color, Alignment::Center));
+    rect.render_widget(Clear, popup_area);
+    rect.render_widget(paragraph, popup_area);
+}
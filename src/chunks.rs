@@ -1,90 +1,88 @@
-use tui::layout::{Rect, Layout, Direction, Constraint};
+use tui::layout::{Constraint, Direction, Layout, Rect};
 
+use crate::{config::LayoutConfig, task::TASK_FIELDS};
+
+/// Rows of the add task form, three for each field.
+pub const TASK_FORM_HEIGHT: u16 = TASK_FIELDS.len() as u16 * 3;
+pub const PROJECT_FORM_HEIGHT: u16 = 3;
+
+/// Where each part of the TUI goes, computed from the terminal size.
 pub struct Chunks {
-        pub menu_or_keybinds: Vec<Rect>,
-        pub projects_or_tasks: Vec<Rect>,
-        pub bottom_fullscreen: Vec<Rect>,
-        pub tasks_with_add_task: Vec<Rect>,
-        pub project_with_add_project: Vec<Rect>,
-        pub add_project_with_projects: Vec<Rect>,
+    pub menu_tabs: Rect,
+    /// None when the terminal is too narrow to show the key bindings.
+    pub key_tabs: Option<Rect>,
+    /// The project list, None while the sidebar is collapsed.
+    pub sidebar: Option<Rect>,
+    /// The task table or board, None while the project list fills the body.
+    pub main: Option<Rect>,
+    /// Everything below the tabs, for the home and import screens.
+    pub body: Rect,
+    pub status_bar: Rect,
 }
 
 impl Chunks {
-    pub fn create_chunks(size: Rect) -> Chunks {
-    let top_bottom_split = Layout::default()
-        .direction(Direction::Vertical)
-        .margin(2)
-        .constraints([Constraint::Length(3), Constraint::Min(2)].as_ref())
-        .split(size);
-
-    let bottom_fullscreen = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Length(75), Constraint::Length(40)])
-        .split(top_bottom_split[1]);
-
-    let projects_section_with_add_project_widget = Layout::default()
-        .direction(Direction::Vertical)
-        .margin(2)
-        .constraints([Constraint::Length(6), Constraint::Min(50)].as_ref())
-        .split(size);
-
-    let task_selection_with_add_task_widget = Layout::default()
-        .direction(Direction::Vertical)
-        .margin(2)
-        .constraints([Constraint::Length(18), Constraint::Min(50)].as_ref())
-        .split(size);
-
-    let add_project_section_with_projects_widget = Layout::default()
-        .direction(Direction::Vertical)
-        .margin(2)
-        .constraints(
-            [
+    /// Splits `size` into a sidebar and a main pane side by side. Below the
+    /// `collapse_below` width only the focused one of them is shown.
+    pub fn create_chunks(size: Rect, layout: &LayoutConfig, sidebar_focused: bool) -> Chunks {
+        let collapsed = size.width < layout.collapse_below;
+        let margin = if collapsed { 0 } else { 1 };
+        let top_bottom = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(margin)
+            .constraints([
                 Constraint::Length(3),
-                Constraint::Length(3),
-                Constraint::Min(50),
-            ]
-            .as_ref(),
-        )
-        .split(size);
-
-    let constraints = [
-        Constraint::Length(30),
-        Constraint::Length(45),
-        Constraint::Length(10),
-    ];
-
-    let menu_or_keybinds = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints(constraints)
-        .split(top_bottom_split[0]);
-
-    let projects_or_tasks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints(constraints)
-        .split(top_bottom_split[1]);
-
-    let project_with_add_project = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints(constraints)
-        .split(projects_section_with_add_project_widget[1]);
+                Constraint::Min(0),
+                Constraint::Length(1),
+            ])
+            .split(size);
+        let (top, body, status_bar) = (top_bottom[0], top_bottom[1], top_bottom[2]);
 
-    let add_project_with_projects = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints(constraints)
-        .split(add_project_section_with_projects_widget[1]);
+        if collapsed {
+            let (sidebar, main) = if sidebar_focused {
+                (Some(body), None)
+            } else {
+                (None, Some(body))
+            };
+            return Chunks {
+                menu_tabs: top,
+                key_tabs: None,
+                sidebar,
+                main,
+                body,
+                status_bar,
+            };
+        }
 
-    let tasks_with_add_task = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints(constraints)
-        .split(task_selection_with_add_task_widget[1]);
+        let sidebar_percent = layout.sidebar_percent.clamp(10, 90);
+        let constraints = [
+            Constraint::Percentage(sidebar_percent),
+            Constraint::Percentage(100 - sidebar_percent),
+        ];
+        let tabs = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(constraints)
+            .split(top);
+        let panes = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(constraints)
+            .split(body);
 
-    Chunks {
-        menu_or_keybinds,
-        projects_or_tasks,
-        bottom_fullscreen,
-        tasks_with_add_task,
-        project_with_add_project,
-        add_project_with_projects,
-    }
+        Chunks {
+            menu_tabs: tabs[0],
+            key_tabs: Some(tabs[1]),
+            sidebar: Some(panes[0]),
+            main: Some(panes[1]),
+            body,
+            status_bar,
+        }
     }
 }
+
+/// Splits `area` into a form of `height` rows and what is left below it.
+pub fn split_form(area: Rect, height: u16) -> (Rect, Rect) {
+    let parts = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(height), Constraint::Min(0)])
+        .split(area);
+    (parts[0], parts[1])
+}
@@ -0,0 +1,495 @@
+use std::path::PathBuf;
+
+use crate::{
+    api::{get_labels, get_projects, get_sections, get_tasks},
+    config::Config,
+    database::Database,
+    date::Zone,
+    export::{export, ExportFormat, DEFAULT_EXPORT_DIR},
+    ics::{write_ics, DEFAULT_ICS_FILE},
+    import::{preview_lines, read_plan, run_import, ImportFormat, ImportState},
+    output::{print_rows, OutputFormat},
+    sort::{sort_projects, sort_tasks},
+    taskwarrior::{apply_sync, plan_sync, SyncMap, Taskwarrior, DEFAULT_MAP_FILE},
+};
+
+pub const USAGE: &str = "Usage:
+    todoist-tui                      start the terminal interface
+    todoist-tui <token>              save the API token to Config.toml and start
+    todoist-tui list [options]       print all active tasks
+    todoist-tui projects [options]   print all projects
+    todoist-tui export [options]     write a backup of all projects and tasks
+    todoist-tui import <file> [opts] create the tasks of a todo.txt, CSV or JSON file
+    todoist-tui ics [--output file]  write dated tasks as a calendar (default: todoist.ics)
+    todoist-tui taskwarrior [opts]   sync tasks both ways with Taskwarrior
+
+Options for list and projects:
+    --format <table|json|tsv>        output format (default: table)
+    --columns <a,b,...>              fields to print, nested fields as due.date
+    --project <id|name>              only tasks of this project (list only)
+
+Options for export:
+    --format <json|csv|markdown>     archive, or one file per project (default: json)
+    --output <dir>                   target directory (default: export)
+
+Options for import:
+    --format <todotxt|csv|json>      input format (default: from the file extension)
+    --dry-run                        print what would be created
+    --yes                            import without the interactive preview
+
+Options for taskwarrior:
+    --data <dir>                     Taskwarrior data directory (default: TASKDATA)
+    --map <file>                     id mapping between runs (default: taskwarrior-sync.json)
+    --dry-run                        print the changes without making them
+
+Exit codes:
+    0  success
+    1  the Todoist API request or reading or writing a file failed
+    2  invalid arguments";
+
+/// Exit code for invalid arguments, see `USAGE`.
+pub const USAGE_EXIT_CODE: i32 = 2;
+
+const TASK_COLUMNS: [&str; 6] = ["id", "project_id", "content", "priority", "due.date", "labels"];
+const PROJECT_COLUMNS: [&str; 4] = ["id", "name", "parent_id", "is_favorite"];
+
+pub enum Command {
+    Tui(Option<String>),
+    Help,
+    List(ListOptions),
+    Projects(ListOptions),
+    Export(ExportOptions),
+    Import(ImportOptions),
+    Ics(PathBuf),
+    Taskwarrior(TaskwarriorOptions),
+}
+
+#[derive(Default)]
+pub struct ListOptions {
+    pub format: OutputFormat,
+    pub columns: Option<Vec<String>>,
+    pub project: Option<String>,
+}
+
+pub struct ExportOptions {
+    pub format: ExportFormat,
+    pub output: PathBuf,
+}
+
+pub struct ImportOptions {
+    pub file: PathBuf,
+    pub format: Option<ImportFormat>,
+    pub dry_run: bool,
+    pub yes: bool,
+}
+
+impl ImportOptions {
+    /// Without `--dry-run` or `--yes` the preview is shown in the interface.
+    pub fn interactive(&self) -> bool {
+        !self.dry_run && !self.yes
+    }
+}
+
+pub struct TaskwarriorOptions {
+    pub data_dir: Option<PathBuf>,
+    pub map: PathBuf,
+    pub dry_run: bool,
+}
+
+pub fn parse_args(args: Vec<String>) -> Result<Command, String> {
+    let mut args = args.into_iter();
+    let command = match args.next() {
+        None => return Ok(Command::Tui(None)),
+        Some(command) => command,
+    };
+
+    match command.as_str() {
+        "help" | "-h" | "--help" => Ok(Command::Help),
+        "list" => Ok(Command::List(parse_list_options(args, true)?)),
+        "projects" => Ok(Command::Projects(parse_list_options(args, false)?)),
+        "export" => Ok(Command::Export(parse_export_options(args)?)),
+        "import" => Ok(Command::Import(parse_import_options(args)?)),
+        "ics" => Ok(Command::Ics(parse_ics_options(args)?)),
+        "taskwarrior" => Ok(Command::Taskwarrior(parse_taskwarrior_options(args)?)),
+        _ if command.starts_with('-') => Err(format!("unknown option '{}'", command)),
+        _ => match args.next() {
+            None => Ok(Command::Tui(Some(command))),
+            Some(extra) => Err(format!("unexpected argument '{}'", extra)),
+        },
+    }
+}
+
+/// The message and exit code for arguments `parse_args` rejected.
+pub fn usage_error(err: &str) -> (String, i32) {
+    (format!("error: {}\n\n{}", err, USAGE), USAGE_EXIT_CODE)
+}
+
+/// Splits `--flag value` and `--flag=value` arguments into pairs. Flags
+/// listed in `switches` take no value.
+fn parse_flags(
+    mut args: impl Iterator<Item = String>,
+    switches: &[&str],
+) -> Result<Vec<(String, String)>, String> {
+    let mut flags = vec![];
+
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            return Err(format!("unexpected argument '{}'", arg));
+        }
+        let (flag, value) = match arg.split_once('=') {
+            Some((flag, value)) => (flag.to_owned(), value.to_owned()),
+            None if switches.contains(&arg.as_str()) => (arg, String::new()),
+            None => {
+                let value = args
+                    .next()
+                    .ok_or_else(|| format!("'{}' expects a value", arg))?;
+                (arg, value)
+            }
+        };
+        flags.push((flag, value));
+    }
+
+    Ok(flags)
+}
+
+fn parse_list_options(
+    args: impl Iterator<Item = String>,
+    allow_project: bool,
+) -> Result<ListOptions, String> {
+    let mut options = ListOptions::default();
+
+    for (flag, value) in parse_flags(args, &[])? {
+        match flag.as_str() {
+            "--format" => options.format = value.parse()?,
+            "--columns" => {
+                let columns: Vec<String> = value
+                    .split(',')
+                    .map(|column| column.trim().to_owned())
+                    .filter(|column| !column.is_empty())
+                    .collect();
+                if columns.is_empty() {
+                    return Err("'--columns' expects at least one column".to_owned());
+                }
+                options.columns = Some(columns);
+            }
+            "--project" if allow_project => options.project = Some(value),
+            _ => return Err(format!("unknown option '{}'", flag)),
+        }
+    }
+
+    Ok(options)
+}
+
+fn parse_export_options(args: impl Iterator<Item = String>) -> Result<ExportOptions, String> {
+    let mut options = ExportOptions {
+        format: ExportFormat::Json,
+        output: PathBuf::from(DEFAULT_EXPORT_DIR),
+    };
+
+    for (flag, value) in parse_flags(args, &[])? {
+        match flag.as_str() {
+            "--format" => options.format = value.parse()?,
+            "--output" => options.output = PathBuf::from(value),
+            _ => return Err(format!("unknown option '{}'", flag)),
+        }
+    }
+
+    Ok(options)
+}
+
+fn parse_import_options(mut args: impl Iterator<Item = String>) -> Result<ImportOptions, String> {
+    let file = match args.next() {
+        Some(file) if !file.starts_with("--") => PathBuf::from(file),
+        _ => return Err("'import' expects a file".to_owned()),
+    };
+    let mut options = ImportOptions {
+        file,
+        format: None,
+        dry_run: false,
+        yes: false,
+    };
+
+    for (flag, value) in parse_flags(args, &["--dry-run", "--yes"])? {
+        match flag.as_str() {
+            "--format" => options.format = Some(value.parse()?),
+            "--dry-run" => options.dry_run = true,
+            "--yes" => options.yes = true,
+            _ => return Err(format!("unknown option '{}'", flag)),
+        }
+    }
+
+    Ok(options)
+}
+
+fn parse_ics_options(args: impl Iterator<Item = String>) -> Result<PathBuf, String> {
+    let mut output = PathBuf::from(DEFAULT_ICS_FILE);
+
+    for (flag, value) in parse_flags(args, &[])? {
+        match flag.as_str() {
+            "--output" => output = PathBuf::from(value),
+            _ => return Err(format!("unknown option '{}'", flag)),
+        }
+    }
+
+    Ok(output)
+}
+
+fn parse_taskwarrior_options(
+    args: impl Iterator<Item = String>,
+) -> Result<TaskwarriorOptions, String> {
+    let mut options = TaskwarriorOptions {
+        data_dir: None,
+        map: PathBuf::from(DEFAULT_MAP_FILE),
+        dry_run: false,
+    };
+
+    for (flag, value) in parse_flags(args, &["--dry-run"])? {
+        match flag.as_str() {
+            "--data" => options.data_dir = Some(PathBuf::from(value)),
+            "--map" => options.map = PathBuf::from(value),
+            "--dry-run" => options.dry_run = true,
+            _ => return Err(format!("unknown option '{}'", flag)),
+        }
+    }
+
+    Ok(options)
+}
+
+/// Runs a non-interactive command and returns the process exit code.
+pub async fn run(command: Command, config: &Config) -> i32 {
+    let result = match command {
+        Command::List(options) => list_tasks(options, config).await,
+        Command::Projects(options) => list_projects(options, config).await,
+        Command::Export(options) => export_database(options, config).await,
+        Command::Import(options) => import_file(options, config).await,
+        Command::Ics(output) => write_calendar(output, config).await,
+        Command::Taskwarrior(options) => sync_taskwarrior(options, config).await,
+        Command::Tui(_) | Command::Help => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+    };
+
+    match result {
+        Ok(()) => 0,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            1
+        }
+    }
+}
+
+async fn list_tasks(options: ListOptions, config: &Config) -> Result<(), anyhow::Error> {
+    let mut tasks = get_tasks(config.token.clone()).await?;
+
+    if let Some(project) = &options.project {
+        let projects = get_projects(config.token.clone()).await?;
+        let project_id = projects
+            .iter()
+            .find(|p| &p.id == project || &p.name == project)
+            .map(|p| p.id.clone())
+            .ok_or_else(|| anyhow::anyhow!("no project named '{}'", project))?;
+        tasks.retain(|task| task.project_id == project_id);
+    }
+
+    sort_tasks(&mut tasks);
+    print_rows(&tasks, &options.columns_or(&TASK_COLUMNS), options.format)
+}
+
+async fn list_projects(options: ListOptions, config: &Config) -> Result<(), anyhow::Error> {
+    let mut projects = get_projects(config.token.clone()).await?;
+    sort_projects(&mut projects);
+    print_rows(&projects, &options.columns_or(&PROJECT_COLUMNS), options.format)
+}
+
+async fn export_database(options: ExportOptions, config: &Config) -> Result<(), anyhow::Error> {
+    let database = fetch_database(config).await?;
+    for path in export(&database, options.format, &options.output)? {
+        println!("{}", path.display());
+    }
+    Ok(())
+}
+
+async fn write_calendar(output: PathBuf, config: &Config) -> Result<(), anyhow::Error> {
+    let tasks = get_tasks(config.token.clone()).await?;
+    write_ics(&tasks, &output)?;
+    println!("{}", output.display());
+    Ok(())
+}
+
+async fn sync_taskwarrior(
+    options: TaskwarriorOptions,
+    config: &Config,
+) -> Result<(), anyhow::Error> {
+    let taskwarrior = Taskwarrior {
+        data_dir: options.data_dir,
+        taskrc: None,
+        zone: Zone::Local,
+    };
+    let mut map = SyncMap::load(&options.map)?;
+    let (tasks, projects) = tokio::try_join!(
+        get_tasks(config.token.clone()),
+        get_projects(config.token.clone()),
+    )?;
+    let tw_tasks = taskwarrior.export()?;
+
+    let actions = plan_sync(&tasks, &projects, &tw_tasks, &map, taskwarrior.zone);
+    for action in &actions {
+        println!("{}", action.describe());
+    }
+    if options.dry_run {
+        return Ok(());
+    }
+
+    let result = apply_sync(config.token.clone(), &taskwarrior, &actions, &mut map).await;
+    map.save(&options.map)?;
+    result
+}
+
+async fn import_file(options: ImportOptions, config: &Config) -> Result<(), anyhow::Error> {
+    let plan = read_plan(&options.file, options.format)?;
+    let state = ImportState::load(&plan.state_path());
+
+    if options.dry_run {
+        for (_, line) in preview_lines(&plan, &state) {
+            println!("{}", line);
+        }
+        return Ok(());
+    }
+
+    run_import(config.token.clone(), &plan, state, |progress| {
+        eprint!("\rImporting {}/{}", progress.done, progress.total);
+        if progress.finished {
+            eprintln!();
+        }
+    })
+    .await?;
+    println!(
+        "Imported {} tasks, progress is kept in {}",
+        plan.task_count(),
+        plan.state_path().display()
+    );
+    Ok(())
+}
+
+pub async fn fetch_database(config: &Config) -> Result<Database, anyhow::Error> {
+    let token = config.token.clone();
+    let (projects, sections, tasks, labels) = tokio::try_join!(
+        get_projects(token.clone()),
+        get_sections(token.clone()),
+        get_tasks(token.clone()),
+        get_labels(token),
+    )?;
+
+    Ok(Database::new(projects, sections, tasks, labels))
+}
+
+impl ListOptions {
+    /// Returns the requested columns, or `None` for JSON without `--columns`
+    /// so that whole objects are printed.
+    fn columns_or(&self, default: &[&str]) -> Option<Vec<String>> {
+        match (&self.columns, self.format) {
+            (Some(columns), _) => Some(columns.clone()),
+            (None, OutputFormat::Json) => None,
+            (None, _) => Some(default.iter().map(|c| c.to_string()).collect()),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use tui::style::Color;
+
+    use super::*;
+    use crate::{config::LayoutConfig, date::DateConfig};
+
+    fn parse(args: &[&str]) -> Result<Command, String> {
+        parse_args(args.iter().map(|arg| arg.to_string()).collect())
+    }
+
+    #[test]
+    fn parses_commands() {
+        assert!(matches!(parse(&[]), Ok(Command::Tui(None))));
+        assert!(matches!(parse(&["abc123"]), Ok(Command::Tui(Some(token))) if token == "abc123"));
+        assert!(matches!(parse(&["--help"]), Ok(Command::Help)));
+
+        match parse(&["list", "--format", "json", "--columns=id, due.date", "--project", "Work"]) {
+            Ok(Command::List(options)) => {
+                assert_eq!(options.format, OutputFormat::Json);
+                assert_eq!(options.columns, Some(vec!["id".to_owned(), "due.date".to_owned()]));
+                assert_eq!(options.project.as_deref(), Some("Work"));
+            }
+            _ => panic!("expected list"),
+        }
+        match parse(&["export", "--format", "md", "--output", "backup"]) {
+            Ok(Command::Export(options)) => {
+                assert_eq!(options.format, ExportFormat::Markdown);
+                assert_eq!(options.output, PathBuf::from("backup"));
+            }
+            _ => panic!("expected export"),
+        }
+        match parse(&["import", "todo.txt", "--dry-run", "--format", "csv"]) {
+            Ok(Command::Import(options)) => {
+                assert_eq!(options.file, PathBuf::from("todo.txt"));
+                assert_eq!(options.format, Some(ImportFormat::Csv));
+                assert!(options.dry_run && !options.yes && !options.interactive());
+            }
+            _ => panic!("expected import"),
+        }
+        assert!(matches!(parse(&["ics"]), Ok(Command::Ics(path)) if path.as_os_str() == DEFAULT_ICS_FILE));
+        match parse(&["taskwarrior", "--data", "tw", "--dry-run"]) {
+            Ok(Command::Taskwarrior(options)) => {
+                assert_eq!(options.data_dir, Some(PathBuf::from("tw")));
+                assert_eq!(options.map, PathBuf::from(DEFAULT_MAP_FILE));
+                assert!(options.dry_run);
+            }
+            _ => panic!("expected taskwarrior"),
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_arguments() {
+        let cases: [(&[&str], &str); 9] = [
+            (&["--bogus"], "unknown option '--bogus'"),
+            (&["abc123", "extra"], "unexpected argument 'extra'"),
+            (&["list", "--format", "xml"], "unknown format 'xml', expected table, json or tsv"),
+            (&["list", "--columns"], "'--columns' expects a value"),
+            (&["list", "--columns", " , "], "'--columns' expects at least one column"),
+            (&["list", "stray"], "unexpected argument 'stray'"),
+            (&["projects", "--project", "Work"], "unknown option '--project'"),
+            (&["import"], "'import' expects a file"),
+            (&["taskwarrior", "--yes"], "'--yes' expects a value"),
+        ];
+        for (args, expected) in cases {
+            match parse(args) {
+                Err(err) => assert_eq!(err, expected, "{:?}", args),
+                Ok(_) => panic!("{:?} was accepted", args),
+            }
+        }
+
+        let (message, code) = usage_error("unknown option '--bogus'");
+        assert_eq!(code, 2);
+        assert!(message.starts_with("error: unknown option '--bogus'\n\nUsage:"));
+    }
+
+    #[tokio::test]
+    async fn failing_commands_exit_with_1() {
+        let config = Config {
+            token: String::new(),
+            color: Color::Blue,
+            ics_path: None,
+            layout: LayoutConfig::default(),
+            refresh_interval: None,
+            dates: DateConfig::default(),
+        };
+        let options = ImportOptions {
+            file: PathBuf::from("/nonexistent/todo.txt"),
+            format: None,
+            dry_run: true,
+            yes: false,
+        };
+        assert_eq!(run(Command::Import(options), &config).await, 1);
+        assert_eq!(run(Command::Help, &config).await, 0);
+    }
+}
@@ -1,20 +1,59 @@
 use std::fs;
 use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
 use std::{fs::File, process::exit};
 
+use chrono::format::{Item, StrftimeItems};
+use chrono_tz::Tz;
 use serde_derive::{Deserialize, Serialize};
 use tui::style::Color;
 
+use crate::date::{DateConfig, Zone, DEFAULT_DATE_FORMAT, DEFAULT_TIME_FORMAT};
+
+const DEFAULT_REFRESH_SECS: u64 = 60;
+
 #[derive(Deserialize, Serialize, Debug)]
 pub struct RawConfig {
     pub token: String,
     pub color: String,
     pub sec_color: String,
+    pub ics_path: Option<String>,
+    /// Share of the width taken by the project list, in percent.
+    pub sidebar_percent: Option<u16>,
+    /// Terminals narrower than this show one pane at a time.
+    pub collapse_below: Option<u16>,
+    /// Seconds between background refreshes, 0 turns them off.
+    pub refresh_interval: Option<u64>,
+    /// IANA name like `Europe/Berlin`, the system timezone when unset.
+    pub timezone: Option<String>,
+    /// strftime formats of due dates and times.
+    pub date_format: Option<String>,
+    pub time_format: Option<String>,
 }
 
 pub struct Config {
     pub token: String,
     pub color: Color,
+    pub ics_path: Option<PathBuf>,
+    pub layout: LayoutConfig,
+    /// None when background refreshes are off.
+    pub refresh_interval: Option<Duration>,
+    pub dates: DateConfig,
+}
+
+pub struct LayoutConfig {
+    pub sidebar_percent: u16,
+    pub collapse_below: u16,
+}
+
+impl Default for LayoutConfig {
+    fn default() -> Self {
+        Self {
+            sidebar_percent: 30,
+            collapse_below: 80,
+        }
+    }
 }
 
 impl Config {
@@ -30,23 +69,63 @@ impl Config {
         Config {
             token: config.token,
             color,
+            ics_path: config.ics_path.map(PathBuf::from),
+            dates: DateConfig {
+                zone: match config.timezone {
+                    Some(name) => match name.parse::<Tz>() {
+                        Ok(tz) => Zone::Named(tz),
+                        Err(_) => {
+                            eprintln!("Unknown timezone '{}' in Config.toml", name);
+                            exit(1);
+                        }
+                    },
+                    None => Zone::Local,
+                },
+                date_format: checked_format(config.date_format, DEFAULT_DATE_FORMAT),
+                time_format: checked_format(config.time_format, DEFAULT_TIME_FORMAT),
+            },
+            refresh_interval: match config.refresh_interval.unwrap_or(DEFAULT_REFRESH_SECS) {
+                0 => None,
+                secs => Some(Duration::from_secs(secs)),
+            },
+            layout: LayoutConfig {
+                sidebar_percent: config
+                    .sidebar_percent
+                    .unwrap_or(LayoutConfig::default().sidebar_percent),
+                collapse_below: config
+                    .collapse_below
+                    .unwrap_or(LayoutConfig::default().collapse_below),
+            },
         }
     }
 }
 
-use std::env;
+/// `format` when it is a valid strftime format, exits otherwise.
+fn checked_format(format: Option<String>, default: &str) -> String {
+    let format = format.unwrap_or_else(|| default.to_owned());
+    if StrftimeItems::new(&format).any(|item| item == Item::Error) {
+        eprintln!("Invalid date format '{}' in Config.toml", format);
+        exit(1);
+    }
+    format
+}
 
-pub fn get_config() -> Config {
+pub fn get_config(token: Option<String>) -> Config {
     let filename = "Config.toml";
-    let mut args = env::args();
-    args.next();
 
-    if let Some(argument) = args.next() {
+    if let Some(token) = token {
         let mut file = File::create(filename).unwrap();
         let config = RawConfig {
-            token: argument,
+            token,
             color: "210, 39, 48".to_string(),
             sec_color: "210, 211, 212".to_string(),
+            ics_path: None,
+            sidebar_percent: None,
+            collapse_below: None,
+            refresh_interval: None,
+            timezone: None,
+            date_format: None,
+            time_format: None,
         };
         let content = toml::to_string_pretty(&config);
         file.write_all(content.unwrap().as_bytes()).unwrap();
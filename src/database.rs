@@ -0,0 +1,391 @@
+use std::collections::HashMap;
+
+use crate::{
+    api::{Label, Project, Section, Task},
+    sync::Placement,
+};
+
+/// Positions into `Database::tasks` or `Database::sections`, grouped by a key.
+type TaskIndex = HashMap<String, Vec<usize>>;
+
+/// Everything loaded from Todoist, with lookups by id, the sections of each
+/// project and per-project, per-section and per-parent task indices that are
+/// kept in sync on every change.
+#[derive(Clone, Debug)]
+pub struct Database {
+    projects: Vec<Project>,
+    sections: Vec<Section>,
+    tasks: Vec<Task>,
+    labels: Vec<Label>,
+    project_positions: HashMap<String, usize>,
+    section_positions: HashMap<String, usize>,
+    sections_by_project: TaskIndex,
+    task_positions: HashMap<String, usize>,
+    tasks_by_project: TaskIndex,
+    tasks_by_section: TaskIndex,
+    tasks_by_parent: TaskIndex,
+}
+
+impl Default for Database {
+    fn default() -> Self {
+        Database::new(vec![Project::name("Loading...")], vec![], vec![], vec![])
+    }
+}
+
+impl Database {
+    pub fn new(
+        projects: Vec<Project>,
+        sections: Vec<Section>,
+        tasks: Vec<Task>,
+        labels: Vec<Label>,
+    ) -> Database {
+        let mut database = Database {
+            projects: vec![],
+            sections: vec![],
+            tasks: vec![],
+            labels,
+            project_positions: HashMap::new(),
+            section_positions: HashMap::new(),
+            sections_by_project: HashMap::new(),
+            task_positions: HashMap::new(),
+            tasks_by_project: HashMap::new(),
+            tasks_by_section: HashMap::new(),
+            tasks_by_parent: HashMap::new(),
+        };
+        database.set_projects(projects);
+        database.set_sections(sections);
+        database.set_tasks(tasks);
+        database
+    }
+
+    pub fn projects(&self) -> &[Project] {
+        &self.projects
+    }
+
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    pub fn tasks(&self) -> &[Task] {
+        &self.tasks
+    }
+
+    pub fn labels(&self) -> &[Label] {
+        &self.labels
+    }
+
+    pub fn project(&self, id: &str) -> Option<&Project> {
+        self.project_positions.get(id).map(|&i| &self.projects[i])
+    }
+
+    pub fn project_position(&self, id: &str) -> Option<usize> {
+        self.project_positions.get(id).copied()
+    }
+
+    pub fn section(&self, id: &str) -> Option<&Section> {
+        self.section_positions.get(id).map(|&i| &self.sections[i])
+    }
+
+    pub fn task(&self, id: &str) -> Option<&Task> {
+        self.task_positions.get(id).map(|&i| &self.tasks[i])
+    }
+
+    fn indexed<'a>(
+        &'a self,
+        index: &'a TaskIndex,
+        key: &str,
+    ) -> impl Iterator<Item = &'a Task> + 'a {
+        let positions = index.get(key).map_or(&[][..], Vec::as_slice);
+        positions.iter().map(move |&i| &self.tasks[i])
+    }
+
+    /// Sections of a project, in section order.
+    pub fn project_sections<'a>(
+        &'a self,
+        project_id: &str,
+    ) -> impl Iterator<Item = &'a Section> + 'a {
+        let positions = self
+            .sections_by_project
+            .get(project_id)
+            .map_or(&[][..], Vec::as_slice);
+        positions.iter().map(move |&i| &self.sections[i])
+    }
+
+    /// Tasks of a project, in no particular order.
+    pub fn project_tasks<'a>(&'a self, project_id: &str) -> impl Iterator<Item = &'a Task> + 'a {
+        self.indexed(&self.tasks_by_project, project_id)
+    }
+
+    pub fn project_task_count(&self, project_id: &str) -> usize {
+        self.tasks_by_project.get(project_id).map_or(0, Vec::len)
+    }
+
+    pub fn section_tasks<'a>(&'a self, section_id: &str) -> impl Iterator<Item = &'a Task> + 'a {
+        self.indexed(&self.tasks_by_section, section_id)
+    }
+
+    pub fn subtasks<'a>(&'a self, parent_id: &str) -> impl Iterator<Item = &'a Task> + 'a {
+        self.indexed(&self.tasks_by_parent, parent_id)
+    }
+
+    pub fn set_projects(&mut self, projects: Vec<Project>) {
+        self.projects = projects;
+        self.project_positions = positions(&self.projects, |project| &project.id);
+    }
+
+    pub fn set_sections(&mut self, sections: Vec<Section>) {
+        self.sections = sections;
+        self.section_positions = positions(&self.sections, |section| &section.id);
+        self.sections_by_project.clear();
+        for (i, section) in self.sections.iter().enumerate() {
+            self.sections_by_project
+                .entry(section.project_id.clone())
+                .or_default()
+                .push(i);
+        }
+        for positions in self.sections_by_project.values_mut() {
+            positions.sort_by_key(|&i| self.sections[i].order);
+        }
+    }
+
+    pub fn set_labels(&mut self, labels: Vec<Label>) {
+        self.labels = labels;
+    }
+
+    pub fn set_tasks(&mut self, tasks: Vec<Task>) {
+        self.tasks = tasks;
+        self.task_positions = positions(&self.tasks, |task| &task.id);
+        self.tasks_by_project.clear();
+        self.tasks_by_section.clear();
+        self.tasks_by_parent.clear();
+        for i in 0..self.tasks.len() {
+            self.index_task(i);
+        }
+    }
+
+    /// The task indices together with the key the task at `i` has in each.
+    fn task_indices(&mut self, i: usize) -> [(&mut TaskIndex, Option<&String>); 3] {
+        let task = &self.tasks[i];
+        [
+            (&mut self.tasks_by_project, Some(&task.project_id)),
+            (&mut self.tasks_by_section, task.section_id.as_ref()),
+            (&mut self.tasks_by_parent, task.parent_id.as_ref()),
+        ]
+    }
+
+    fn index_task(&mut self, i: usize) {
+        for (index, key) in self.task_indices(i) {
+            if let Some(key) = key {
+                index.entry(key.clone()).or_default().push(i);
+            }
+        }
+    }
+
+    fn unindex_task(&mut self, i: usize) {
+        for (index, key) in self.task_indices(i) {
+            if let Some(key) = key {
+                if let Some(positions) = index.get_mut(key) {
+                    positions.retain(|&position| position != i);
+                    if positions.is_empty() {
+                        index.remove(key);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Points the index entries of the task at `from` to `to`.
+    fn reindex_task(&mut self, from: usize, to: usize) {
+        for (index, key) in self.task_indices(from) {
+            let positions = key.and_then(|key| index.get_mut(key));
+            if let Some(position) = positions.and_then(|p| p.iter_mut().find(|p| **p == from)) {
+                *position = to;
+            }
+        }
+    }
+
+    pub fn push_project(&mut self, project: Project) {
+        self.project_positions
+            .insert(project.id.clone(), self.projects.len());
+        self.projects.push(project);
+    }
+
+    /// Removes a project together with its tasks.
+    pub fn remove_project(&mut self, id: &str) -> Option<Project> {
+        let position = self.project_positions.remove(id)?;
+        let task_ids: Vec<String> = self.project_tasks(id).map(|task| task.id.clone()).collect();
+        for task_id in task_ids {
+            self.remove_task(&task_id);
+        }
+        let project = self.projects.remove(position);
+        for i in self.project_positions.values_mut() {
+            if *i > position {
+                *i -= 1;
+            }
+        }
+        Some(project)
+    }
+
+    pub fn push_task(&mut self, task: Task) {
+        let i = self.tasks.len();
+        self.task_positions.insert(task.id.clone(), i);
+        self.tasks.push(task);
+        self.index_task(i);
+    }
+
+    /// Changes a task in place, reindexing it if it ends up elsewhere.
+    pub fn update_task(&mut self, id: &str, update: impl FnOnce(&mut Task)) {
+        let position = match self.task_positions.get(id) {
+            Some(&position) => position,
+            None => return,
+        };
+        let before = Placement::of(&self.tasks[position]);
+        update(&mut self.tasks[position]);
+        if Placement::of(&self.tasks[position]) != before {
+            if let Some(task) = self.remove_task(id) {
+                self.push_task(task);
+            }
+        }
+    }
+
+    /// Moves a task to `to`, taking its subtasks along.
+    pub fn move_task(&mut self, id: &str, to: &Placement) {
+        let mut ids = vec![id.to_owned()];
+        let mut i = 0;
+        while i < ids.len() {
+            let children: Vec<String> = self.subtasks(&ids[i]).map(|t| t.id.clone()).collect();
+            ids.extend(children);
+            i += 1;
+        }
+        for (i, task_id) in ids.iter().enumerate() {
+            if let Some(mut task) = self.remove_task(task_id) {
+                task.project_id = to.project_id.clone();
+                task.section_id = to.section_id.clone();
+                if i == 0 {
+                    task.parent_id = to.parent_id.clone();
+                }
+                self.push_task(task);
+            }
+        }
+    }
+
+    /// Removes a task, moving the last task into its place.
+    pub fn remove_task(&mut self, id: &str) -> Option<Task> {
+        let position = self.task_positions.remove(id)?;
+        self.unindex_task(position);
+        let last = self.tasks.len() - 1;
+        if position != last {
+            self.reindex_task(last, position);
+            self.task_positions
+                .insert(self.tasks[last].id.clone(), position);
+        }
+        Some(self.tasks.swap_remove(position))
+    }
+}
+
+fn positions<T>(items: &[T], id: impl Fn(&T) -> &String) -> HashMap<String, usize> {
+    items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| (id(item).clone(), i))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::TaskContent;
+
+    fn task(id: &str, project_id: &str, section_id: Option<&str>, parent_id: Option<&str>) -> Task {
+        let mut task = Task::temp(TaskContent::default(), project_id.to_owned());
+        task.id = id.to_owned();
+        task.section_id = section_id.map(str::to_owned);
+        task.parent_id = parent_id.map(str::to_owned);
+        task
+    }
+
+    fn project(id: &str) -> Project {
+        let mut project = Project::name(id);
+        project.id = id.to_owned();
+        project
+    }
+
+    fn section(id: &str, project_id: &str, order: usize) -> Section {
+        Section {
+            id: id.to_owned(),
+            project_id: project_id.to_owned(),
+            order,
+            name: id.to_owned(),
+        }
+    }
+
+    fn ids<'a>(tasks: impl Iterator<Item = &'a Task>) -> Vec<&'a str> {
+        let mut ids: Vec<&str> = tasks.map(|task| task.id.as_str()).collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    fn database() -> Database {
+        Database::new(
+            vec![project("a"), project("b")],
+            vec![
+                section("s2", "a", 2),
+                section("s1", "a", 1),
+                section("s3", "b", 1),
+            ],
+            vec![
+                task("1", "a", Some("s1"), None),
+                task("2", "a", Some("s1"), Some("1")),
+                task("3", "a", None, None),
+                task("4", "b", Some("s3"), None),
+                task("5", "a", Some("s2"), Some("1")),
+            ],
+            vec![],
+        )
+    }
+
+    #[test]
+    fn sections_are_listed_per_project_in_order() {
+        let database = database();
+        let sections: Vec<&str> = database
+            .project_sections("a")
+            .map(|s| s.id.as_str())
+            .collect();
+        assert_eq!(sections, ["s1", "s2"]);
+        assert_eq!(database.project_sections("c").count(), 0);
+    }
+
+    #[test]
+    fn removing_a_task_keeps_the_indices() {
+        let mut database = database();
+        assert_eq!(database.remove_task("2").unwrap().id, "2");
+        assert!(database.remove_task("2").is_none());
+
+        // "5" was the last task and took the place of "2"
+        assert_eq!(database.task("5").unwrap().id, "5");
+        assert_eq!(ids(database.project_tasks("a")), ["1", "3", "5"]);
+        assert_eq!(ids(database.section_tasks("s1")), ["1"]);
+        assert_eq!(ids(database.section_tasks("s2")), ["5"]);
+        assert_eq!(ids(database.subtasks("1")), ["5"]);
+
+        database.remove_task("5");
+        assert_eq!(ids(database.subtasks("1")), Vec::<&str>::new());
+        assert!(!database.tasks_by_parent.contains_key("1"));
+        assert_eq!(database.tasks().len(), 3);
+    }
+
+    #[test]
+    fn removing_a_project_removes_its_tasks() {
+        let mut database = database();
+        database.remove_project("a");
+
+        assert!(database.project("a").is_none());
+        assert_eq!(database.project("b").unwrap().id, "b");
+        assert_eq!(ids(database.tasks().iter()), ["4"]);
+        assert_eq!(database.project_task_count("a"), 0);
+        assert_eq!(ids(database.section_tasks("s1")), Vec::<&str>::new());
+        assert_eq!(ids(database.subtasks("1")), Vec::<&str>::new());
+        assert_eq!(ids(database.section_tasks("s3")), ["4"]);
+        assert_eq!(database.task("4").unwrap().id, "4");
+    }
+}
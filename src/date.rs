@@ -0,0 +1,199 @@
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use tui::style::{Color, Style};
+
+use crate::{
+    api::{Deadline, Due, DurationUnit, TaskDuration},
+    due_parser::{parse_due_string, ParsedDue},
+};
+
+pub const DEFAULT_DATE_FORMAT: &str = "%b %-d";
+pub const DEFAULT_TIME_FORMAT: &str = "%H:%M";
+
+/// The timezone dates are shown in.
+#[derive(Clone, Copy, Debug)]
+pub enum Zone {
+    Local,
+    Named(Tz),
+}
+
+impl Zone {
+    pub fn local_time(self, utc: DateTime<Utc>) -> NaiveDateTime {
+        match self {
+            Zone::Local => utc.with_timezone(&Local).naive_local(),
+            Zone::Named(tz) => utc.with_timezone(&tz).naive_local(),
+        }
+    }
+
+    /// The UTC time of `local`, the earlier one when clocks go back. None
+    /// for times skipped when clocks go forward.
+    pub fn utc(self, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+        let utc = match self {
+            Zone::Local => Local.from_local_datetime(&local).earliest()?.naive_utc(),
+            Zone::Named(tz) => tz.from_local_datetime(&local).earliest()?.naive_utc(),
+        };
+        Some(utc.and_utc())
+    }
+
+    pub fn now(self) -> NaiveDateTime {
+        self.local_time(Utc::now())
+    }
+}
+
+/// How due dates are shown, from `Config.toml`.
+#[derive(Clone, Debug)]
+pub struct DateConfig {
+    pub zone: Zone,
+    /// strftime format of dates more than a week away.
+    pub date_format: String,
+    pub time_format: String,
+}
+
+impl Default for DateConfig {
+    fn default() -> Self {
+        Self {
+            zone: Zone::Local,
+            date_format: DEFAULT_DATE_FORMAT.to_owned(),
+            time_format: DEFAULT_TIME_FORMAT.to_owned(),
+        }
+    }
+}
+
+/// When a task is due, in the timezone dates are shown in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DueAt {
+    Day(NaiveDate),
+    Time(NaiveDateTime),
+}
+
+impl DueAt {
+    pub fn date(self) -> NaiveDate {
+        match self {
+            DueAt::Day(date) => date,
+            DueAt::Time(datetime) => datetime.date(),
+        }
+    }
+
+    pub fn is_overdue(self, now: NaiveDateTime) -> bool {
+        match self {
+            DueAt::Day(date) => date < now.date(),
+            DueAt::Time(datetime) => datetime < now,
+        }
+    }
+}
+
+fn parse_datetime(text: &str, zone: Zone) -> Option<NaiveDateTime> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(text) {
+        return Some(zone.local_time(datetime.with_timezone(&Utc)));
+    }
+    // floating times have no offset and are the same in every timezone
+    NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f").ok()
+}
+
+/// Parses `due` into the timezone of `zone`. Times given in UTC are
+/// converted, floating ones and whole days are taken as they are.
+pub fn parse_due(due: &Due, zone: Zone) -> Option<DueAt> {
+    if let Some(datetime) = due
+        .datetime
+        .as_deref()
+        .and_then(|text| parse_datetime(text, zone))
+    {
+        return Some(DueAt::Time(datetime));
+    }
+    let date = due.date.as_deref()?;
+    match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+        Ok(date) => Some(DueAt::Day(date)),
+        // the Sync API puts floating times into `date`
+        Err(_) => parse_datetime(date, zone).map(DueAt::Time),
+    }
+}
+
+/// `due_at` relative to `now`, like "Tomorrow", "Mon 14:00" or "3 days
+/// overdue". Dates more than a week away use the configured format, with
+/// the year added when it is not the current one.
+pub fn format_due(due_at: DueAt, now: NaiveDateTime, config: &DateConfig) -> String {
+    let today = now.date();
+    let date = due_at.date();
+    let days = (date - today).num_days();
+    if days < 0 {
+        let unit = if days == -1 { "day" } else { "days" };
+        return format!("{} {} overdue", -days, unit);
+    }
+    let day = match days {
+        0 => "Today".to_owned(),
+        1 => "Tomorrow".to_owned(),
+        2..=6 => date.format("%a").to_string(),
+        _ if date.year() == today.year() => date.format(&config.date_format).to_string(),
+        _ => format!("{} {}", date.format(&config.date_format), date.format("%Y")),
+    };
+    match due_at {
+        DueAt::Day(_) => day,
+        DueAt::Time(datetime) => format!("{} {}", day, datetime.format(&config.time_format)),
+    }
+}
+
+/// What the add task form shows for a typed due string, like "Tomorrow,
+/// Tue Oct 20 2026" or "Repeats, first Fri 09:00". None for strings the
+/// local parser does not understand.
+pub fn preview_due_string(text: &str, now: NaiveDateTime, config: &DateConfig) -> Option<String> {
+    let parsed = parse_due_string(text, now)?;
+    let relative = format_due(parsed.due_at(), now, config);
+    Some(match parsed {
+        ParsedDue::Once(due_at) => {
+            format!("{}, {}", relative, due_at.date().format("%a %b %-d %Y"))
+        }
+        ParsedDue::Repeating(_) => format!("Repeats, first {}", relative),
+    })
+}
+
+/// `duration` the way it is typed into the add task form, like "1h 30m"
+/// or "2d".
+pub fn format_duration(duration: TaskDuration) -> String {
+    let amount = duration.amount;
+    match duration.unit {
+        DurationUnit::Day => format!("{}d", amount),
+        DurationUnit::Minute if amount < 60 => format!("{}m", amount),
+        DurationUnit::Minute if amount.is_multiple_of(60) => format!("{}h", amount / 60),
+        DurationUnit::Minute => format!("{}h {}m", amount / 60, amount % 60),
+    }
+}
+
+/// The text and style of a task's deadline, red once it has passed.
+pub fn deadline_label(deadline: &Deadline, config: &DateConfig) -> (String, Style) {
+    let now = config.zone.now();
+    match NaiveDate::parse_from_str(&deadline.date, "%Y-%m-%d") {
+        Ok(date) => {
+            let due_at = DueAt::Day(date);
+            let style = if due_at.is_overdue(now) {
+                Style::default().fg(Color::Red)
+            } else {
+                Style::default()
+            };
+            (format!("Deadline {}", format_due(due_at, now, config)), style)
+        }
+        Err(_) => (format!("Deadline {}", deadline.date), Style::default()),
+    }
+}
+
+/// The text and style of a task's due date, red when overdue and green
+/// when due today. Dues that cannot be parsed show their string.
+pub fn due_label(due: &Due, config: &DateConfig) -> Option<(String, Style)> {
+    let now = config.zone.now();
+    match parse_due(due, config.zone) {
+        Some(due_at) => {
+            let style = if due_at.is_overdue(now) {
+                Style::default().fg(Color::Red)
+            } else if due_at.date() == now.date() {
+                Style::default().fg(Color::Green)
+            } else {
+                Style::default()
+            };
+            Some((format_due(due_at, now, config), style))
+        }
+        None => due
+            .string
+            .as_ref()
+            .or(due.datetime.as_ref())
+            .map(|string| (string.clone(), Style::default())),
+    }
+}
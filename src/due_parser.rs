@@ -0,0 +1,482 @@
+use chrono::{Datelike, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, Weekday};
+
+use crate::{
+    api::{DurationUnit, TaskDuration},
+    date::DueAt,
+};
+
+/// What a typed due string means, worked out locally so the form can show
+/// it before Todoist gets to see it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParsedDue {
+    Once(DueAt),
+    /// A recurring due date and its first occurrence.
+    Repeating(DueAt),
+}
+
+impl ParsedDue {
+    pub fn due_at(self) -> DueAt {
+        match self {
+            ParsedDue::Once(due_at) | ParsedDue::Repeating(due_at) => due_at,
+        }
+    }
+}
+
+/// Parses the common Todoist phrases: `today`, `tomorrow`, `next monday`,
+/// `in 3 days`, `jan 5`, `2027-01-05`, `every other friday at 9` and the
+/// like. None when the string is something only Todoist understands.
+pub fn parse_due_string(text: &str, now: NaiveDateTime) -> Option<ParsedDue> {
+    let text = text.trim().to_lowercase().replace(',', " ");
+    let words: Vec<&str> = text.split_whitespace().collect();
+    if words.is_empty() {
+        return None;
+    }
+    let (words, time) = split_time(&words)?;
+    let today = now.date();
+    let (date, repeating) = match words {
+        ["every", rest @ ..] => (first_repeat(rest, today)?, true),
+        ["daily"] => (today, true),
+        ["weekly" | "monthly" | "yearly" | "annually"] => (today, true),
+        _ => (parse_date(words, today)?, false),
+    };
+    let due_at = match time {
+        Some(time) => DueAt::Time(date.and_time(time)),
+        None => DueAt::Day(date),
+    };
+    Some(if repeating {
+        ParsedDue::Repeating(due_at)
+    } else {
+        ParsedDue::Once(due_at)
+    })
+}
+
+/// A deadline is a day, so only strings meaning a single one will do.
+pub fn parse_deadline(text: &str, now: NaiveDateTime) -> Option<NaiveDate> {
+    match parse_due_string(text, now)? {
+        ParsedDue::Once(DueAt::Day(date)) => Some(date),
+        _ => None,
+    }
+}
+
+/// Parses durations like `45`, `30m`, `1h30m`, `2 hours` or `3d`. Todoist
+/// counts them in either minutes or days, so the two do not mix.
+pub fn parse_duration(text: &str) -> Option<TaskDuration> {
+    let text: String = text.to_lowercase().split_whitespace().collect();
+    if let Ok(amount) = text.parse::<u32>() {
+        return Some(TaskDuration {
+            amount,
+            unit: DurationUnit::Minute,
+        })
+        .filter(|_| amount > 0);
+    }
+    let (mut minutes, mut days) = (0u32, 0u32);
+    let mut rest = text.as_str();
+    while !rest.is_empty() {
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let amount: u32 = rest[..digits].parse().ok()?;
+        rest = &rest[digits..];
+        let letters = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_alphabetic()).len();
+        match &rest[..letters] {
+            "m" | "min" | "mins" | "minute" | "minutes" => minutes = minutes.checked_add(amount)?,
+            "h" | "hr" | "hrs" | "hour" | "hours" => {
+                minutes = minutes.checked_add(amount.checked_mul(60)?)?
+            }
+            "d" | "day" | "days" => days = days.checked_add(amount)?,
+            _ => return None,
+        }
+        rest = &rest[letters..];
+    }
+    let (amount, unit) = match (minutes, days) {
+        (0, 0) => return None,
+        (minutes, 0) => (minutes, DurationUnit::Minute),
+        (0, days) => (days, DurationUnit::Day),
+        _ => return None,
+    };
+    Some(TaskDuration { amount, unit })
+}
+
+/// Splits a trailing time off `words`, given as `at 9`, `at 9 pm`, `9:30`
+/// or `9pm`. None when `at` is followed by something else.
+fn split_time<'a>(words: &'a [&'a str]) -> Option<(&'a [&'a str], Option<NaiveTime>)> {
+    if let Some(at) = words.iter().rposition(|word| *word == "at") {
+        let time = parse_time(&words[at + 1..].concat())?;
+        return Some((&words[..at], Some(time)));
+    }
+    if let Some((last, rest)) = words.split_last() {
+        let looks_like_time = last.ends_with("am") || last.ends_with("pm") || last.contains(':');
+        if let Some(time) = parse_time(last).filter(|_| looks_like_time) {
+            return Some((rest, Some(time)));
+        }
+    }
+    Some((words, None))
+}
+
+fn parse_time(text: &str) -> Option<NaiveTime> {
+    match text {
+        "noon" => return NaiveTime::from_hms_opt(12, 0, 0),
+        "midnight" => return NaiveTime::from_hms_opt(0, 0, 0),
+        _ => {}
+    }
+    let (text, half) = if let Some(text) = text.strip_suffix("am") {
+        (text, Some(0))
+    } else if let Some(text) = text.strip_suffix("pm") {
+        (text, Some(12))
+    } else {
+        (text, None)
+    };
+    let (hour, minute) = match text.split_once(':') {
+        Some((hour, minute)) => (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?),
+        None => (text.parse::<u32>().ok()?, 0),
+    };
+    let hour = match half {
+        Some(half) if (1..=12).contains(&hour) => hour % 12 + half,
+        Some(_) => return None,
+        None => hour,
+    };
+    NaiveTime::from_hms_opt(hour, minute, 0)
+}
+
+fn parse_date(words: &[&str], today: NaiveDate) -> Option<NaiveDate> {
+    match words {
+        // a time alone is for today
+        [] | ["today" | "tod"] => Some(today),
+        ["tomorrow" | "tom" | "tmr"] => today.succ_opt(),
+        ["next", "week"] => Some(upcoming(today.succ_opt()?, Weekday::Mon)),
+        ["next", "month"] => today.with_day(1)?.checked_add_months(Months::new(1)),
+        ["next", "year"] => NaiveDate::from_ymd_opt(today.year() + 1, 1, 1),
+        ["next", day] => Some(upcoming(today.succ_opt()?, weekday(day)?)),
+        ["this", day] | [day] if weekday(day).is_some() => Some(upcoming(today, weekday(day)?)),
+        ["in", count, unit] => add(today, number(count)?, unit),
+        [iso] if iso.contains('-') => NaiveDate::parse_from_str(iso, "%Y-%m-%d").ok(),
+        _ => month_day(words, today),
+    }
+}
+
+/// Dates like `jan 5`, `5 january` or `jan 5th 2027`. Without a year the
+/// next such day is meant.
+fn month_day(words: &[&str], today: NaiveDate) -> Option<NaiveDate> {
+    let (month, day, year) = match words {
+        [first, second] | [first, second, _] if month(first).is_some() => {
+            (month(first)?, day_of_month(second)?, words.get(2))
+        }
+        [first, second] | [first, second, _] => {
+            (month(second)?, day_of_month(first)?, words.get(2))
+        }
+        _ => return None,
+    };
+    match year {
+        Some(year) => NaiveDate::from_ymd_opt(year.parse().ok()?, month, day),
+        None => {
+            let date = NaiveDate::from_ymd_opt(today.year(), month, day)?;
+            if date < today {
+                NaiveDate::from_ymd_opt(today.year() + 1, month, day)
+            } else {
+                Some(date)
+            }
+        }
+    }
+}
+
+/// The first occurrence of what follows `every`.
+fn first_repeat(words: &[&str], today: NaiveDate) -> Option<NaiveDate> {
+    match words {
+        ["day" | "week" | "month" | "year"] => Some(today),
+        ["other", "day" | "week" | "month" | "year"] => Some(today),
+        ["weekday" | "workday"] => {
+            let mut date = today;
+            while matches!(date.weekday(), Weekday::Sat | Weekday::Sun) {
+                date = date.succ_opt()?;
+            }
+            Some(date)
+        }
+        [count, unit] if number(count).is_some() && unit_of(unit).is_some() => Some(today),
+        ["other", day] => Some(upcoming(today, weekday(day)?)),
+        // `every 15th` is monthly on that day
+        [day] if day_of_month(day).is_some() => {
+            let day = day_of_month(day)?;
+            let mut month = today.with_day(1)?;
+            loop {
+                match month.with_day(day) {
+                    Some(date) if date >= today => return Some(date),
+                    _ => month = month.checked_add_months(Months::new(1))?,
+                }
+            }
+        }
+        // one or more weekdays, like `every mon fri`
+        days => days
+            .iter()
+            .map(|day| weekday(day).map(|weekday| upcoming(today, weekday)))
+            .collect::<Option<Vec<_>>>()?
+            .into_iter()
+            .min(),
+    }
+}
+
+/// The first `weekday` on or after `from`.
+fn upcoming(from: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let days = (7 + weekday.num_days_from_monday() - from.weekday().num_days_from_monday()) % 7;
+    from + TimeDelta::days(days as i64)
+}
+
+#[derive(Clone, Copy)]
+enum Unit {
+    Day,
+    Week,
+    Month,
+    Year,
+}
+
+fn unit_of(word: &str) -> Option<Unit> {
+    match word.trim_end_matches('s') {
+        "day" => Some(Unit::Day),
+        "week" => Some(Unit::Week),
+        "month" => Some(Unit::Month),
+        "year" => Some(Unit::Year),
+        _ => None,
+    }
+}
+
+fn add(date: NaiveDate, count: u32, unit: &str) -> Option<NaiveDate> {
+    match unit_of(unit)? {
+        Unit::Day => date.checked_add_signed(TimeDelta::days(count as i64)),
+        Unit::Week => date.checked_add_signed(TimeDelta::weeks(count as i64)),
+        Unit::Month => date.checked_add_months(Months::new(count)),
+        Unit::Year => date.checked_add_months(Months::new(count.checked_mul(12)?)),
+    }
+}
+
+fn number(word: &str) -> Option<u32> {
+    match word {
+        "a" | "an" | "one" => Some(1),
+        "two" => Some(2),
+        "three" => Some(3),
+        word => word.parse().ok(),
+    }
+}
+
+fn day_of_month(word: &str) -> Option<u32> {
+    let digits = word.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+    let suffix = &word[digits.len()..];
+    if !matches!(suffix, "" | "st" | "nd" | "rd" | "th") {
+        return None;
+    }
+    digits.parse().ok().filter(|day| (1..=31).contains(day))
+}
+
+fn weekday(word: &str) -> Option<Weekday> {
+    match word {
+        "mon" | "monday" => Some(Weekday::Mon),
+        "tue" | "tues" | "tuesday" => Some(Weekday::Tue),
+        "wed" | "wednesday" => Some(Weekday::Wed),
+        "thu" | "thur" | "thurs" | "thursday" => Some(Weekday::Thu),
+        "fri" | "friday" => Some(Weekday::Fri),
+        "sat" | "saturday" => Some(Weekday::Sat),
+        "sun" | "sunday" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+/// A month by its full name or first three letters.
+fn month(word: &str) -> Option<u32> {
+    let word = if word == "sept" { "sep" } else { word };
+    MONTHS
+        .iter()
+        .position(|month| *month == word || month[..3] == *word)
+        .map(|i| i as u32 + 1)
+}
+
+const MONTHS: [&str; 12] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn at(date: NaiveDate, hour: u32, minute: u32) -> NaiveDateTime {
+        date.and_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    fn once(date: NaiveDate) -> Option<ParsedDue> {
+        Some(ParsedDue::Once(DueAt::Day(date)))
+    }
+
+    fn once_at(date: NaiveDate, hour: u32, minute: u32) -> Option<ParsedDue> {
+        Some(ParsedDue::Once(DueAt::Time(at(date, hour, minute))))
+    }
+
+    fn repeating(date: NaiveDate) -> Option<ParsedDue> {
+        Some(ParsedDue::Repeating(DueAt::Day(date)))
+    }
+
+    fn repeating_at(date: NaiveDate, hour: u32, minute: u32) -> Option<ParsedDue> {
+        Some(ParsedDue::Repeating(DueAt::Time(at(date, hour, minute))))
+    }
+
+    #[test]
+    fn parses_due_strings() {
+        let monday = at(day(2026, 10, 19), 10, 0);
+        let sunday = at(day(2026, 10, 18), 10, 0);
+        let end_of_january = at(day(2027, 1, 31), 10, 0);
+        let cases = [
+            // relative days
+            ("today", monday, once(day(2026, 10, 19))),
+            ("Today", monday, once(day(2026, 10, 19))),
+            ("tod", monday, once(day(2026, 10, 19))),
+            ("tom", monday, once(day(2026, 10, 20))),
+            ("tomorrow", monday, once(day(2026, 10, 20))),
+            ("  tomorrow  ", monday, once(day(2026, 10, 20))),
+            ("next week", monday, once(day(2026, 10, 26))),
+            ("next week", sunday, once(day(2026, 10, 19))),
+            ("next month", monday, once(day(2026, 11, 1))),
+            ("next year", monday, once(day(2027, 1, 1))),
+            // weekdays
+            ("next monday", sunday, once(day(2026, 10, 19))),
+            ("next monday", monday, once(day(2026, 10, 26))),
+            ("monday", monday, once(day(2026, 10, 19))),
+            ("mon", sunday, once(day(2026, 10, 19))),
+            ("fri", monday, once(day(2026, 10, 23))),
+            ("this wed", monday, once(day(2026, 10, 21))),
+            ("sat", monday, once(day(2026, 10, 24))),
+            ("sunday", sunday, once(day(2026, 10, 18))),
+            // counted
+            ("in 3 days", monday, once(day(2026, 10, 22))),
+            ("in a day", monday, once(day(2026, 10, 20))),
+            ("in two weeks", monday, once(day(2026, 11, 2))),
+            ("in a week", monday, once(day(2026, 10, 26))),
+            ("in 1 month", end_of_january, once(day(2027, 2, 28))),
+            ("in 2 months", monday, once(day(2026, 12, 19))),
+            ("in 1 year", monday, once(day(2027, 10, 19))),
+            ("in 999999999 years", monday, None),
+            ("in 3 dayz", monday, None),
+            ("in some days", monday, None),
+            // calendar dates
+            ("jan 5", monday, once(day(2027, 1, 5))),
+            ("jan 5", at(day(2027, 1, 2), 10, 0), once(day(2027, 1, 5))),
+            ("jan 5", at(day(2027, 1, 6), 10, 0), once(day(2028, 1, 5))),
+            ("oct 19", monday, once(day(2026, 10, 19))),
+            ("5 jan", monday, once(day(2027, 1, 5))),
+            ("5th jan 2027", monday, once(day(2027, 1, 5))),
+            ("january 5th 2028", monday, once(day(2028, 1, 5))),
+            ("dec 1st", monday, once(day(2026, 12, 1))),
+            ("sept 3", monday, once(day(2027, 9, 3))),
+            ("2027-01-05", monday, once(day(2027, 1, 5))),
+            ("feb 30", monday, None),
+            ("jan 32", monday, None),
+            ("5x jan", monday, None),
+            ("2027-13-01", monday, None),
+            // times
+            ("9pm", monday, once_at(day(2026, 10, 19), 21, 0)),
+            ("at 9 pm", monday, once_at(day(2026, 10, 19), 21, 0)),
+            ("at 9", monday, once_at(day(2026, 10, 19), 9, 0)),
+            ("12am", monday, once_at(day(2026, 10, 19), 0, 0)),
+            ("12pm", monday, once_at(day(2026, 10, 19), 12, 0)),
+            ("tomorrow at 9:30am", monday, once_at(day(2026, 10, 20), 9, 30)),
+            ("tomorrow 14:00", monday, once_at(day(2026, 10, 20), 14, 0)),
+            ("fri at noon", monday, once_at(day(2026, 10, 23), 12, 0)),
+            ("jan 5 at midnight", monday, once_at(day(2027, 1, 5), 0, 0)),
+            ("13pm", monday, None),
+            ("0am", monday, None),
+            ("24:00", monday, None),
+            ("at 24:00", monday, None),
+            ("tomorrow at 9:60", monday, None),
+            ("tomorrow at", monday, None),
+            ("tomorrow at lunch", monday, None),
+            // repeating
+            ("every day", monday, repeating(day(2026, 10, 19))),
+            ("daily", monday, repeating(day(2026, 10, 19))),
+            ("weekly", monday, repeating(day(2026, 10, 19))),
+            ("every other week", monday, repeating(day(2026, 10, 19))),
+            ("every 3 days", monday, repeating(day(2026, 10, 19))),
+            ("every weekday", monday, repeating(day(2026, 10, 19))),
+            ("every weekday", at(day(2026, 10, 17), 10, 0), repeating(day(2026, 10, 19))),
+            ("every workday", sunday, repeating(day(2026, 10, 19))),
+            ("every friday", monday, repeating(day(2026, 10, 23))),
+            ("every other friday at 9", monday, repeating_at(day(2026, 10, 23), 9, 0)),
+            ("every mon, fri", at(day(2026, 10, 20), 10, 0), repeating(day(2026, 10, 23))),
+            ("every 15th", monday, repeating(day(2026, 11, 15))),
+            ("every 19th", monday, repeating(day(2026, 10, 19))),
+            ("every 31st", at(day(2026, 11, 1), 10, 0), repeating(day(2026, 12, 31))),
+            ("every day at 8am", monday, repeating_at(day(2026, 10, 19), 8, 0)),
+            ("every", monday, None),
+            ("every blue moon", monday, None),
+            // garbage
+            ("", monday, None),
+            ("   ", monday, None),
+            ("blah", monday, None),
+            ("next", monday, None),
+            ("tomorrow or so", monday, None),
+        ];
+        for (text, now, expected) in cases {
+            assert_eq!(parse_due_string(text, now), expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn parses_deadlines() {
+        let monday = at(day(2026, 10, 19), 10, 0);
+        let cases = [
+            ("fri", Some(day(2026, 10, 23))),
+            ("jan 5", Some(day(2027, 1, 5))),
+            ("every day", None),
+            ("tomorrow at 9", None),
+            ("blah", None),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(parse_deadline(text, monday), expected, "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn parses_durations() {
+        let minutes = |amount| {
+            Some(TaskDuration {
+                amount,
+                unit: DurationUnit::Minute,
+            })
+        };
+        let days = |amount| {
+            Some(TaskDuration {
+                amount,
+                unit: DurationUnit::Day,
+            })
+        };
+        let cases = [
+            ("45", minutes(45)),
+            ("30m", minutes(30)),
+            ("90 min", minutes(90)),
+            ("1h30m", minutes(90)),
+            ("1h 30m", minutes(90)),
+            ("2 hours", minutes(120)),
+            ("2d", days(2)),
+            ("3 days", days(3)),
+            ("1h2d", None),
+            ("0", None),
+            ("0m", None),
+            ("", None),
+            ("h", None),
+            ("abc", None),
+            ("5 weeks", None),
+            ("99999999999", None),
+            ("99999999h", None),
+        ];
+        for (text, expected) in cases {
+            assert_eq!(parse_duration(text), expected, "{:?}", text);
+        }
+    }
+}
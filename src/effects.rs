@@ -0,0 +1,166 @@
+use std::{future::Future, path::Path, time::Duration};
+
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::{
+    api::{
+        delete_project, ApiError, get_labels, get_projects, get_sections, get_tasks, post_projects,
+        post_task, update_project,
+    },
+    app::{Action, Effect},
+    config::Config,
+    export::{export_all, DEFAULT_EXPORT_DIR},
+    ics::sync_calendar,
+    import::run_import,
+    sync::{get_archived_projects, get_user, sync_commands, SyncCommand},
+};
+
+/// Runs `effect` in the background and sends its result back as an action.
+pub fn run_effect(effect: Effect, config: &Config, tx: &UnboundedSender<Action>) {
+    let token = config.token.clone();
+    let tx = tx.clone();
+    match effect {
+        Effect::FetchProjects => {
+            tokio::spawn(async move {
+                let projects = retrying(&tx, || get_projects(token.clone())).await;
+                let _ = tx.send(Action::ProjectsLoaded(projects));
+            });
+        }
+        Effect::FetchTasks => {
+            tokio::spawn(async move {
+                let tasks = retrying(&tx, || get_tasks(token.clone())).await;
+                let _ = tx.send(Action::TasksLoaded(tasks));
+            });
+        }
+        Effect::FetchSections => {
+            tokio::spawn(async move {
+                let sections = retrying(&tx, || get_sections(token.clone())).await;
+                let _ = tx.send(Action::SectionsLoaded(sections));
+            });
+        }
+        Effect::FetchLabels => {
+            tokio::spawn(async move {
+                let labels = retrying(&tx, || get_labels(token.clone())).await;
+                let _ = tx.send(Action::LabelsLoaded(labels));
+            });
+        }
+        Effect::PostTask(task) => {
+            tokio::spawn(async move {
+                let task = retrying(&tx, || post_task(token.clone(), (*task).clone()))
+                    .await
+                    .map(Box::new);
+                let _ = tx.send(Action::TaskPosted(task));
+            });
+        }
+        Effect::PostProject(project) => {
+            tokio::spawn(async move {
+                let project = retrying(&tx, || post_projects(token.clone(), project.clone())).await;
+                let _ = tx.send(Action::ProjectPosted(project));
+            });
+        }
+        Effect::RecreateTask(task) => {
+            tokio::spawn(async move {
+                let old_id = task.id.clone();
+                let result = retrying(&tx, || post_task(token.clone(), (*task).clone()))
+                    .await
+                    .map(Box::new);
+                let _ = tx.send(Action::TaskRecreated { old_id, result });
+            });
+        }
+        Effect::SyncTasks(commands) => {
+            tokio::spawn(async move {
+                let result = retrying(&tx, || sync_commands(token.clone(), commands.clone())).await;
+                let _ = tx.send(Action::TasksSynced(result));
+            });
+        }
+        Effect::ReorderTasks(orders) => {
+            tokio::spawn(async move {
+                let commands = vec![SyncCommand::item_reorder(&orders)];
+                let result = retrying(&tx, || sync_commands(token.clone(), commands.clone())).await;
+                let _ = tx.send(Action::Reordered(result));
+            });
+        }
+        Effect::ReorderProjects(orders) => {
+            tokio::spawn(async move {
+                let commands = vec![SyncCommand::project_reorder(&orders)];
+                let result = retrying(&tx, || sync_commands(token.clone(), commands.clone())).await;
+                let _ = tx.send(Action::Reordered(result));
+            });
+        }
+        Effect::DeleteProject(id) => {
+            tokio::spawn(async move {
+                let result = retrying(&tx, || delete_project(token.clone(), id.clone())).await;
+                let _ = tx.send(Action::ProjectDeleted(result));
+            });
+        }
+        Effect::UpdateProject(id, update) => {
+            tokio::spawn(async move {
+                let result = retrying(&tx, || update_project(token.clone(), id.clone(), update.clone()))
+                    .await;
+                let _ = tx.send(Action::ProjectUpdated(result));
+            });
+        }
+        Effect::SyncProjects(commands) => {
+            tokio::spawn(async move {
+                let result = retrying(&tx, || sync_commands(token.clone(), commands.clone())).await;
+                let _ = tx.send(Action::ProjectsSynced(result));
+            });
+        }
+        Effect::FetchUser => {
+            tokio::spawn(async move {
+                let user = retrying(&tx, || get_user(token.clone())).await;
+                let _ = tx.send(Action::UserLoaded(user));
+            });
+        }
+        Effect::FetchArchivedProjects => {
+            tokio::spawn(async move {
+                let projects = retrying(&tx, || get_archived_projects(token.clone())).await;
+                let _ = tx.send(Action::ArchivedProjectsLoaded(projects));
+            });
+        }
+        Effect::Export(database) => {
+            tokio::task::spawn_blocking(move || {
+                let result = export_all(&database, Path::new(DEFAULT_EXPORT_DIR))
+                    .map_err(|err| err.to_string());
+                let _ = tx.send(Action::Exported(result));
+            });
+        }
+        Effect::SyncCalendar(tasks) => {
+            let ics_path = config.ics_path.clone();
+            tokio::task::spawn_blocking(move || {
+                let result = sync_calendar(&ics_path, &tasks).map_err(|err| {
+                    format!("{}: {}", ics_path.unwrap_or_default().display(), err)
+                });
+                let _ = tx.send(Action::CalendarSynced(result));
+            });
+        }
+        Effect::RunImport(plan, state) => {
+            tokio::spawn(async move {
+                let _ = run_import(token, &plan, state, |progress| {
+                    let _ = tx.send(Action::ImportProgressed(progress.clone()));
+                })
+                .await;
+            });
+        }
+    }
+}
+
+/// Runs `request`, and when Todoist rate limits it, says so and tries once
+/// more after the wait Todoist asked for.
+async fn retrying<T, F, R>(tx: &UnboundedSender<Action>, request: F) -> Result<T, ApiError>
+where
+    F: Fn() -> R,
+    R: Future<Output = Result<T, anyhow::Error>>,
+{
+    match request().await.map_err(ApiError::from) {
+        Err(ApiError {
+            retry_after: Some(seconds),
+            ..
+        }) => {
+            let _ = tx.send(Action::RateLimited(seconds));
+            tokio::time::sleep(Duration::from_secs(seconds)).await;
+            request().await.map_err(ApiError::from)
+        }
+        result => result,
+    }
+}
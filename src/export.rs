@@ -0,0 +1,551 @@
+use std::{
+    collections::HashSet,
+    fmt::Write as _,
+    fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde_derive::{Deserialize, Serialize};
+
+use crate::{
+    api::{Label, Project, Section, Task},
+    database::Database,
+    priority::shown_priority,
+};
+
+pub const DEFAULT_EXPORT_DIR: &str = "export";
+pub const ARCHIVE_FILE: &str = "todoist-export.json";
+pub const ARCHIVE_VERSION: u32 = 1;
+
+/// Column layout of the CSV template Todoist uses for project import/export.
+pub const CSV_HEADER: [&str; 10] = [
+    "TYPE",
+    "CONTENT",
+    "DESCRIPTION",
+    "PRIORITY",
+    "INDENT",
+    "AUTHOR",
+    "RESPONSIBLE",
+    "DATE",
+    "DATE_LANG",
+    "TIMEZONE",
+];
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+    Markdown,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(ExportFormat::Json),
+            "csv" => Ok(ExportFormat::Csv),
+            "markdown" | "md" => Ok(ExportFormat::Markdown),
+            _ => Err(format!("unknown format '{}', expected json, csv or markdown", s)),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Archive {
+    pub version: u32,
+    pub exported_at: u64,
+    pub projects: Vec<ProjectArchive>,
+    pub labels: Vec<Label>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct ProjectArchive {
+    #[serde(flatten)]
+    pub project: Project,
+    pub sections: Vec<Section>,
+    pub tasks: Vec<TaskNode>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct TaskNode {
+    #[serde(flatten)]
+    pub task: Task,
+    pub children: Vec<TaskNode>,
+}
+
+/// Builds `level` and, through the parent index of `database`, the subtasks
+/// below it, each level sorted by `order`.
+fn task_tree(database: &Database, mut level: Vec<&Task>) -> Vec<TaskNode> {
+    level.sort_by(|a, b| (a.order, &a.id).cmp(&(b.order, &b.id)));
+    level
+        .into_iter()
+        .map(|task| TaskNode {
+            task: task.clone(),
+            children: task_tree(database, database.subtasks(&task.id).collect()),
+        })
+        .collect()
+}
+
+pub fn build_archive(database: &Database) -> Archive {
+    let projects = database
+        .projects()
+        .iter()
+        .filter(|project| !project.is_temp())
+        .map(|project| {
+            let sections = database.project_sections(&project.id).cloned().collect();
+            // subtasks whose parent is missing are kept at the top level
+            let top_level: Vec<&Task> = database
+                .project_tasks(&project.id)
+                .filter(|task| {
+                    let parent = task.parent_id.as_deref();
+                    parent.and_then(|id| database.task(id)).is_none()
+                })
+                .collect();
+            let mut section_ids: Vec<Option<&str>> = vec![None];
+            for task in &top_level {
+                // sections that failed to load still keep their tasks
+                if !section_ids.contains(&task.section_id.as_deref()) {
+                    section_ids.push(task.section_id.as_deref());
+                }
+            }
+            let mut tasks = vec![];
+            for section_id in section_ids {
+                let level = top_level
+                    .iter()
+                    .filter(|task| task.section_id.as_deref() == section_id)
+                    .copied()
+                    .collect();
+                tasks.extend(task_tree(database, level));
+            }
+            ProjectArchive {
+                project: project.clone(),
+                sections,
+                tasks,
+            }
+        })
+        .collect();
+
+    Archive {
+        version: ARCHIVE_VERSION,
+        exported_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default(),
+        projects,
+        labels: database.labels().to_vec(),
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+fn csv_row(fields: &[String]) -> String {
+    let fields: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+    fields.join(",") + "\n"
+}
+
+fn csv_tasks(out: &mut String, nodes: &[TaskNode], indent: usize) {
+    for node in nodes {
+        let task = &node.task;
+        let mut content = task.content.clone();
+        for label in &task.labels {
+            content.push_str(&format!(" @{}", label));
+        }
+        let due = task.due.as_ref();
+        out.push_str(&csv_row(&[
+            "task".to_owned(),
+            content,
+            task.description.clone(),
+            // the template uses the p1-p4 numbering of the apps
+            shown_priority(task.priority).to_string(),
+            indent.to_string(),
+            task.creator_id.clone(),
+            task.assignee_id.clone().unwrap_or_default(),
+            due.and_then(|due| due.string.clone()).unwrap_or_default(),
+            due.map(|_| "en".to_owned()).unwrap_or_default(),
+            due.and_then(|due| due.timezone.clone()).unwrap_or_default(),
+        ]));
+        csv_tasks(out, &node.children, indent + 1);
+    }
+}
+
+pub fn project_to_csv(project: &ProjectArchive) -> String {
+    let mut out = csv_row(&CSV_HEADER.map(String::from));
+    csv_tasks(&mut out, &project_level_tasks(project, None), 1);
+    for section in &project.sections {
+        let mut row = vec![String::new(); CSV_HEADER.len()];
+        row[0] = "section".to_owned();
+        row[1] = section.name.clone();
+        out.push_str(&csv_row(&row));
+        csv_tasks(&mut out, &project_level_tasks(project, Some(&section.id)), 1);
+    }
+    out
+}
+
+/// Top level tasks of a section, or of the project itself for `None`, which
+/// also picks up tasks whose section is unknown.
+fn project_level_tasks(project: &ProjectArchive, section_id: Option<&str>) -> Vec<TaskNode> {
+    project
+        .tasks
+        .iter()
+        .filter(|node| match (section_id, node.task.section_id.as_deref()) {
+            (None, Some(task_section)) => !project.sections.iter().any(|s| s.id == task_section),
+            (wanted, task_section) => wanted == task_section,
+        })
+        .cloned()
+        .collect()
+}
+
+fn markdown_tasks(out: &mut String, nodes: &[TaskNode], depth: usize) {
+    let indent = "  ".repeat(depth);
+    for node in nodes {
+        let task = &node.task;
+        let check = if task.is_completed { 'x' } else { ' ' };
+        let _ = write!(out, "{}- [{}] {}", indent, check, task.content);
+        if task.priority > 1 {
+            let _ = write!(out, " (p{})", shown_priority(task.priority));
+        }
+        if let Some(due) = &task.due {
+            let date = due.datetime.as_ref().or(due.date.as_ref()).or(due.string.as_ref());
+            if let Some(date) = date {
+                let _ = write!(out, " — due {}", date);
+            }
+        }
+        for label in &task.labels {
+            let _ = write!(out, " `@{}`", label);
+        }
+        out.push('\n');
+        for line in task.description.lines().filter(|line| !line.trim().is_empty()) {
+            let _ = writeln!(out, "{}  > {}", indent, line);
+        }
+        markdown_tasks(out, &node.children, depth + 1);
+    }
+}
+
+pub fn project_to_markdown(project: &ProjectArchive) -> String {
+    let mut out = format!("# {}\n\n", project.project.name);
+    markdown_tasks(&mut out, &project_level_tasks(project, None), 0);
+    for section in &project.sections {
+        let _ = write!(out, "\n## {}\n\n", section.name);
+        markdown_tasks(&mut out, &project_level_tasks(project, Some(&section.id)), 0);
+    }
+    out
+}
+
+fn file_stem(name: &str) -> String {
+    let stem: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' || c == ' ' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    match stem.trim() {
+        "" => "project".to_owned(),
+        stem => stem.to_owned(),
+    }
+}
+
+/// Writes `database` to `dir`: one archive file for JSON, one file per
+/// project for CSV and Markdown. Returns the written paths.
+pub fn export(database: &Database, format: ExportFormat, dir: &Path) -> io::Result<Vec<PathBuf>> {
+    fs::create_dir_all(dir)?;
+    let archive = build_archive(database);
+
+    if format == ExportFormat::Json {
+        let path = dir.join(ARCHIVE_FILE);
+        fs::write(&path, serde_json::to_string_pretty(&archive)?)?;
+        return Ok(vec![path]);
+    }
+
+    let extension = match format {
+        ExportFormat::Csv => "csv",
+        _ => "md",
+    };
+    let mut used = HashSet::new();
+    let mut paths = vec![];
+    for project in &archive.projects {
+        let mut stem = file_stem(&project.project.name);
+        if !used.insert(stem.clone()) {
+            stem = format!("{} {}", stem, project.project.id);
+        }
+        let path = dir.join(format!("{}.{}", stem, extension));
+        let contents = match format {
+            ExportFormat::Csv => project_to_csv(project),
+            _ => project_to_markdown(project),
+        };
+        fs::write(&path, contents)?;
+        paths.push(path);
+    }
+    Ok(paths)
+}
+
+pub fn export_all(database: &Database, dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut paths = vec![];
+    for format in [ExportFormat::Json, ExportFormat::Csv, ExportFormat::Markdown] {
+        paths.extend(export(database, format, dir)?);
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::{Due, TaskContent};
+
+    fn task(id: &str, content: &str, section_id: Option<&str>, parent_id: Option<&str>) -> Task {
+        let content = TaskContent {
+            content: content.to_owned(),
+            ..Default::default()
+        };
+        let mut task = Task::temp(content, "1".to_owned());
+        task.id = id.to_owned();
+        task.created_at = "2026-10-01T09:00:00Z".to_owned();
+        task.section_id = section_id.map(str::to_owned);
+        task.parent_id = parent_id.map(str::to_owned);
+        task.url = format!("https://todoist.com/showTask?id={}", id);
+        task
+    }
+
+    /// A project with a nested subtask, a section and fields that need quoting.
+    fn archive() -> Archive {
+        let mut project = Project::name("Home");
+        project.id = "1".to_owned();
+        project.parent_id = None;
+        let section = Section {
+            id: "s1".to_owned(),
+            project_id: "1".to_owned(),
+            order: 1,
+            name: "Garden".to_owned(),
+        };
+        let mut groceries = task("a", "Buy milk, eggs", None, None);
+        groceries.order = 2;
+        groceries.priority = 4;
+        groceries.labels = vec!["shop".to_owned()];
+        groceries.description = "2 \"large\" ones".to_owned();
+        groceries.due = Some(Due {
+            date: Some("2026-10-20".to_owned()),
+            is_recurring: Some(false),
+            datetime: None,
+            timezone: None,
+            string: Some("tomorrow".to_owned()),
+        });
+        let mut bills = task("e", "Pay bills", None, None);
+        bills.order = 1;
+        let tasks = vec![
+            task("d", "Check price", None, Some("b")),
+            task("c", "Water plants", Some("s1"), None),
+            groceries,
+            task("b", "Oat milk", None, Some("a")),
+            bills,
+        ];
+        let database = Database::new(vec![project], vec![section], tasks, vec![]);
+        let mut archive = build_archive(&database);
+        archive.exported_at = 0;
+        archive
+    }
+
+    #[test]
+    fn exports_csv() {
+        let expected = "\
+TYPE,CONTENT,DESCRIPTION,PRIORITY,INDENT,AUTHOR,RESPONSIBLE,DATE,DATE_LANG,TIMEZONE
+task,Pay bills,,4,1,,,,,
+task,\"Buy milk, eggs @shop\",\"2 \"\"large\"\" ones\",1,1,,,tomorrow,en,
+task,Oat milk,,4,2,,,,,
+task,Check price,,4,3,,,,,
+section,Garden,,,,,,,,
+task,Water plants,,4,1,,,,,
+";
+        assert_eq!(project_to_csv(&archive().projects[0]), expected);
+    }
+
+    #[test]
+    fn exports_markdown() {
+        let expected = "\
+# Home
+
+- [ ] Pay bills
+- [ ] Buy milk, eggs (p1) — due 2026-10-20 `@shop`
+  > 2 \"large\" ones
+  - [ ] Oat milk
+    - [ ] Check price
+
+## Garden
+
+- [ ] Water plants
+";
+        assert_eq!(project_to_markdown(&archive().projects[0]), expected);
+    }
+
+    #[test]
+    fn exports_json() {
+        let expected = r#"{
+  "version": 1,
+  "exported_at": 0,
+  "projects": [
+    {
+      "id": "1",
+      "name": "Home",
+      "comment_count": 0,
+      "order": 0,
+      "color": "",
+      "is_shared": false,
+      "is_favorite": false,
+      "parent_id": null,
+      "is_inbox_project": false,
+      "is_team_inbox": false,
+      "view_style": "",
+      "url": "",
+      "sections": [
+        {
+          "id": "s1",
+          "project_id": "1",
+          "order": 1,
+          "name": "Garden"
+        }
+      ],
+      "tasks": [
+        {
+          "creator_id": "",
+          "created_at": "2026-10-01T09:00:00Z",
+          "assignee_id": null,
+          "assigner_id": null,
+          "comment_count": 0,
+          "is_completed": false,
+          "content": "Pay bills",
+          "description": "",
+          "id": "e",
+          "due": null,
+          "labels": [],
+          "order": 1,
+          "priority": 1,
+          "project_id": "1",
+          "section_id": null,
+          "parent_id": null,
+          "due_string": "",
+          "duration": null,
+          "deadline": null,
+          "url": "https://todoist.com/showTask?id=e",
+          "children": []
+        },
+        {
+          "creator_id": "",
+          "created_at": "2026-10-01T09:00:00Z",
+          "assignee_id": null,
+          "assigner_id": null,
+          "comment_count": 0,
+          "is_completed": false,
+          "content": "Buy milk, eggs",
+          "description": "2 \"large\" ones",
+          "id": "a",
+          "due": {
+            "date": "2026-10-20",
+            "is_recurring": false,
+            "datetime": null,
+            "timezone": null,
+            "string": "tomorrow"
+          },
+          "labels": [
+            "shop"
+          ],
+          "order": 2,
+          "priority": 4,
+          "project_id": "1",
+          "section_id": null,
+          "parent_id": null,
+          "due_string": "",
+          "duration": null,
+          "deadline": null,
+          "url": "https://todoist.com/showTask?id=a",
+          "children": [
+            {
+              "creator_id": "",
+              "created_at": "2026-10-01T09:00:00Z",
+              "assignee_id": null,
+              "assigner_id": null,
+              "comment_count": 0,
+              "is_completed": false,
+              "content": "Oat milk",
+              "description": "",
+              "id": "b",
+              "due": null,
+              "labels": [],
+              "order": 0,
+              "priority": 1,
+              "project_id": "1",
+              "section_id": null,
+              "parent_id": "a",
+              "due_string": "",
+              "duration": null,
+              "deadline": null,
+              "url": "https://todoist.com/showTask?id=b",
+              "children": [
+                {
+                  "creator_id": "",
+                  "created_at": "2026-10-01T09:00:00Z",
+                  "assignee_id": null,
+                  "assigner_id": null,
+                  "comment_count": 0,
+                  "is_completed": false,
+                  "content": "Check price",
+                  "description": "",
+                  "id": "d",
+                  "due": null,
+                  "labels": [],
+                  "order": 0,
+                  "priority": 1,
+                  "project_id": "1",
+                  "section_id": null,
+                  "parent_id": "b",
+                  "due_string": "",
+                  "duration": null,
+                  "deadline": null,
+                  "url": "https://todoist.com/showTask?id=d",
+                  "children": []
+                }
+              ]
+            }
+          ]
+        },
+        {
+          "creator_id": "",
+          "created_at": "2026-10-01T09:00:00Z",
+          "assignee_id": null,
+          "assigner_id": null,
+          "comment_count": 0,
+          "is_completed": false,
+          "content": "Water plants",
+          "description": "",
+          "id": "c",
+          "due": null,
+          "labels": [],
+          "order": 0,
+          "priority": 1,
+          "project_id": "1",
+          "section_id": "s1",
+          "parent_id": null,
+          "due_string": "",
+          "duration": null,
+          "deadline": null,
+          "url": "https://todoist.com/showTask?id=c",
+          "children": []
+        }
+      ]
+    }
+  ],
+  "labels": []
+}"#;
+        assert_eq!(serde_json::to_string_pretty(&archive()).unwrap(), expected);
+    }
+}
@@ -1,10 +1,11 @@
 use tui::{
     layout::Alignment,
     style::{Color, Style},
+    text::Spans,
     widgets::{Block, BorderType, Borders, Paragraph},
 };
 
-pub fn create_basic_block(title: &str, highlight_color: Color) -> Block {
+pub fn create_basic_block(title: &str, highlight_color: Color) -> Block<'_> {
     Block::default()
         .title(title)
         .borders(Borders::ALL)
@@ -12,11 +13,11 @@ pub fn create_basic_block(title: &str, highlight_color: Color) -> Block {
         .border_type(BorderType::Plain)
 }
 
-pub fn create_advanced_block(
-    title: &str,
+pub fn create_advanced_block<'a>(
+    title: impl Into<Spans<'a>>,
     highlight_color: Color,
     alignment: Alignment,
-) -> Block {
+) -> Block<'a> {
     Block::default()
         .title(title)
         .borders(Borders::ALL)
@@ -31,3 +32,38 @@ pub fn create_basic_paragraph(paragraph: String, block: Block) -> Paragraph {
         .style(Style::default().fg(Color::White))
         .block(block)
 }
+
+/// Breaks `text` into lines of at most `width` characters, at spaces where
+/// possible.
+pub fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = vec![];
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let mut word: Vec<char> = word.chars().collect();
+        let line_len = line.chars().count();
+        if line_len > 0 && line_len + 1 + word.len() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        while word.len() > width {
+            let rest = word.split_off(width);
+            lines.push(word.into_iter().collect());
+            word = rest;
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.extend(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// The end of `text` that fits in `width` columns with the cursor after it.
+pub fn input_tail(text: &str, width: u16) -> String {
+    let count = text.chars().count();
+    let fits = (width as usize).saturating_sub(1);
+    text.chars().skip(count.saturating_sub(fits)).collect()
+}
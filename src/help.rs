@@ -0,0 +1,62 @@
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::{app::Help, handler::create_advanced_block, key_events::Binding, popup::centered_rect};
+
+const KEYS_WIDTH: usize = 14;
+
+/// Lists `entries` by context below a search line, starting at the scroll
+/// offset of `help`.
+pub fn render_help<B: Backend>(
+    rect: &mut Frame<B>,
+    help: &Help,
+    entries: &[&Binding],
+    color: Color,
+    area: Rect,
+) {
+    let popup_area = centered_rect(64, area.height.saturating_sub(4), area);
+    let block = create_advanced_block("Help · Esc to close", color, Alignment::Center);
+    let inner = block.inner(popup_area);
+    rect.render_widget(Clear, popup_area);
+    rect.render_widget(block, popup_area);
+
+    let parts = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(2), Constraint::Min(0)])
+        .split(inner);
+    let search = Paragraph::new(Spans::from(vec![
+        Span::styled("Search: ", Style::default().fg(color)),
+        Span::raw(help.query.as_str()),
+    ]))
+    .block(Block::default().borders(Borders::BOTTOM));
+    rect.render_widget(search, parts[0]);
+
+    let mut lines = vec![];
+    let mut context = None;
+    for binding in entries.iter().skip(help.offset) {
+        if context != Some(binding.context) {
+            context = Some(binding.context);
+            lines.push(Spans::from(Span::styled(
+                binding.context.title(),
+                Style::default().fg(color).add_modifier(Modifier::BOLD),
+            )));
+        }
+        lines.push(Spans::from(vec![
+            Span::styled(
+                format!("  {:<width$}", binding.keys_label(), width = KEYS_WIDTH),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(binding.description),
+        ]));
+    }
+    if lines.is_empty() {
+        lines.push(Spans::from("No bindings match the search."));
+    }
+    rect.render_widget(Paragraph::new(lines), parts[1]);
+}
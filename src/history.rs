@@ -0,0 +1,167 @@
+use serde_json::{json, Value};
+
+use crate::{
+    api::{Due, Task},
+    priority::shown_priority,
+    sync::{due_args, Placement},
+};
+
+const MAX_HISTORY: usize = 50;
+
+/// A change made from the TUI, holding what is needed to revert it.
+#[derive(Clone, Debug)]
+pub enum Change {
+    /// Reverted by creating the tasks again with the same fields.
+    DeletedTasks(Vec<Task>),
+    /// Reverted by reopening the tasks.
+    ClosedTasks(Vec<Task>),
+    /// Reverted by moving every task back to where it was.
+    MovedTasks { tasks: Vec<Task>, to: Placement },
+    /// Reverted by setting the edited field back to its value in `tasks`.
+    EditedTasks { tasks: Vec<Task>, edit: TaskEdit },
+}
+
+/// A change to one field of a task.
+#[derive(Clone, Debug)]
+pub enum TaskEdit {
+    /// The priority as the API counts it, where 4 is p1.
+    Priority(usize),
+    AddLabel(String),
+    RemoveLabel(String),
+    /// A due string for the server to parse; empty removes the due date.
+    Reschedule(String),
+}
+
+impl TaskEdit {
+    pub fn apply(&self, task: &mut Task) {
+        match self {
+            TaskEdit::Priority(priority) => task.priority = *priority,
+            TaskEdit::AddLabel(label) => {
+                if !task.labels.contains(label) {
+                    task.labels.push(label.clone());
+                }
+            }
+            TaskEdit::RemoveLabel(label) => task.labels.retain(|other| other != label),
+            TaskEdit::Reschedule(string) if string.is_empty() => task.due = None,
+            TaskEdit::Reschedule(string) => {
+                task.due = Some(Due {
+                    date: None,
+                    is_recurring: None,
+                    datetime: None,
+                    timezone: None,
+                    string: Some(string.clone()),
+                })
+            }
+        }
+    }
+
+    /// Sets the field this edit touches back to its value in `before`.
+    pub fn restore(&self, task: &mut Task, before: &Task) {
+        match self {
+            TaskEdit::Priority(_) => task.priority = before.priority,
+            TaskEdit::AddLabel(_) | TaskEdit::RemoveLabel(_) => task.labels = before.labels.clone(),
+            TaskEdit::Reschedule(_) => task.due = before.due.clone(),
+        }
+    }
+
+    /// The field this edit touches, as `task` has it, in the form the
+    /// `item_update` command takes.
+    pub fn fields(&self, task: &Task) -> Value {
+        match self {
+            TaskEdit::Priority(_) => json!({ "priority": task.priority }),
+            TaskEdit::AddLabel(_) | TaskEdit::RemoveLabel(_) => json!({ "labels": task.labels }),
+            TaskEdit::Reschedule(_) => json!({ "due": due_args(task.due.as_ref()) }),
+        }
+    }
+}
+
+/// `"content"` of a single task, otherwise how many there are.
+fn subject(tasks: &[Task]) -> String {
+    match tasks {
+        [task] => format!("\"{}\"", task.content),
+        tasks => format!("{} tasks", tasks.len()),
+    }
+}
+
+impl Change {
+    pub fn tasks(&self) -> &[Task] {
+        match self {
+            Change::DeletedTasks(tasks)
+            | Change::ClosedTasks(tasks)
+            | Change::MovedTasks { tasks, .. }
+            | Change::EditedTasks { tasks, .. } => tasks,
+        }
+    }
+
+    fn tasks_mut(&mut self) -> &mut [Task] {
+        match self {
+            Change::DeletedTasks(tasks)
+            | Change::ClosedTasks(tasks)
+            | Change::MovedTasks { tasks, .. }
+            | Change::EditedTasks { tasks, .. } => tasks,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let subject = subject(self.tasks());
+        match self {
+            Change::DeletedTasks(_) => format!("Deleted {}", subject),
+            Change::ClosedTasks(_) => format!("Completed {}", subject),
+            Change::MovedTasks { .. } => format!("Moved {}", subject),
+            Change::EditedTasks { edit, .. } => match edit {
+                TaskEdit::Priority(priority) => {
+                    format!("Set p{} on {}", shown_priority(*priority), subject)
+                }
+                TaskEdit::AddLabel(label) => format!("Added @{} to {}", label, subject),
+                TaskEdit::RemoveLabel(label) => format!("Removed @{} from {}", label, subject),
+                TaskEdit::Reschedule(string) if string.is_empty() => {
+                    format!("Removed the due date of {}", subject)
+                }
+                TaskEdit::Reschedule(string) => format!("Rescheduled {} to {}", subject, string),
+            },
+        }
+    }
+}
+
+/// Undo and redo stacks of changes.
+#[derive(Default, Debug)]
+pub struct History {
+    undo: Vec<Change>,
+    redo: Vec<Change>,
+}
+
+impl History {
+    /// Records a new change, which discards everything that could be redone.
+    pub fn record(&mut self, change: Change) {
+        self.undo.push(change);
+        if self.undo.len() > MAX_HISTORY {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    pub fn undo(&mut self) -> Option<Change> {
+        let change = self.undo.pop()?;
+        self.redo.push(change.clone());
+        Some(change)
+    }
+
+    pub fn redo(&mut self) -> Option<Change> {
+        let change = self.redo.pop()?;
+        self.undo.push(change.clone());
+        Some(change)
+    }
+
+    /// Points changes at the new id of a task that was created again.
+    pub fn rename_task(&mut self, old_id: &str, new_id: &str) {
+        let changes = self.undo.iter_mut().chain(self.redo.iter_mut());
+        for task in changes.flat_map(Change::tasks_mut) {
+            if task.id == old_id {
+                task.id = new_id.to_owned();
+            }
+            if task.parent_id.as_deref() == Some(old_id) {
+                task.parent_id = Some(new_id.to_owned());
+            }
+        }
+    }
+}
@@ -0,0 +1,366 @@
+use std::{fmt::Write as _, fs, io, path::Path};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+
+use crate::{
+    api::{Due, DurationUnit, Task, TaskDuration},
+    date::Zone,
+};
+
+pub const DEFAULT_ICS_FILE: &str = "todoist.ics";
+
+const ICS_UTC_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+const WEEKDAYS: [(&str, &str); 7] = [
+    ("mon", "MO"),
+    ("tue", "TU"),
+    ("wed", "WE"),
+    ("thu", "TH"),
+    ("fri", "FR"),
+    ("sat", "SA"),
+    ("sun", "SU"),
+];
+
+/// Escapes TEXT values as described in RFC 5545, section 3.3.11.
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// Folds content lines longer than 75 octets, without splitting characters.
+fn fold_line(line: &str) -> String {
+    let mut folded = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded.push_str("\r\n");
+    folded
+}
+
+fn compact(date: &str) -> String {
+    date.chars().filter(|c| c.is_ascii_digit()).collect()
+}
+
+/// The DTSTART property: a DATE for all-day tasks, otherwise a DATE-TIME in
+/// UTC, or floating when Todoist gives a local time without a timezone.
+/// Times in a timezone are written as UTC so the calendar needs no VTIMEZONE.
+fn dtstart(due: &Due) -> Option<String> {
+    match (&due.datetime, &due.date) {
+        (Some(datetime), _) => {
+            if let Ok(utc) = DateTime::parse_from_rfc3339(datetime) {
+                return Some(format!(
+                    "DTSTART:{}",
+                    utc.with_timezone(&Utc).format(ICS_UTC_FORMAT)
+                ));
+            }
+            let value = compact(datetime);
+            if value.len() < 14 {
+                return None;
+            }
+            let local = NaiveDateTime::parse_from_str(&value[..14], "%Y%m%d%H%M%S").ok()?;
+            let zone = due
+                .timezone
+                .as_deref()
+                .and_then(|name| name.parse::<Tz>().ok());
+            Some(match zone {
+                Some(tz) => format!(
+                    "DTSTART:{}",
+                    Zone::Named(tz).utc(local)?.format(ICS_UTC_FORMAT)
+                ),
+                None => format!("DTSTART:{}", local.format("%Y%m%dT%H%M%S")),
+            })
+        }
+        (None, Some(date)) => {
+            let value = compact(date);
+            (value.len() == 8).then(|| format!("DTSTART;VALUE=DATE:{}", value))
+        }
+        (None, None) => None,
+    }
+}
+
+/// Best-effort RRULE for Todoist's recurring due strings like "every day",
+/// "every 2 weeks", "every other friday" or "every mon, thu".
+pub fn rrule(due_string: &str) -> Option<String> {
+    let string = due_string.to_lowercase();
+    let rest = string
+        .strip_prefix("every ")
+        .or_else(|| string.strip_prefix("ev "))?;
+    let rest = rest.split(" at ").next().unwrap_or(rest);
+    let rest = rest.split(" starting ").next().unwrap_or(rest).trim();
+
+    let (interval, rest) = match rest.split_once(' ') {
+        Some(("other", rest)) => (2, rest),
+        Some((n, rest)) if n.parse::<u32>().is_ok() => (n.parse().unwrap_or(1), rest),
+        _ => (1, rest),
+    };
+    let with_interval = |rule: String| match interval {
+        1 => rule,
+        n => format!("{};INTERVAL={}", rule, n),
+    };
+
+    let frequency = match rest.trim_end_matches('s') {
+        "day" | "night" => Some("DAILY"),
+        "week" => Some("WEEKLY"),
+        "month" => Some("MONTHLY"),
+        "year" => Some("YEARLY"),
+        "hour" => Some("HOURLY"),
+        _ => None,
+    };
+    if let Some(frequency) = frequency {
+        return Some(with_interval(format!("FREQ={}", frequency)));
+    }
+    if rest == "weekday" || rest == "workday" {
+        return Some(with_interval("FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR".to_owned()));
+    }
+
+    let days: Vec<&str> = rest
+        .split([',', ' '])
+        .filter(|word| !word.is_empty() && *word != "and")
+        .map(|word| {
+            WEEKDAYS
+                .iter()
+                .find(|(prefix, _)| word.starts_with(prefix))
+                .map(|(_, day)| *day)
+        })
+        .collect::<Option<_>>()?;
+    if days.is_empty() {
+        return None;
+    }
+    Some(with_interval(format!("FREQ=WEEKLY;BYDAY={}", days.join(","))))
+}
+
+/// The DURATION value. All-day events can only last whole days.
+fn ics_duration(duration: TaskDuration, all_day: bool) -> Option<String> {
+    let amount = duration.amount;
+    if amount == 0 {
+        return None;
+    }
+    match duration.unit {
+        DurationUnit::Day => Some(format!("P{}D", amount)),
+        DurationUnit::Minute if all_day => None,
+        DurationUnit::Minute => Some(match (amount / 60, amount % 60) {
+            (0, minutes) => format!("PT{}M", minutes),
+            (hours, 0) => format!("PT{}H", hours),
+            (hours, minutes) => format!("PT{}H{}M", hours, minutes),
+        }),
+    }
+}
+
+fn ics_priority(priority: usize) -> u8 {
+    match priority {
+        4 => 1,
+        3 => 3,
+        2 => 5,
+        _ => 0,
+    }
+}
+
+fn write_event(out: &mut String, task: &Task, dtstamp: &str) {
+    let due = match &task.due {
+        Some(due) => due,
+        None => return,
+    };
+    let start = match dtstart(due) {
+        Some(start) => start,
+        None => return,
+    };
+
+    let mut lines = vec![
+        "BEGIN:VEVENT".to_owned(),
+        format!("UID:{}@todoist-tui", task.id),
+        format!("DTSTAMP:{}", dtstamp),
+        start,
+        format!("SUMMARY:{}", escape_text(&task.content)),
+    ];
+    let all_day = due.datetime.is_none();
+    if let Some(duration) = task.duration.and_then(|d| ics_duration(d, all_day)) {
+        lines.push(format!("DURATION:{}", duration));
+    }
+    if !task.description.is_empty() {
+        lines.push(format!("DESCRIPTION:{}", escape_text(&task.description)));
+    }
+    if due.is_recurring == Some(true) {
+        if let Some(rule) = due.string.as_deref().and_then(rrule) {
+            lines.push(format!("RRULE:{}", rule));
+        }
+    }
+    if !task.labels.is_empty() {
+        let labels: Vec<String> = task.labels.iter().map(|l| escape_text(l)).collect();
+        lines.push(format!("CATEGORIES:{}", labels.join(",")));
+    }
+    if ics_priority(task.priority) > 0 {
+        lines.push(format!("PRIORITY:{}", ics_priority(task.priority)));
+    }
+    if !task.url.is_empty() {
+        lines.push(format!("URL:{}", task.url));
+    }
+    lines.push("END:VEVENT".to_owned());
+
+    for line in lines {
+        out.push_str(&fold_line(&line));
+    }
+}
+
+/// Renders every task with a due date as an event of an RFC 5545 calendar.
+pub fn to_ics(tasks: &[Task]) -> String {
+    let dtstamp = Utc::now().format(ICS_UTC_FORMAT).to_string();
+
+    let mut out = String::new();
+    for line in [
+        "BEGIN:VCALENDAR",
+        "VERSION:2.0",
+        "PRODID:-//todoist-tui//EN",
+        "CALSCALE:GREGORIAN",
+        "X-WR-CALNAME:Todoist",
+    ] {
+        let _ = write!(out, "{}\r\n", line);
+    }
+    let mut tasks: Vec<&Task> = tasks.iter().collect();
+    tasks.sort_by(|a, b| a.id.cmp(&b.id));
+    for task in tasks {
+        write_event(&mut out, task, &dtstamp);
+    }
+    out.push_str("END:VCALENDAR\r\n");
+    out
+}
+
+pub fn write_ics(tasks: &[Task], path: &Path) -> io::Result<()> {
+    fs::write(path, to_ics(tasks))
+}
+
+/// Rewrites the calendar configured as `ics_path` after tasks were fetched,
+/// if there is one.
+pub fn sync_calendar(path: &Option<impl AsRef<Path>>, tasks: &[Task]) -> io::Result<()> {
+    match path {
+        Some(path) => write_ics(tasks, path.as_ref()),
+        None => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::TaskContent;
+
+    fn due(datetime: &str, timezone: Option<&str>) -> Due {
+        Due {
+            date: Some(datetime[..10].to_owned()),
+            is_recurring: Some(false),
+            datetime: Some(datetime.to_owned()),
+            timezone: timezone.map(str::to_owned),
+            string: None,
+        }
+    }
+
+    #[test]
+    fn start_times_are_utc_or_floating() {
+        let cases = [
+            (
+                "2026-10-19T08:00:00Z",
+                Some("Europe/Berlin"),
+                "DTSTART:20261019T080000Z",
+            ),
+            (
+                "2026-10-19T10:00:00+02:00",
+                None,
+                "DTSTART:20261019T080000Z",
+            ),
+            (
+                "2026-10-19T10:00:00",
+                Some("Europe/Berlin"),
+                "DTSTART:20261019T080000Z",
+            ),
+            (
+                "2026-12-19T10:00:00",
+                Some("Europe/Berlin"),
+                "DTSTART:20261219T090000Z",
+            ),
+            ("2026-10-19T10:00:00", None, "DTSTART:20261019T100000"),
+            (
+                "2026-10-19T10:00:00",
+                Some("UTC+02:00"),
+                "DTSTART:20261019T100000",
+            ),
+        ];
+        for (datetime, timezone, expected) in cases {
+            assert_eq!(
+                dtstart(&due(datetime, timezone)).as_deref(),
+                Some(expected),
+                "{}",
+                datetime
+            );
+        }
+    }
+
+    #[test]
+    fn all_day_dates_stay_dates() {
+        let due = Due {
+            date: Some("2026-10-19".to_owned()),
+            is_recurring: Some(false),
+            datetime: None,
+            timezone: None,
+            string: None,
+        };
+        assert_eq!(
+            dtstart(&due).as_deref(),
+            Some("DTSTART;VALUE=DATE:20261019")
+        );
+    }
+
+    #[test]
+    fn calendars_have_no_tzid() {
+        let mut task = Task::temp(TaskContent::default(), "inbox".to_owned());
+        task.id = "1".to_owned();
+        task.due = Some(due("2026-10-19T10:00:00", Some("Europe/Berlin")));
+        let ics = to_ics(&[task]);
+        assert!(ics.contains("DTSTART:20261019T080000Z\r\n"));
+        assert!(!ics.contains("TZID"));
+    }
+
+    #[test]
+    fn durations_are_exported() {
+        let minutes = |amount| TaskDuration {
+            amount,
+            unit: DurationUnit::Minute,
+        };
+        let days = |amount| TaskDuration {
+            amount,
+            unit: DurationUnit::Day,
+        };
+        let cases = [
+            (minutes(45), false, Some("PT45M")),
+            (minutes(120), false, Some("PT2H")),
+            (minutes(90), false, Some("PT1H30M")),
+            (minutes(0), false, None),
+            (minutes(30), true, None),
+            (days(2), false, Some("P2D")),
+            (days(3), true, Some("P3D")),
+        ];
+        for (duration, all_day, expected) in cases {
+            assert_eq!(ics_duration(duration, all_day).as_deref(), expected, "{:?}", duration);
+        }
+
+        let mut task = Task::temp(TaskContent::default(), "inbox".to_owned());
+        task.id = "1".to_owned();
+        task.due = Some(due("2026-10-19T08:00:00Z", None));
+        task.duration = Some(minutes(90));
+        assert!(to_ics(&[task]).contains("\r\nDURATION:PT1H30M\r\n"));
+    }
+
+    #[test]
+    fn calendar_write_errors_are_returned() {
+        assert!(sync_calendar(&None::<&Path>, &[]).is_ok());
+        let path = Path::new("/nonexistent/dir/todoist.ics");
+        assert!(sync_calendar(&Some(path), &[]).is_err());
+    }
+}
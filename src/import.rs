@@ -0,0 +1,851 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use serde_derive::{Deserialize, Serialize};
+use tui::{
+    layout::Alignment,
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Paragraph, Wrap},
+};
+
+use crate::{
+    api::{
+        get_projects, post_projects, post_section, post_task, PostProject, PostSection, Project, Task,
+        TaskContent,
+    },
+    date::format_duration,
+    export::{Archive, ProjectArchive, TaskNode},
+    handler::create_advanced_block,
+    priority::{api_priority, shown_priority, DEFAULT_PRIORITY},
+};
+
+pub const DEFAULT_PROJECT: &str = "Inbox";
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ImportFormat {
+    TodoTxt,
+    Csv,
+    Json,
+}
+
+impl FromStr for ImportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "todotxt" | "todo.txt" | "txt" => Ok(ImportFormat::TodoTxt),
+            "csv" => Ok(ImportFormat::Csv),
+            "json" => Ok(ImportFormat::Json),
+            _ => Err(format!("unknown format '{}', expected todotxt, csv or json", s)),
+        }
+    }
+}
+
+impl ImportFormat {
+    pub fn from_path(path: &Path) -> Option<ImportFormat> {
+        match path.extension()?.to_str()? {
+            "txt" => Some(ImportFormat::TodoTxt),
+            "csv" => Some(ImportFormat::Csv),
+            "json" => Some(ImportFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+/// A task to create. `key` identifies it in the source file and is what the
+/// resume state is keyed on.
+#[derive(Clone, Debug)]
+pub struct PlannedTask {
+    pub key: String,
+    pub parent_key: Option<String>,
+    pub section_key: Option<String>,
+    pub depth: usize,
+    pub content: TaskContent,
+}
+
+#[derive(Clone, Debug)]
+pub struct PlannedSection {
+    pub key: String,
+    pub name: String,
+}
+
+/// A project to create. Projects with a `parent_key` come after their parent.
+#[derive(Clone, Debug)]
+pub struct PlannedProject {
+    pub key: String,
+    pub parent_key: Option<String>,
+    pub project: PostProject,
+    pub sections: Vec<PlannedSection>,
+    pub tasks: Vec<PlannedTask>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ImportPlan {
+    pub source: PathBuf,
+    pub projects: Vec<PlannedProject>,
+}
+
+impl ImportPlan {
+    pub fn task_count(&self) -> usize {
+        self.projects.iter().map(|project| project.tasks.len()).sum()
+    }
+
+    pub fn state_path(&self) -> PathBuf {
+        let mut name = self.source.file_name().unwrap_or_default().to_os_string();
+        name.push(".import-state.json");
+        self.source.with_file_name(name)
+    }
+}
+
+/// Maps plan keys to the ids of the projects and tasks already created, so an
+/// interrupted import can be started again without duplicates.
+#[derive(Default, Deserialize, Serialize, Debug, Clone)]
+pub struct ImportState {
+    pub created: HashMap<String, String>,
+}
+
+impl ImportState {
+    pub fn load(path: &Path) -> ImportState {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), anyhow::Error> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+#[derive(Default, Clone, Debug)]
+pub struct ImportProgress {
+    pub started: bool,
+    pub finished: bool,
+    pub done: usize,
+    pub total: usize,
+    pub current: String,
+    pub error: Option<String>,
+    /// Plan key and id of the item created since the previous report.
+    pub created: Option<(String, String)>,
+}
+
+impl ImportProgress {
+    pub fn is_running(&self) -> bool {
+        self.started && !self.finished
+    }
+}
+
+pub struct ImportStatus {
+    pub plan: ImportPlan,
+    pub state: ImportState,
+    pub progress: ImportProgress,
+    pub scroll: u16,
+}
+
+impl ImportStatus {
+    pub fn new(plan: ImportPlan) -> ImportStatus {
+        let state = ImportState::load(&plan.state_path());
+        ImportStatus {
+            plan,
+            state,
+            progress: ImportProgress::default(),
+            scroll: 0,
+        }
+    }
+
+    /// Takes over a progress report of `run_import`.
+    pub fn update(&mut self, mut progress: ImportProgress) {
+        if let Some((key, id)) = progress.created.take() {
+            self.state.created.insert(key, id);
+        }
+        self.progress = progress;
+    }
+}
+
+pub fn read_plan(path: &Path, format: Option<ImportFormat>) -> Result<ImportPlan, anyhow::Error> {
+    let format = format
+        .or_else(|| ImportFormat::from_path(path))
+        .ok_or_else(|| anyhow::anyhow!("cannot tell the format of '{}'", path.display()))?;
+    let contents = fs::read_to_string(path)?;
+
+    let projects = match format {
+        ImportFormat::TodoTxt => parse_todo_txt(&contents),
+        ImportFormat::Csv => {
+            let name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_else(|| DEFAULT_PROJECT.to_owned());
+            vec![parse_todoist_csv(&contents, &name)?]
+        }
+        ImportFormat::Json => parse_archive(&contents)?,
+    };
+
+    Ok(ImportPlan {
+        source: path.to_owned(),
+        projects,
+    })
+}
+
+fn project_entry<'a>(projects: &'a mut Vec<PlannedProject>, name: &str) -> &'a mut PlannedProject {
+    let index = match projects.iter().position(|p| p.project.name == name) {
+        Some(index) => index,
+        None => {
+            projects.push(PlannedProject {
+                key: format!("project:{}", name),
+                parent_key: None,
+                project: PostProject {
+                    name: name.to_owned(),
+                    parent_id: None,
+                },
+                sections: vec![],
+                tasks: vec![],
+            });
+            projects.len() - 1
+        }
+    };
+    &mut projects[index]
+}
+
+fn is_date(word: &str) -> bool {
+    let bytes = word.as_bytes();
+    bytes.len() == 10
+        && bytes[4] == b'-'
+        && bytes[7] == b'-'
+        && bytes
+            .iter()
+            .enumerate()
+            .all(|(i, b)| i == 4 || i == 7 || b.is_ascii_digit())
+}
+
+/// Reads todo.txt lines: `(A)` priorities, `+project`, `@context` as labels
+/// and `due:` dates. Completed lines are skipped.
+pub fn parse_todo_txt(contents: &str) -> Vec<PlannedProject> {
+    let mut projects: Vec<PlannedProject> = vec![];
+
+    for (line_number, line) in contents.lines().enumerate() {
+        let mut words = line.split_whitespace().peekable();
+        if words.peek().is_none() || words.peek() == Some(&"x") {
+            continue;
+        }
+
+        let mut priority = DEFAULT_PRIORITY;
+        if let Some(word) = words.peek() {
+            if word.len() == 3 && word.starts_with('(') && word.ends_with(')') {
+                priority = match &word[1..2] {
+                    "A" => api_priority(1),
+                    "B" => api_priority(2),
+                    "C" => api_priority(3),
+                    _ => DEFAULT_PRIORITY,
+                };
+                words.next();
+            }
+        }
+        while words.peek().is_some_and(|word| is_date(word)) {
+            words.next();
+        }
+
+        let mut project = None;
+        let mut content = vec![];
+        let mut labels = vec![];
+        let mut due_string = String::new();
+        for word in words {
+            if let Some(name) = word.strip_prefix('+').filter(|name| !name.is_empty()) {
+                project.get_or_insert_with(|| name.to_owned());
+            } else if let Some(label) = word.strip_prefix('@').filter(|label| !label.is_empty()) {
+                labels.push(label.to_owned());
+            } else if let Some(due) = word.strip_prefix("due:") {
+                due_string = due.to_owned();
+            } else {
+                content.push(word);
+            }
+        }
+
+        let project = project.unwrap_or_else(|| DEFAULT_PROJECT.to_owned());
+        project_entry(&mut projects, &project).tasks.push(PlannedTask {
+            key: format!("todo.txt:{}", line_number + 1),
+            parent_key: None,
+            section_key: None,
+            depth: 0,
+            content: TaskContent {
+                content: content.join(" "),
+                description: String::new(),
+                labels: labels.join(","),
+                priority,
+                due_string,
+                deadline: String::new(),
+                duration: String::new(),
+            },
+        });
+    }
+
+    projects
+}
+
+/// Splits CSV text into records, honouring quoted fields with embedded
+/// commas, quotes and line breaks.
+pub fn parse_csv_records(contents: &str) -> Vec<Vec<String>> {
+    let mut records = vec![];
+    let mut record = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = contents.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => record.push(std::mem::take(&mut field)),
+            '\r' if !quoted => {}
+            '\n' if !quoted => {
+                record.push(std::mem::take(&mut field));
+                records.push(std::mem::take(&mut record));
+            }
+            c => field.push(c),
+        }
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+
+    records
+}
+
+/// Reads a project in Todoist's CSV template. Trailing `@label` words of the
+/// content become labels, `INDENT` builds the subtask hierarchy and tasks
+/// below a `section` row go into that section.
+pub fn parse_todoist_csv(contents: &str, project_name: &str) -> Result<PlannedProject, anyhow::Error> {
+    let mut records = parse_csv_records(contents).into_iter();
+    let header = records
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("the CSV file is empty"))?;
+    let column = |name: &str| header.iter().position(|h| h.trim() == name);
+    let (type_column, content_column) = match (column("TYPE"), column("CONTENT")) {
+        (Some(type_column), Some(content_column)) => (type_column, content_column),
+        _ => anyhow::bail!("the CSV file has no TYPE and CONTENT columns"),
+    };
+    let description_column = column("DESCRIPTION");
+    let priority_column = column("PRIORITY");
+    let indent_column = column("INDENT");
+    let date_column = column("DATE");
+
+    let mut project = PlannedProject {
+        key: format!("project:{}", project_name),
+        parent_key: None,
+        project: PostProject {
+            name: project_name.to_owned(),
+            parent_id: None,
+        },
+        sections: vec![],
+        tasks: vec![],
+    };
+    let mut section_key = None;
+    // keys of the latest task on each indent level
+    let mut parents: Vec<String> = vec![];
+
+    for (row, record) in records.enumerate() {
+        let field = |index: Option<usize>| {
+            index
+                .and_then(|index| record.get(index))
+                .map(|field| field.trim().to_owned())
+                .unwrap_or_default()
+        };
+        match field(Some(type_column)).as_str() {
+            "task" => {}
+            "section" => {
+                parents.clear();
+                let key = format!("csv:{}", row + 2);
+                project.sections.push(PlannedSection {
+                    key: key.clone(),
+                    name: field(Some(content_column)),
+                });
+                section_key = Some(key);
+                continue;
+            }
+            _ => continue,
+        }
+
+        let mut content = vec![];
+        let mut labels = vec![];
+        let raw_content = field(Some(content_column));
+        for word in raw_content.split(' ') {
+            match word.strip_prefix('@') {
+                Some(label) if !label.is_empty() => labels.push(label.to_owned()),
+                _ => content.push(word),
+            }
+        }
+        let priority = match field(priority_column).parse::<usize>() {
+            Ok(p) if (1..=4).contains(&p) => api_priority(p),
+            _ => DEFAULT_PRIORITY,
+        };
+        let depth = field(indent_column)
+            .parse::<usize>()
+            .unwrap_or(1)
+            .clamp(1, parents.len() + 1)
+            - 1;
+        parents.truncate(depth);
+        let key = format!("csv:{}", row + 2);
+
+        project.tasks.push(PlannedTask {
+            key: key.clone(),
+            parent_key: parents.last().cloned(),
+            section_key: section_key.clone(),
+            depth,
+            content: TaskContent {
+                content: content.join(" ").trim().to_owned(),
+                description: field(description_column),
+                labels: labels.join(","),
+                priority,
+                due_string: field(date_column),
+                deadline: String::new(),
+                duration: String::new(),
+            },
+        });
+        parents.push(key);
+    }
+
+    Ok(project)
+}
+
+fn section_key(section_id: &str) -> String {
+    format!("json:section:{}", section_id)
+}
+
+fn plan_task_nodes(
+    tasks: &mut Vec<PlannedTask>,
+    project: &ProjectArchive,
+    nodes: &[TaskNode],
+    parent_key: Option<String>,
+    depth: usize,
+) {
+    for node in nodes {
+        let task = &node.task;
+        if task.is_completed {
+            continue;
+        }
+        let key = format!("json:{}", task.id);
+        // tasks of sections that failed to export stay in the project
+        let section_key = task
+            .section_id
+            .as_deref()
+            .filter(|id| project.sections.iter().any(|section| section.id == *id))
+            .map(section_key);
+        tasks.push(PlannedTask {
+            key: key.clone(),
+            parent_key: parent_key.clone(),
+            section_key,
+            depth,
+            content: TaskContent {
+                content: task.content.clone(),
+                description: task.description.clone(),
+                labels: task.labels.join(","),
+                priority: task.priority.clamp(1, 4),
+                due_string: task
+                    .due
+                    .as_ref()
+                    .and_then(|due| due.string.clone())
+                    .unwrap_or_default(),
+                deadline: task
+                    .deadline
+                    .as_ref()
+                    .map(|deadline| deadline.date.clone())
+                    .unwrap_or_default(),
+                duration: task.duration.map(format_duration).unwrap_or_default(),
+            },
+        });
+        plan_task_nodes(tasks, project, &node.children, Some(key), depth + 1);
+    }
+}
+
+/// Plans the projects below `parent_id`, each followed by its own subprojects.
+fn plan_projects(planned: &mut Vec<PlannedProject>, archive: &Archive, parent_id: Option<&str>) {
+    let is_root = |project: &ProjectArchive| match project.project.parent_id.as_deref() {
+        Some(parent_id) => !archive.projects.iter().any(|p| p.project.id == parent_id),
+        None => true,
+    };
+    for project in &archive.projects {
+        let below = match parent_id {
+            Some(parent_id) => project.project.parent_id.as_deref() == Some(parent_id),
+            None => is_root(project),
+        };
+        if !below {
+            continue;
+        }
+        let mut tasks = vec![];
+        plan_task_nodes(&mut tasks, project, &project.tasks, None, 0);
+        planned.push(PlannedProject {
+            key: format!("json:{}", project.project.id),
+            parent_key: parent_id.map(|id| format!("json:{}", id)),
+            project: PostProject {
+                name: project.project.name.clone(),
+                parent_id: None,
+            },
+            sections: project
+                .sections
+                .iter()
+                .map(|section| PlannedSection {
+                    key: section_key(&section.id),
+                    name: section.name.clone(),
+                })
+                .collect(),
+            tasks,
+        });
+        plan_projects(planned, archive, Some(&project.project.id));
+    }
+}
+
+/// Reads an archive written by `export --format json`, keeping the nesting
+/// of projects and the sections of tasks.
+pub fn parse_archive(contents: &str) -> Result<Vec<PlannedProject>, anyhow::Error> {
+    let archive: Archive = serde_json::from_str(contents)?;
+    let mut projects = vec![];
+    plan_projects(&mut projects, &archive, None);
+    Ok(projects)
+}
+
+/// Creates everything in `plan` that `state` does not list yet, saving the
+/// state after every item. Projects that already exist under the same name
+/// and parent are reused. `report` is called whenever the progress changes.
+pub async fn run_import(
+    token: String,
+    plan: &ImportPlan,
+    mut state: ImportState,
+    mut report: impl FnMut(&ImportProgress),
+) -> Result<(), anyhow::Error> {
+    let mut progress = ImportProgress {
+        started: true,
+        total: plan.task_count(),
+        ..Default::default()
+    };
+    report(&progress);
+
+    let result = create_planned(token, plan, &mut state, &mut progress, &mut report).await;
+    progress.finished = true;
+    progress.created = None;
+    if let Err(err) = &result {
+        progress.error = Some(err.to_string());
+    }
+    report(&progress);
+    result
+}
+
+async fn create_planned(
+    token: String,
+    plan: &ImportPlan,
+    state: &mut ImportState,
+    progress: &mut ImportProgress,
+    report: &mut impl FnMut(&ImportProgress),
+) -> Result<(), anyhow::Error> {
+    let state_path = plan.state_path();
+    let mut record_created = |state: &mut ImportState,
+                              progress: &mut ImportProgress,
+                              key: &str,
+                              id: String| {
+        state.created.insert(key.to_owned(), id.clone());
+        progress.created = Some((key.to_owned(), id));
+        report(progress);
+        state.save(&state_path)
+    };
+
+    let existing: Vec<Project> = get_projects(token.clone()).await?;
+    for planned_project in &plan.projects {
+        let project_id = match state.created.get(&planned_project.key) {
+            Some(id) => id.clone(),
+            None => {
+                let name = &planned_project.project.name;
+                let parent_id = planned_project
+                    .parent_key
+                    .as_ref()
+                    .and_then(|key| state.created.get(key))
+                    .cloned();
+                let id = match existing.iter().find(|p| &p.name == name && p.parent_id == parent_id) {
+                    Some(project) => project.id.clone(),
+                    None => {
+                        progress.current = name.clone();
+                        let project = PostProject {
+                            parent_id,
+                            ..planned_project.project.clone()
+                        };
+                        post_projects(token.clone(), project).await?.id
+                    }
+                };
+                record_created(state, progress, &planned_project.key, id.clone())?;
+                id
+            }
+        };
+
+        for planned_section in &planned_project.sections {
+            if !state.created.contains_key(&planned_section.key) {
+                progress.current = planned_section.name.clone();
+                let section = PostSection {
+                    name: planned_section.name.clone(),
+                    project_id: project_id.clone(),
+                };
+                let created = post_section(token.clone(), section).await?;
+                record_created(state, progress, &planned_section.key, created.id)?;
+            }
+        }
+
+        for planned_task in &planned_project.tasks {
+            progress.done += 1;
+            if !state.created.contains_key(&planned_task.key) {
+                progress.current = planned_task.content.content.clone();
+                let mut task = Task::temp(planned_task.content.clone(), project_id.clone());
+                task.parent_id = planned_task
+                    .parent_key
+                    .as_ref()
+                    .and_then(|key| state.created.get(key))
+                    .cloned();
+                task.section_id = planned_task
+                    .section_key
+                    .as_ref()
+                    .and_then(|key| state.created.get(key))
+                    .cloned();
+                let created = post_task(token.clone(), task).await?;
+                record_created(state, progress, &planned_task.key, created.id)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// One line per project and task, flagged `true` for projects. Entries that
+/// were already created are checked off.
+pub fn preview_lines(plan: &ImportPlan, state: &ImportState) -> Vec<(bool, String)> {
+    let mut lines = vec![];
+    for project in &plan.projects {
+        let mark = if state.created.contains_key(&project.key) { "✓" } else { " " };
+        let line = format!("{} {} ({} tasks)", mark, project.project.name, project.tasks.len());
+        lines.push((true, line));
+        for task in &project.tasks {
+            let mark = if state.created.contains_key(&task.key) { "✓" } else { " " };
+            let mut line = format!("{}   {}- {}", mark, "  ".repeat(task.depth), task.content.content);
+            if task.content.priority != DEFAULT_PRIORITY {
+                line.push_str(&format!(" [p{}]", shown_priority(task.content.priority)));
+            }
+            if !task.content.due_string.is_empty() {
+                line.push_str(&format!(" due {}", task.content.due_string));
+            }
+            for label in task.content.labels.split(',').filter(|l| !l.is_empty()) {
+                line.push_str(&format!(" @{}", label));
+            }
+            lines.push((false, line));
+        }
+    }
+    lines
+}
+
+pub fn render_import_preview(import_status: &ImportStatus, highlight_color: Color) -> Paragraph<'static> {
+    let progress = &import_status.progress;
+    let title = match (&progress.error, progress.finished, progress.started) {
+        (Some(err), _, _) => format!("Import failed: {} - Enter to retry, Esc to close", err),
+        (None, true, _) => format!("Imported {} tasks - Esc to close", progress.total),
+        (None, false, true) => format!("Importing {}/{}: {}", progress.done, progress.total, progress.current),
+        _ => format!(
+            "Import preview of {} - Enter to import, Esc to cancel",
+            import_status.plan.source.display()
+        ),
+    };
+    let block = create_advanced_block(title, highlight_color, Alignment::Left);
+
+    let lines: Vec<Spans> = preview_lines(&import_status.plan, &import_status.state)
+        .into_iter()
+        .map(|(is_project, line)| {
+            if is_project {
+                Spans::from(Span::styled(
+                    line,
+                    Style::default().fg(highlight_color).add_modifier(Modifier::BOLD),
+                ))
+            } else {
+                Spans::from(line)
+            }
+        })
+        .collect();
+
+    Paragraph::new(lines)
+        .block(block)
+        .wrap(Wrap { trim: false })
+        .scroll((import_status.scroll, 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        api::{Due, Section},
+        database::Database,
+        export::build_archive,
+    };
+
+    fn titles(project: &PlannedProject) -> Vec<&str> {
+        project.tasks.iter().map(|task| task.content.content.as_str()).collect()
+    }
+
+    #[test]
+    fn parses_todo_txt() {
+        let contents = "(A) 2026-10-19 Call mom +Family @phone due:2026-10-20\n\
+                        x 2026-10-18 Done already +Family\n\
+                        \n\
+                        Buy milk\n\
+                        (C) Write report +Work @office @focus\n";
+        let projects = parse_todo_txt(contents);
+
+        let names: Vec<&str> = projects.iter().map(|p| p.project.name.as_str()).collect();
+        assert_eq!(names, ["Family", DEFAULT_PROJECT, "Work"]);
+        assert_eq!(titles(&projects[0]), ["Call mom"]);
+        assert_eq!(titles(&projects[1]), ["Buy milk"]);
+
+        let call = &projects[0].tasks[0];
+        assert_eq!(call.key, "todo.txt:1");
+        assert_eq!(shown_priority(call.content.priority), 1);
+        assert_eq!(call.content.labels, "phone");
+        assert_eq!(call.content.due_string, "2026-10-20");
+        assert_eq!(projects[1].tasks[0].content.priority, DEFAULT_PRIORITY);
+
+        let report = &projects[2].tasks[0];
+        assert_eq!(report.key, "todo.txt:5");
+        assert_eq!(shown_priority(report.content.priority), 3);
+        assert_eq!(report.content.labels, "office,focus");
+        assert!(report.content.due_string.is_empty());
+    }
+
+    #[test]
+    fn parses_csv_records() {
+        let contents = "a,\"b, c\",\"say \"\"hi\"\"\"\r\n\"two\nlines\",,x\nlast";
+        assert_eq!(
+            parse_csv_records(contents),
+            [
+                vec!["a", "b, c", "say \"hi\""],
+                vec!["two\nlines", "", "x"],
+                vec!["last"],
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_todoist_csv() {
+        let contents = "TYPE,CONTENT,DESCRIPTION,PRIORITY,INDENT,DATE\n\
+                        task,Plan trip @travel,Book flights,1,1,tomorrow\n\
+                        task,Pack,,4,2,\n\
+                        task,Socks,,,3,\n\
+                        section,Later,,,,\n\
+                        task,Sort photos,,2,1,every sunday\n";
+        let project = parse_todoist_csv(contents, "Holiday").unwrap();
+
+        assert_eq!(project.project.name, "Holiday");
+        assert_eq!(project.sections.len(), 1);
+        assert_eq!(project.sections[0].key, "csv:5");
+        assert_eq!(project.sections[0].name, "Later");
+        assert_eq!(titles(&project), ["Plan trip", "Pack", "Socks", "Sort photos"]);
+
+        let [trip, pack, socks, photos] = &project.tasks[..] else {
+            panic!("expected four tasks");
+        };
+        assert_eq!(shown_priority(trip.content.priority), 1);
+        assert_eq!(trip.content.labels, "travel");
+        assert_eq!(trip.content.description, "Book flights");
+        assert_eq!(trip.content.due_string, "tomorrow");
+        assert_eq!((trip.parent_key.as_deref(), trip.depth), (None, 0));
+        assert_eq!(shown_priority(pack.content.priority), 4);
+        assert_eq!((pack.parent_key.as_deref(), pack.depth), (Some("csv:2"), 1));
+        assert_eq!(socks.content.priority, DEFAULT_PRIORITY);
+        assert_eq!((socks.parent_key.as_deref(), socks.depth), (Some("csv:3"), 2));
+        assert_eq!(trip.section_key, None);
+        assert_eq!(photos.section_key.as_deref(), Some("csv:5"));
+        assert_eq!((photos.parent_key.as_deref(), photos.depth), (None, 0));
+        assert_eq!(shown_priority(photos.content.priority), 2);
+        assert_eq!(photos.content.due_string, "every sunday");
+    }
+
+    #[test]
+    fn rejects_csv_without_the_template_columns() {
+        assert!(parse_todoist_csv("NAME,DATE\nfoo,today\n", "Holiday").is_err());
+        assert!(parse_todoist_csv("", "Holiday").is_err());
+    }
+
+    fn project(id: &str, name: &str, parent_id: Option<&str>) -> Project {
+        let mut project = Project::name(name);
+        project.id = id.to_owned();
+        project.parent_id = parent_id.map(str::to_owned);
+        project
+    }
+
+    fn task(id: &str, project_id: &str, section_id: Option<&str>, parent_id: Option<&str>) -> Task {
+        let content = TaskContent {
+            content: format!("Task {}", id),
+            ..Default::default()
+        };
+        let mut task = Task::temp(content, project_id.to_owned());
+        task.id = id.to_owned();
+        task.section_id = section_id.map(str::to_owned);
+        task.parent_id = parent_id.map(str::to_owned);
+        task
+    }
+
+    #[test]
+    fn json_archives_round_trip() {
+        // the subproject comes first to check that parents are planned first
+        let projects = vec![project("2", "Frontend", Some("1")), project("1", "Work", None)];
+        let sections = vec![Section {
+            id: "s1".to_owned(),
+            project_id: "2".to_owned(),
+            order: 1,
+            name: "Bugs".to_owned(),
+        }];
+        let mut fix = task("t1", "2", Some("s1"), None);
+        fix.priority = 4;
+        fix.labels = vec!["urgent".to_owned()];
+        fix.due = Some(Due {
+            date: Some("2026-10-19".to_owned()),
+            is_recurring: Some(true),
+            datetime: None,
+            timezone: None,
+            string: Some("every day".to_owned()),
+        });
+        let mut done = task("t4", "1", None, None);
+        done.is_completed = true;
+        let tasks = vec![
+            fix,
+            task("t2", "2", Some("s1"), Some("t1")),
+            task("t3", "1", None, None),
+            done,
+        ];
+        let database = Database::new(projects, sections, tasks, vec![]);
+        let contents = serde_json::to_string(&build_archive(&database)).unwrap();
+
+        let planned = parse_archive(&contents).unwrap();
+        let keys: Vec<(&str, Option<&str>)> = planned
+            .iter()
+            .map(|p| (p.key.as_str(), p.parent_key.as_deref()))
+            .collect();
+        assert_eq!(keys, [("json:1", None), ("json:2", Some("json:1"))]);
+
+        let (work, frontend) = (&planned[0], &planned[1]);
+        assert!(work.sections.is_empty());
+        assert_eq!(titles(work), ["Task t3"]);
+        assert_eq!(frontend.sections.len(), 1);
+        assert_eq!(frontend.sections[0].key, "json:section:s1");
+        assert_eq!(frontend.sections[0].name, "Bugs");
+
+        let [fix, sub] = &frontend.tasks[..] else {
+            panic!("expected two tasks");
+        };
+        assert_eq!(fix.key, "json:t1");
+        assert_eq!(fix.section_key.as_deref(), Some("json:section:s1"));
+        assert_eq!(shown_priority(fix.content.priority), 1);
+        assert_eq!(fix.content.labels, "urgent");
+        assert_eq!(fix.content.due_string, "every day");
+        assert_eq!((sub.parent_key.as_deref(), sub.depth), (Some("json:t1"), 1));
+        assert_eq!(sub.section_key.as_deref(), Some("json:section:s1"));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{project::ProjectStatus, task::{TaskStatus, TaskItem}, ProjectItem};
+use crate::{priority::DEFAULT_PRIORITY, project::ProjectStatus, task::{TaskStatus, TaskItem}, ProjectItem};
 
 pub fn push_char_to_field(
     e: char,
@@ -11,13 +11,8 @@ pub fn push_char_to_field(
         TaskItem::Desc => task_content.description.push(e),
         TaskItem::Label => task_content.labels.push(e),
         TaskItem::Due => task_content.due_string.push(e),
-        TaskItem::Prio => {
-            task_content.priority.push(e);
-            match task_content.priority.parse::<usize>() {
-                Ok(x) if (1..=4).contains(&x) => {}
-                _ => task_content.priority.clear(),
-            };
-        }
+        TaskItem::Deadline => task_content.deadline.push(e),
+        TaskItem::Duration => task_content.duration.push(e),
         _ => {}
     }
     match project_status.active_project_item {
@@ -33,7 +28,12 @@ pub fn remove_char_from_field(task_status: &mut TaskStatus, project_status: &mut
         TaskItem::Desc => task_content.description.pop(),
         TaskItem::Label => task_content.labels.pop(),
         TaskItem::Due => task_content.due_string.pop(),
-        TaskItem::Prio => task_content.priority.pop(),
+        TaskItem::Deadline => task_content.deadline.pop(),
+        TaskItem::Duration => task_content.duration.pop(),
+        TaskItem::Prio => {
+            task_content.priority = DEFAULT_PRIORITY;
+            None
+        }
         _ => None,
     };
     match project_status.active_project_item {
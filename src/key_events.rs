@@ -1,264 +1,248 @@
-use std::sync::{Arc, Mutex};
-
-use crossterm::event::{KeyCode, KeyEvent};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 
 use crate::{
-    api::{
-        delete_project, delete_task, get_projects, get_tasks, post_projects, post_task,
-        PostProject, Project, Task, TaskContent,
-    },
-    config::Config,
-    input::{push_char_to_field, remove_char_from_field},
-    menu::{cleanup, Database, MenuItem},
-    navigation::{
-        change_active_add_task_input_field, navigate_down_projects, navigate_down_tasks,
-        navigate_up_projects, navigate_up_tasks,
-    },
-    project::{ProjectItem, ProjectStatus},
-    task::{AddTaskHighlight, TaskItem, TaskStatus},
+    app::{Action, App},
+    calendar,
+    menu::MenuItem,
 };
+use Context::*;
 
-pub enum EventExit {
-    Exit,
-    Error(anyhow::Error),
-    Continue,
+/// Where a binding applies. Several can be active at once, see
+/// `active_contexts`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Context {
+    Help,
+    Calendar,
+    Confirm,
+    Picker,
+    Import,
+    Form,
+    Projects,
+    Tasks,
+    General,
 }
 
-pub fn get_key_event(
-    event: KeyEvent,
-    active_menu_item: &mut MenuItem,
-    task_status: &mut TaskStatus,
-    project_status: &mut ProjectStatus,
-    config: &Config,
-    database: Arc<Mutex<Database>>,
-) -> EventExit {
-    match event.code {
-        KeyCode::Esc
-            if *active_menu_item == MenuItem::AddTask
-                || *active_menu_item == MenuItem::AddProject =>
-        {
-            cleanup(active_menu_item, task_status, project_status)
-        }
-        KeyCode::Char(e)
-            if *active_menu_item == MenuItem::AddTask
-                || *active_menu_item == MenuItem::AddProject =>
-        {
-            push_char_to_field(e, task_status, project_status)
-        }
-        KeyCode::Backspace
-            if *active_menu_item == MenuItem::AddTask
-                || *active_menu_item == MenuItem::AddProject =>
-        {
-            remove_char_from_field(task_status, project_status)
-        }
-        KeyCode::Enter if *active_menu_item == MenuItem::AddTask => {
-            if let Some(selected) = project_status.project_table_state.selected() {
-                let current_selected_project =
-                    &database.lock().unwrap().projects[selected].id.clone();
-                let temp_task = Task::temp(
-                    task_status.task_content.clone(),
-                    current_selected_project.to_string(),
-                );
-                database.lock().unwrap().tasks.push(temp_task.clone());
-                let database_mutex = Arc::clone(&database);
-                let token2 = config.token.clone();
-                tokio::spawn(async move {
-                    let _ = post_task(token2.clone(), temp_task).await;
-                    database_mutex.lock().unwrap().tasks = get_tasks(token2).await.unwrap();
-                    database_mutex
-                        .lock()
-                        .unwrap()
-                        .tasks
-                        .sort_by(|a, b| a.project_id.cmp(&b.project_id));
-                });
-                task_status.task_content = TaskContent::default();
-                *active_menu_item = MenuItem::Projects;
-                task_status.add_task_highlight = AddTaskHighlight::default();
-                task_status.active_task_item = TaskItem::Empty;
-            }
+impl Context {
+    pub fn title(self) -> &'static str {
+        match self {
+            Context::Help => "Help",
+            Context::Calendar => "Calendar",
+            Context::Confirm => "Confirm",
+            Context::Picker => "Picker",
+            Context::Import => "Import",
+            Context::Form => "Forms",
+            Context::Projects => "Projects",
+            Context::Tasks => "Tasks",
+            Context::General => "General",
         }
-        KeyCode::Enter if *active_menu_item == MenuItem::AddProject => {
-            let temp_project = Project::name(&project_status.project_item.name);
-            database.lock().unwrap().projects.push(temp_project);
-            let token = config.token.clone();
-            let project_item = project_status.project_item.clone();
-            let database2 = Arc::clone(&database);
-            tokio::spawn(async move {
-                let _ = post_projects(token.clone(), project_item).await;
-                database2.lock().unwrap().projects = get_projects(token).await.unwrap();
-            });
-            project_status.project_item = PostProject::default();
-            *active_menu_item = MenuItem::Projects;
-            project_status.active_project_item = ProjectItem::Empty;
-        }
-        KeyCode::Tab if *active_menu_item == MenuItem::AddTask => {
-            change_active_add_task_input_field(task_status, config.color);
-        }
-        KeyCode::BackTab if *active_menu_item == MenuItem::AddTask => {
-            change_active_add_task_input_field(task_status, config.color);
-            change_active_add_task_input_field(task_status, config.color);
-            change_active_add_task_input_field(task_status, config.color);
-            change_active_add_task_input_field(task_status, config.color);
-        }
-        KeyCode::Char('q') => return EventExit::Exit,
-        KeyCode::Char('h') => match active_menu_item {
-            MenuItem::Home => *active_menu_item = MenuItem::Projects,
-            MenuItem::Projects => {
-                *active_menu_item = MenuItem::Home;
-                project_status.project_table_state.select(Some(0));
-            }
-            MenuItem::Tasks => {
-                *active_menu_item = MenuItem::Projects;
-            }
-            _ => {}
-        },
-        KeyCode::Char('l') => match active_menu_item {
-            MenuItem::Home => *active_menu_item = MenuItem::Projects,
-            MenuItem::Projects => {
-                if let Some(selected) = project_status.project_table_state.selected() {
-                    task_status.task_table_state.select(Some(0));
-                    let project_id = &database.lock().unwrap().projects[selected].id.clone();
-                    let tasks2 = database.lock().unwrap();
-                    let tasks_from_project: Vec<_> = tasks2
-                        .tasks
-                        .iter()
-                        .filter(|x| x.project_id == project_id.clone())
-                        .collect();
+    }
+}
+
+pub enum Key {
+    Code(KeyCode),
+    Ctrl(char),
+    /// Any character typed without Ctrl.
+    AnyChar,
+}
 
-                    if !tasks_from_project.is_empty() {
-                        *active_menu_item = MenuItem::Tasks;
-                    }
-                }
-            }
-            MenuItem::Tasks => {
-                *active_menu_item = MenuItem::Projects;
-            }
-            _ => {}
-        },
-        KeyCode::Char('p') => {
-            if let MenuItem::Projects | MenuItem::Tasks = active_menu_item {
-                project_status.active_project_item = ProjectItem::Name;
-                *active_menu_item = MenuItem::AddProject;
-            }
+impl Key {
+    fn matches(&self, event: &KeyEvent) -> bool {
+        let ctrl = event.modifiers.contains(KeyModifiers::CONTROL);
+        match *self {
+            Key::Code(code) => !ctrl && event.code == code,
+            Key::Ctrl(c) => ctrl && event.code == KeyCode::Char(c),
+            Key::AnyChar => !ctrl && matches!(event.code, KeyCode::Char(_)),
         }
-        KeyCode::Char('a') => {
-            if let MenuItem::Projects | MenuItem::Tasks = active_menu_item {
-                *active_menu_item = MenuItem::AddTask;
-                task_status.active_task_item = TaskItem::Name;
-                task_status.add_task_highlight.name = config.color;
-            }
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Key::Code(KeyCode::Char(' ')) => "Space".to_owned(),
+            Key::Code(KeyCode::Char(c)) => c.to_string(),
+            Key::Code(KeyCode::F(n)) => format!("F{}", n),
+            Key::Code(KeyCode::Up) => "Up".to_owned(),
+            Key::Code(KeyCode::Down) => "Down".to_owned(),
+            Key::Code(KeyCode::Tab) => "Tab".to_owned(),
+            Key::Code(KeyCode::BackTab) => "Shift-Tab".to_owned(),
+            Key::Code(code) => format!("{:?}", code),
+            Key::Ctrl(c) => format!("Ctrl-{}", c),
+            Key::AnyChar => "text".to_owned(),
         }
-        KeyCode::Char('d') => match active_menu_item {
-            MenuItem::Tasks => {
-                if let Some(selected) = task_status.task_table_state.selected() {
-                    if let Some(selected_project) = project_status.project_table_state.selected() {
-                        let mut task_count = 0;
+    }
+}
 
-                        let mut tasks_above_current = vec![];
-                        for i in 0..selected_project {
-                            let task_id_at_i = database.lock().unwrap().projects[i].id.clone();
-                            tasks_above_current.push(task_id_at_i);
-                        }
-                        database.lock().unwrap().tasks.iter().for_each(|task| {
-                            if tasks_above_current.iter().any(|s| *s == task.project_id) {
-                                task_count += 1;
-                            }
-                        });
+/// A key binding, used both to handle key presses and to list them in the
+/// help overlay.
+pub struct Binding {
+    pub context: Context,
+    pub keys: &'static [Key],
+    pub description: &'static str,
+    action: fn(KeyEvent) -> Action,
+}
 
-                        let task_at_select = database.lock().unwrap().tasks[task_count + selected]
-                            .id
-                            .clone();
-                        let token_c = config.token.clone();
-                        let database_c = Arc::clone(&database);
-                        tokio::spawn(async move {
-                            database_c
-                                .lock()
-                                .unwrap()
-                                .tasks
-                                .remove(selected + task_count);
-                            let _ = delete_task(token_c.clone(), task_at_select.to_string()).await;
-                            database_c.lock().unwrap().tasks = get_tasks(token_c).await.unwrap();
-                            database_c
-                                .lock()
-                                .unwrap()
-                                .tasks
-                                .sort_by(|a, b| a.project_id.cmp(&b.project_id));
-                        });
+impl Binding {
+    /// The keys of the binding joined for display, like `Down/Tab`.
+    pub fn keys_label(&self) -> String {
+        let labels: Vec<String> = self.keys.iter().map(Key::label).collect();
+        labels.join("/")
+    }
+}
 
-                        let mut task_count = 0;
-                        let tasks_len = database.lock().unwrap().tasks.len();
-                        (0..tasks_len).for_each(|i| {
-                            let selected_project_id = database.lock().unwrap().projects
-                                [selected_project]
-                                .id
-                                .clone();
-                            let project_id_from_task =
-                                database.lock().unwrap().tasks[i].project_id.clone();
-                            if selected_project_id == project_id_from_task {
-                                task_count += 1;
-                            }
-                        });
-                        let task_table_state = &mut task_status.task_table_state;
-                        if selected == 0 && task_count == 1 {
-                            *active_menu_item = MenuItem::Projects;
-                            task_table_state.select(Some(0));
-                        } else if selected > 0 {
-                            task_table_state.select(Some(selected - 1));
-                        }
-                    }
-                }
-            }
-            MenuItem::Projects => {
-                let project_table_state = &mut project_status.project_table_state;
-                if let Some(selected) = project_table_state.selected() {
-                    if selected == 0 {
-                        return EventExit::Continue;
-                    }
-                    let id = database.lock().unwrap().projects[selected].id.clone();
-                    let token = config.token.clone();
-                    tokio::spawn(async move { delete_project(token, id).await });
-                    database.lock().unwrap().projects.remove(selected);
-                    if selected > 0 {
-                        project_status
-                            .project_table_state
-                            .select(Some(selected - 1));
-                    } else {
-                        project_status.project_table_state.select(Some(0));
-                    }
-                }
-            }
-            _ => {}
-        },
-        KeyCode::Char('j') => match active_menu_item {
-            MenuItem::Projects => {
-                navigate_down_projects(
-                    &mut project_status.project_table_state,
-                    Arc::clone(&database).lock().unwrap().projects.len(),
-                );
-            }
-            MenuItem::Tasks => navigate_down_tasks(
-                Arc::clone(&database),
-                &mut task_status.task_table_state,
-                &project_status.project_table_state,
-            ),
+const fn bind(
+    context: Context,
+    keys: &'static [Key],
+    description: &'static str,
+    action: fn(KeyEvent) -> Action,
+) -> Binding {
+    Binding {
+        context,
+        keys,
+        description,
+        action,
+    }
+}
 
-            _ => {}
-        },
-        KeyCode::Char('k') => match active_menu_item {
-            MenuItem::Projects => {
-                navigate_up_projects(
-                    &mut project_status.project_table_state,
-                    Arc::clone(&database).lock().unwrap().projects.len(),
-                );
-            }
-            MenuItem::Tasks => navigate_up_tasks(
-                Arc::clone(&database),
-                &mut task_status.task_table_state,
-                &project_status.project_table_state,
-            ),
-            _ => {}
-        },
-        _ => {}
+fn typed(event: KeyEvent) -> char {
+    match event.code {
+        KeyCode::Char(c) => c,
+        _ => ' ',
     }
-    EventExit::Continue
+}
+
+const fn key(c: char) -> Key {
+    Key::Code(KeyCode::Char(c))
+}
+
+/// Every key binding. Within a context the first matching binding wins.
+#[rustfmt::skip]
+pub const KEYMAP: &[Binding] = &[
+    bind(Help, &[Key::Code(KeyCode::Esc), Key::Code(KeyCode::F(1))], "Close help", |_| Action::CloseHelp),
+    bind(Help, &[Key::Code(KeyCode::Down)], "Scroll down", |_| Action::HelpScrollDown),
+    bind(Help, &[Key::Code(KeyCode::Up)], "Scroll up", |_| Action::HelpScrollUp),
+    bind(Help, &[Key::Code(KeyCode::Backspace)], "Delete a search character", |_| Action::HelpBackspace),
+    bind(Help, &[Key::AnyChar], "Search bindings", |event| Action::HelpChar(typed(event))),
+    bind(Calendar, &[Key::Code(KeyCode::Esc)], "Close the calendar", |_| Action::CloseCalendar),
+    bind(Calendar, &[Key::Code(KeyCode::Enter)], "Pick the day", |_| Action::CalendarConfirm),
+    bind(Calendar, &[key('h'), Key::Code(KeyCode::Left)], "Previous day", |_| Action::CalendarDays(-1)),
+    bind(Calendar, &[key('l'), Key::Code(KeyCode::Right)], "Next day", |_| Action::CalendarDays(1)),
+    bind(Calendar, &[key('k'), Key::Code(KeyCode::Up)], "Previous week", |_| Action::CalendarDays(-7)),
+    bind(Calendar, &[key('j'), Key::Code(KeyCode::Down)], "Next week", |_| Action::CalendarDays(7)),
+    bind(Calendar, &[key('H')], "Previous month", |_| Action::CalendarMonths(-1)),
+    bind(Calendar, &[key('L')], "Next month", |_| Action::CalendarMonths(1)),
+    bind(Calendar, &[key('t')], "Today", |_| Action::CalendarQuick(calendar::TODAY)),
+    bind(Calendar, &[key('m')], "Tomorrow", |_| Action::CalendarQuick(calendar::TOMORROW)),
+    bind(Calendar, &[key('w')], "Next week", |_| Action::CalendarQuick(calendar::NEXT_WEEK)),
+    bind(Calendar, &[key('x')], "No date", |_| Action::CalendarQuick(calendar::NO_DATE)),
+    bind(Calendar, &[key('1')], "Every day", |_| Action::CalendarQuick(calendar::EVERY_DAY)),
+    bind(Calendar, &[key('2')], "Every weekday", |_| Action::CalendarQuick(calendar::EVERY_WEEKDAY)),
+    bind(Calendar, &[key('3')], "Every week", |_| Action::CalendarQuick(calendar::EVERY_WEEK)),
+    bind(Calendar, &[key('4')], "Every month", |_| Action::CalendarQuick(calendar::EVERY_MONTH)),
+    bind(Confirm, &[key('y'), Key::Code(KeyCode::Enter)], "Confirm", |_| Action::ConfirmYes),
+    bind(Confirm, &[key('n'), Key::Code(KeyCode::Esc)], "Cancel", |_| Action::ConfirmNo),
+    bind(Picker, &[Key::Code(KeyCode::Esc)], "Cancel", |_| Action::PickerCancel),
+    bind(Picker, &[Key::Code(KeyCode::Enter)], "Choose", |_| Action::PickerConfirm),
+    bind(Picker, &[Key::Code(KeyCode::Down), Key::Code(KeyCode::Tab)], "Next choice", |_| Action::PickerNext),
+    bind(Picker, &[Key::Code(KeyCode::Up), Key::Code(KeyCode::BackTab)], "Previous choice", |_| Action::PickerPrevious),
+    bind(Picker, &[Key::Code(KeyCode::Backspace)], "Delete a query character", |_| Action::PickerBackspace),
+    bind(Picker, &[Key::AnyChar], "Narrow down the choices", |event| Action::PickerChar(typed(event))),
+    bind(Import, &[Key::Code(KeyCode::Esc)], "Close the import", |_| Action::CloseImport),
+    bind(Import, &[Key::Code(KeyCode::Enter)], "Start the import", |_| Action::StartImport),
+    bind(Import, &[key('j')], "Scroll down", |_| Action::ScrollImportDown),
+    bind(Import, &[key('k')], "Scroll up", |_| Action::ScrollImportUp),
+    bind(Form, &[Key::Code(KeyCode::Esc)], "Close the form", |_| Action::CloseForm),
+    bind(Form, &[Key::Code(KeyCode::Enter)], "Submit", |_| Action::Submit),
+    bind(Form, &[Key::Code(KeyCode::Tab)], "Next field", |_| Action::NextField),
+    bind(Form, &[Key::Code(KeyCode::BackTab)], "Previous field", |_| Action::PreviousField),
+    bind(Form, &[Key::Code(KeyCode::Backspace)], "Delete a character", |_| Action::InputBackspace),
+    bind(Form, &[Key::Code(KeyCode::Down)], "Open the calendar of the due date", |_| Action::OpenCalendar),
+    bind(Form, &[Key::Code(KeyCode::F(1))], "Show help", |_| Action::OpenHelp),
+    bind(Form, &[Key::AnyChar], "Type into the field, or search the priorities", |event| Action::InputChar(typed(event))),
+    bind(Projects, &[key('P')], "Add a child project", |_| Action::OpenAddChildProject),
+    bind(Projects, &[key('R')], "Rename the project", |_| Action::OpenRenameProject),
+    bind(Projects, &[key('C')], "Change the project color", |_| Action::OpenColorPicker),
+    bind(Projects, &[key('f')], "Toggle favorite", |_| Action::ToggleFavorite),
+    bind(Projects, &[key('v')], "Switch between list and board", |_| Action::ToggleViewStyle),
+    bind(Projects, &[key('A')], "Archive the project", |_| Action::ArchiveSelected),
+    bind(Projects, &[key('U')], "Unarchive a project", |_| Action::OpenUnarchivePicker),
+    bind(Tasks, &[key(' ')], "Mark the task", |_| Action::ToggleMark),
+    bind(Tasks, &[key('V')], "Start or end a range of marks", |_| Action::ToggleVisual),
+    bind(Tasks, &[Key::Code(KeyCode::Esc)], "Clear the marks", |_| Action::ClearMarks),
+    bind(Tasks, &[key('1'), key('2'), key('3'), key('4')], "Set priority p1 to p4", |event| {
+        Action::SetPriority(typed(event).to_digit(10).unwrap_or(4) as usize)
+    }),
+    bind(Tasks, &[key('P')], "Pick a priority", |_| Action::OpenPriorityPicker),
+    bind(Tasks, &[key('+')], "Add a label", |_| Action::OpenLabelPicker { add: true }),
+    bind(Tasks, &[key('-')], "Remove a label", |_| Action::OpenLabelPicker { add: false }),
+    bind(Tasks, &[key('t')], "Reschedule", |_| Action::OpenReschedulePicker),
+    bind(Tasks, &[key('>')], "Move the card to the next section", |_| Action::MoveCardNext),
+    bind(Tasks, &[key('<')], "Move the card to the previous section", |_| Action::MoveCardPrevious),
+    bind(General, &[key('?'), Key::Code(KeyCode::F(1))], "Show help", |_| Action::OpenHelp),
+    bind(General, &[Key::Ctrl('r')], "Redo", |_| Action::Redo),
+    bind(General, &[key('q')], "Quit", |_| Action::Quit),
+    bind(General, &[key('r')], "Refresh", |_| Action::Refresh),
+    bind(General, &[key('h')], "Focus the pane to the left", |_| Action::MenuLeft),
+    bind(General, &[key('l')], "Focus the pane to the right", |_| Action::MenuRight),
+    bind(General, &[key('j')], "Select the next item", |_| Action::SelectNext),
+    bind(General, &[key('k')], "Select the previous item", |_| Action::SelectPrevious),
+    bind(General, &[key('p')], "Add a project", |_| Action::OpenAddProject),
+    bind(General, &[key('a')], "Add a task", |_| Action::OpenAddTask),
+    bind(General, &[key('e')], "Export", |_| Action::Export),
+    bind(General, &[key('d')], "Delete", |_| Action::DeleteSelected),
+    bind(General, &[key('c')], "Complete", |_| Action::CloseSelected),
+    bind(General, &[key('m')], "Move to another project or section", |_| Action::OpenMovePicker),
+    bind(General, &[key('K')], "Move up", |_| Action::MoveSelectedUp),
+    bind(General, &[key('J')], "Move down", |_| Action::MoveSelectedDown),
+    bind(General, &[key('s')], "Cycle the sort mode", |_| Action::CycleSortMode),
+    bind(General, &[key('u')], "Undo", |_| Action::Undo),
+];
+
+/// Contexts whose bindings apply to the current screen, ignoring the help
+/// overlay, most specific first.
+pub fn active_contexts(app: &App) -> Vec<Context> {
+    if app.calendar.is_some() {
+        return vec![Calendar];
+    }
+    if app.confirm.is_some() {
+        return vec![Confirm];
+    }
+    if app.popup.is_some() {
+        return vec![Picker];
+    }
+    match app.active_menu_item {
+        MenuItem::Import => vec![Import, General],
+        MenuItem::AddTask | MenuItem::AddProject => vec![Form],
+        MenuItem::Projects => vec![Projects, General],
+        MenuItem::Tasks => vec![Tasks, General],
+        MenuItem::Home => vec![General],
+    }
+}
+
+/// Bindings of the current screen whose keys or description contain `query`.
+pub fn help_entries(app: &App, query: &str) -> Vec<&'static Binding> {
+    let query = query.to_lowercase();
+    active_contexts(app)
+        .into_iter()
+        .flat_map(|context| KEYMAP.iter().filter(move |binding| binding.context == context))
+        .filter(|binding| {
+            binding.description.to_lowercase().contains(&query)
+                || binding.keys_label().to_lowercase().contains(&query)
+        })
+        .collect()
+}
+
+/// Maps a key press to the action it triggers in the current menu.
+pub fn get_action(event: KeyEvent, app: &App) -> Option<Action> {
+    let contexts = if app.help.is_some() {
+        vec![Help]
+    } else {
+        active_contexts(app)
+    };
+    contexts
+        .into_iter()
+        .find_map(|context| {
+            KEYMAP.iter().find(|binding| {
+                binding.context == context && binding.keys.iter().any(|key| key.matches(&event))
+            })
+        })
+        .map(|binding| (binding.action)(event))
 }
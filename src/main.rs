@@ -1,58 +1,140 @@
 use anyhow::Result;
-use api::get_tasks;
-use chunks::Chunks;
+use app::{Action, App, Popup};
+use calendar::render_calendar;
+use chunks::{split_form, Chunks, PROJECT_FORM_HEIGHT, TASK_FORM_HEIGHT};
+use cli::{parse_args, Command, USAGE};
 use config::{get_config, Config};
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event as CEvent},
+    event::{
+        DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+        Event as CEvent, EventStream, KeyEvent, MouseEvent,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use key_events::{get_key_event, EventExit};
-use menu::{render_active_menu_widget, Database, MenuItem};
+use effects::run_effect;
+use futures::StreamExt;
+use help::render_help;
+use import::{read_plan, render_import_preview, ImportStatus};
+use key_events::{get_action, help_entries};
+use menu::{menu_tab_areas, render_active_menu_widget, MenuItem};
 use menu::{render_key_tabs, render_menu_tabs};
-use project::{render_project_item, ProjectItem, ProjectStatus};
-use task::{render_active_task_input_widget, TaskStatus};
+use mouse::{get_mouse_action, ClickMap};
+use picker::render_picker;
+use popup::render_confirm;
+use project::{render_project_item, ProjectItem};
+use status::render_status_bar;
+use task::{render_active_task_input_widget, task_form_fields, TASK_FIELDS};
+use tokio::{
+    sync::mpsc::unbounded_channel,
+    time::{Interval, MissedTickBehavior},
+};
 
-use std::{io, sync::mpsc};
 use std::{
-    sync::Arc,
+    env,
+    future::pending,
+    io,
+    process::exit,
     time::{Duration, Instant},
 };
-use std::{sync::Mutex, thread};
 use tui::backend::Backend;
 use tui::{backend::CrosstermBackend, Terminal};
 
 pub mod api;
+pub mod app;
+pub mod board;
+pub mod calendar;
 pub mod chunks;
+pub mod cli;
 pub mod config;
+pub mod database;
+pub mod date;
+pub mod due_parser;
+pub mod effects;
+pub mod export;
 pub mod handler;
+pub mod help;
+pub mod history;
 pub mod home;
+pub mod ics;
+pub mod import;
 pub mod input;
 pub mod key_events;
 pub mod menu;
+pub mod mouse;
 pub mod navigation;
+pub mod output;
+pub mod picker;
+pub mod popup;
+pub mod priority;
 pub mod project;
+pub mod sort;
+pub mod status;
+pub mod sync;
 pub mod task;
-use crate::api::get_projects;
+pub mod taskwarrior;
+pub mod uuid;
 
-#[derive(Copy, Clone, Debug)]
-enum Event<I> {
-    Input(I),
+#[derive(Debug)]
+enum Event {
+    Input(KeyEvent),
+    Mouse(MouseEvent),
+    Resize,
+    Paste(String),
+    ApiResult(Action),
     Tick,
+    Refresh,
+}
+
+/// Waits for the next background refresh, forever when they are off.
+async fn next_refresh(refresh: &mut Option<Interval>) {
+    match refresh {
+        Some(interval) => {
+            interval.tick().await;
+        }
+        None => pending().await,
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<(), anyhow::Error> {
+    let (token, import_status) = match parse_args(env::args().skip(1).collect()) {
+        Ok(Command::Tui(token)) => (token, None),
+        Ok(Command::Import(options)) if options.interactive() => {
+            match read_plan(&options.file, options.format) {
+                Ok(plan) => (None, Some(ImportStatus::new(plan))),
+                Err(err) => {
+                    eprintln!("error: {}", err);
+                    exit(1);
+                }
+            }
+        }
+        Ok(Command::Help) => {
+            println!("{}", USAGE);
+            return Ok(());
+        }
+        Ok(command) => exit(cli::run(command, &get_config(None)).await),
+        Err(err) => {
+            let (message, code) = cli::usage_error(&err);
+            eprintln!("{}", message);
+            exit(code);
+        }
+    };
+    let config: Config = get_config(token);
+
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(
+        stdout,
+        EnterAlternateScreen,
+        EnableMouseCapture,
+        EnableBracketedPaste
+    )?;
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
     terminal.clear()?;
 
-    let config: Config = get_config();
-
-    let res = run_app(&mut terminal, config);
+    let res = run_app(&mut terminal, config, import_status).await;
 
     if let Err(err) = res {
         println!("{:?}", err)
@@ -63,104 +145,146 @@ async fn main() -> Result<(), anyhow::Error> {
         terminal.backend_mut(),
         LeaveAlternateScreen,
         DisableMouseCapture,
+        DisableBracketedPaste,
     )?;
     terminal.show_cursor()?;
 
     Ok(())
 }
 
-pub fn run_app<B: Backend>(
+pub async fn run_app<B: Backend>(
     terminal: &mut Terminal<B>,
     config: Config,
+    import_status: Option<ImportStatus>,
 ) -> Result<(), anyhow::Error> {
-    let (tx, rx) = mpsc::channel();
-    let tick_rate = Duration::from_millis(200);
-    thread::spawn(move || {
-        let mut last_tick = Instant::now();
-        loop {
-            let timeout = tick_rate
-                .checked_sub(last_tick.elapsed())
-                .unwrap_or_else(|| Duration::from_secs(0));
-
-            if event::poll(timeout).expect("poll works") {
-                if let CEvent::Key(key) = event::read().expect("can read events") {
-                    tx.send(Event::Input(key)).expect("can send events");
-                }
-            }
+    let mut events = EventStream::new();
+    let mut ticks = tokio::time::interval(Duration::from_millis(200));
+    let mut refresh = config.refresh_interval.map(|period| {
+        let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        interval
+    });
+
+    let (action_tx, mut action_rx) = unbounded_channel();
+    let mut app = App::new(&config, import_status);
+    for effect in app.init() {
+        run_effect(effect, &config, &action_tx);
+    }
+
+    let mut changed = true;
+    while !app.should_quit {
+        if changed {
+            draw(terminal, &mut app, &config)?;
+        }
+
+        let event = tokio::select! {
+            Some(event) = events.next() => match event? {
+                CEvent::Key(key) => Event::Input(key),
+                CEvent::Mouse(mouse) => Event::Mouse(mouse),
+                CEvent::Resize(..) => Event::Resize,
+                CEvent::Paste(text) => Event::Paste(text),
+                CEvent::FocusGained | CEvent::FocusLost => continue,
+            },
+            Some(action) = action_rx.recv() => Event::ApiResult(action),
+            _ = ticks.tick() => Event::Tick,
+            _ = next_refresh(&mut refresh) => Event::Refresh,
+        };
 
-            if last_tick.elapsed() >= tick_rate && tx.send(Event::Tick).is_ok() {
-                last_tick = Instant::now();
+        let action = match event {
+            Event::Input(key) => get_action(key, &app),
+            Event::Paste(text) => Some(Action::InputPaste(text)),
+            Event::ApiResult(action) => Some(action),
+            Event::Resize => {
+                changed = true;
+                continue;
+            }
+            Event::Mouse(mouse) => get_mouse_action(mouse, &app),
+            Event::Tick if app.toast_expired(Instant::now()) => Some(Action::DismissToast),
+            Event::Tick => None,
+            Event::Refresh => Some(Action::Refresh),
+        };
+        changed = action.is_some();
+        if let Some(action) = action {
+            for effect in app.update(action) {
+                run_effect(effect, &config, &action_tx);
             }
         }
-    });
+    }
+    Ok(())
+}
 
-    let mut active_menu_item = MenuItem::Home;
-    let mut task_status = TaskStatus::default();
-    task_status.task_table_state.select(Some(0));
+fn draw<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    config: &Config,
+) -> Result<(), anyhow::Error> {
+    terminal.draw(|rect| {
+        let size = rect.size();
 
-    let mut project_status = ProjectStatus::default();
-    project_status.project_table_state.select(Some(0));
+        let chunks = Chunks::create_chunks(size, &config.layout, app.sidebar_focused());
 
-    let database = Arc::new(Mutex::new(Database::default()));
+        app.click_map = ClickMap {
+            menu_tabs: menu_tab_areas(chunks.menu_tabs),
+            ..Default::default()
+        };
 
-    let (database_mutex, token_mutex) = (Arc::clone(&database), config.token.clone());
-    tokio::spawn(async move {
-        database_mutex.lock().unwrap().projects = get_projects(token_mutex).await.unwrap();
-    });
+        let menu_tabs = render_menu_tabs(app.active_menu_item, config.color);
+        rect.render_widget(menu_tabs, chunks.menu_tabs);
+        if let Some(area) = chunks.key_tabs {
+            rect.render_widget(render_key_tabs(config.color), area);
+        }
 
-    let (database_mutex, token_mutex) = (Arc::clone(&database), config.token.clone());
-    tokio::spawn(async move {
-        database_mutex.lock().unwrap().tasks = get_tasks(token_mutex).await.unwrap();
-    });
+        render_active_menu_widget(rect, app, &chunks);
 
-    loop {
-        terminal.draw(|rect| {
-            let size = rect.size();
-
-            let chunks = Chunks::create_chunks(size);
-
-            let menu_tabs = render_menu_tabs(active_menu_item, config.color);
-            rect.render_widget(menu_tabs, chunks.menu_or_keybinds[0]);
-            let key_tabs = render_key_tabs(config.color);
-            rect.render_widget(key_tabs, chunks.menu_or_keybinds[1]);
-
-            render_active_menu_widget(
-                rect,
-                active_menu_item,
-                Arc::clone(&database),
-                &mut project_status,
-                &mut task_status,
-                &config,
-                &chunks,
-            );
+        if let (ProjectItem::Name, Some(sidebar)) =
+            (app.project_status.active_project_item, chunks.sidebar)
+        {
+            let area = split_form(sidebar, PROJECT_FORM_HEIGHT).0;
+            render_project_item(rect, area, &app.project_status, config.color);
+        }
 
-            if project_status.active_project_item == ProjectItem::Name {
-                render_project_item(
-                    rect,
-                    chunks.add_project_with_projects,
-                    &project_status.project_item,
-                    config.color,
-                );
+        if let Some(main) = chunks.main {
+            let area = split_form(main, TASK_FORM_HEIGHT).0;
+            if app.active_menu_item == MenuItem::AddTask {
+                app.click_map.task_fields =
+                    task_form_fields(area).into_iter().zip(TASK_FIELDS).collect();
             }
+            render_active_task_input_widget(rect, &app.task_status, &app.dates, area);
+        }
 
-            render_active_task_input_widget(rect, &task_status, chunks.projects_or_tasks);
-        })?;
-
-        match rx.recv().unwrap() {
-            Event::Input(event) => match get_key_event(
-                event,
-                &mut active_menu_item,
-                &mut task_status,
-                &mut project_status,
-                &config,
-                Arc::clone(&database),
-            ) {
-                EventExit::Exit => break,
-                EventExit::Error(err) => return Err(err),
-                EventExit::Continue => {}
-            },
-            Event::Tick => {}
+        if let (MenuItem::Import, Some(import_status)) = (app.active_menu_item, &app.import_status)
+        {
+            rect.render_widget(
+                render_import_preview(import_status, config.color),
+                chunks.body,
+            );
         }
-    }
+
+        render_status_bar(rect, app, config.color, chunks.status_bar);
+
+        match &app.popup {
+            Some(Popup::Move(picker)) => render_picker(rect, picker, config.color, size),
+            Some(Popup::Priority(picker)) => render_picker(rect, picker, config.color, size),
+            Some(
+                Popup::AddLabel(picker)
+                | Popup::RemoveLabel(picker)
+                | Popup::Reschedule(picker)
+                | Popup::ProjectColor(picker)
+                | Popup::Unarchive(picker),
+            ) => render_picker(rect, picker, config.color, size),
+            None => {}
+        }
+        if let Some(calendar) = &app.calendar {
+            let today = app.dates.zone.now().date();
+            render_calendar(rect, calendar, today, config.color, size);
+        }
+        if let Some(confirm) = &app.confirm {
+            render_confirm(rect, confirm, config.color, size);
+        }
+        if let Some(help) = &app.help {
+            let entries = help_entries(app, &help.query);
+            render_help(rect, help, &entries, config.color, size);
+        }
+    })?;
     Ok(())
 }
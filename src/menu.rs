@@ -1,22 +1,24 @@
-use std::{sync::{Arc, Mutex}, io};
-
 use tui::{
     backend::Backend,
-    layout::Alignment,
+    layout::{Alignment, Rect},
     style::{Color, Modifier, Style},
     symbols,
     text::{Span, Spans},
-    widgets::{Block, Borders, Tabs},
+    widgets::{Block, Borders, TableState, Tabs},
     Frame,
 };
 
 use crate::{
-    api::{PostProject, Project, Task, TaskContent},
-    chunks::Chunks,
-    config::Config,
+    api::{PostProject, TaskContent},
+    app::App,
+    board::{board_columns, render_board},
+    chunks::{split_form, Chunks, PROJECT_FORM_HEIGHT, TASK_FORM_HEIGHT},
+    sort::sorted_project_tasks,
     home::render_home,
+    mouse::row_regions,
+    navigation::scroll_offset,
     project::{get_project_table_list, ProjectItem, ProjectStatus},
-    task::{get_task_table_list, AddTaskHighlight, TaskItem, TaskStatus},
+    task::{get_task_table_list, task_row_heights, AddTaskHighlight, TaskItem, TaskStatus},
 };
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -26,6 +28,7 @@ pub enum MenuItem {
     Tasks,
     AddProject,
     AddTask,
+    Import,
 }
 
 impl From<MenuItem> for usize {
@@ -36,126 +39,109 @@ impl From<MenuItem> for usize {
             MenuItem::Tasks => 2,
             MenuItem::AddTask => 3,
             MenuItem::AddProject => 4,
+            MenuItem::Import => 5,
         }
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct Database {
-    pub projects: Vec<Project>,
-    pub tasks: Vec<Task>,
-}
-
-impl Default for Database {
-    fn default() -> Self {
-        Self {
-            projects: vec![Project::name("Loading...")],
-            tasks: vec![],
-        }
-    }
-}
-
-pub fn render_active_menu_widget<B: Backend> (
+pub fn render_active_menu_widget<B: Backend>(
     rect: &mut Frame<B>,
-    active_menu_item: MenuItem,
-    database: Arc<Mutex<Database>>,
-    project_status: &mut ProjectStatus,
-    task_status: &mut TaskStatus,
-    config: &Config,
+    app: &mut App,
     chunks: &Chunks,
 ) {
-    let highlight_color = config.color;
-
-    match active_menu_item {
-        MenuItem::Home => rect.render_widget(render_home(), chunks.bottom_fullscreen[0]),
-        MenuItem::Projects => {
-            let project_table = get_project_table_list(
-                &database,
-                highlight_color,
-                highlight_color,
-            );
+    let highlight_color = app.color;
+    let menu = app.active_menu_item;
+    let database = &app.database;
+    let dates = &app.dates;
+    let project_id = app.selected_project().map_or("", |project| project.id.as_str());
+    let sort_mode = app.sort_mode(project_id);
+    let tasks = sorted_project_tasks(database, project_id, sort_mode);
+    let marked = app.marked_task_ids();
+    let board = app.is_board();
+    let selected_id = app.selected_task().map(|task| task.id.clone());
+    let columns = if board {
+        board_columns(database, project_id, &tasks)
+    } else {
+        vec![]
+    };
+    let project_position = app.project_status.project_table_state.selected().unwrap_or(0);
+    let task_position = app.task_status.task_table_state.selected().unwrap_or(0);
+    let project_status = &mut app.project_status;
+    let task_status = &mut app.task_status;
+    let clicks = &mut app.click_map;
+
+    match menu {
+        MenuItem::Home => {
+            rect.render_widget(render_home(), chunks.body);
+            return;
+        }
+        MenuItem::Import => return,
+        _ => {}
+    }
 
-            let task_table = get_task_table_list(
-                &project_status.project_table_state,
-                Arc::clone(&database),
-                Color::White,
-                highlight_color,
-            );
-            rect.render_stateful_widget(
-                project_table,
-                chunks.projects_or_tasks[0],
-                &mut project_status.project_table_state,
-            );
-            rect.render_widget(task_table, chunks.projects_or_tasks[1]);
+    if let Some(sidebar) = chunks.sidebar {
+        let area = match menu {
+            MenuItem::AddProject => split_form(sidebar, PROJECT_FORM_HEIGHT).1,
+            _ => sidebar,
+        };
+        let selection_color = match menu {
+            MenuItem::Projects => highlight_color,
+            _ => Color::White,
+        };
+        let heights = vec![1; database.projects().len()];
+        let max_height = area.height.saturating_sub(2);
+        let offset = scroll_offset(&heights, project_position, project_status.offset, max_height);
+        project_status.offset = offset;
+        for (region, i) in row_regions(area, &heights, offset) {
+            clicks.projects.push((region, database.projects()[i].id.clone()));
         }
-        MenuItem::Tasks => {
-            let project_table = get_project_table_list(
-                &database,
-                Color::White,
-                config.color,
-            );
-            let task_table = get_task_table_list(
-                &project_status.project_table_state,
-                Arc::clone(&database),
-                Color::White,
-                highlight_color,
-            );
-            rect.render_stateful_widget(
-                project_table,
-                chunks.projects_or_tasks[0],
-                &mut project_status.project_table_state,
-            );
-            rect.render_stateful_widget(
-                task_table,
-                chunks.projects_or_tasks[1],
-                &mut task_status.task_table_state,
+        let project_table =
+            get_project_table_list(database, offset, selection_color, highlight_color);
+        let mut state = TableState::default();
+        state.select(Some(project_position.saturating_sub(offset)));
+        rect.render_stateful_widget(project_table, area, &mut state);
+    }
+
+    if let Some(main) = chunks.main {
+        let area = match menu {
+            MenuItem::AddTask => split_form(main, TASK_FORM_HEIGHT).1,
+            _ => main,
+        };
+        let focused = menu == MenuItem::Tasks;
+        if board {
+            let selected_id = selected_id.as_deref();
+            clicks.tasks = render_board(
+                rect,
+                &columns,
+                selected_id,
+                &marked,
+                Some(highlight_color).filter(|_| focused),
+                dates,
+                area,
             );
+            return;
         }
-        MenuItem::AddTask => {
-            let project_table = get_project_table_list(
-                &database,
-                Color::White,
-                config.color,
-            );
-            let task_table = get_task_table_list(
-                &project_status.project_table_state,
-                Arc::clone(&database),
-                Color::White,
-                highlight_color,
-            );
-            rect.render_stateful_widget(
-                project_table,
-                chunks.projects_or_tasks[0],
-                &mut project_status.project_table_state,
-            );
-            rect.render_widget(task_table, chunks.tasks_with_add_task[1]);
+        let heights = task_row_heights(&tasks, area.width, dates);
+        let max_height = area.height.saturating_sub(2);
+        let offset = scroll_offset(&heights, task_position, task_status.offset, max_height);
+        task_status.offset = offset;
+        for (region, i) in row_regions(area, &heights, offset) {
+            clicks.tasks.push((region, tasks[i].id.clone()));
         }
-        MenuItem::AddProject => {
-            let project_table = get_project_table_list(
-                &database,
-                Color::White,
-                config.color,
-            );
-            let task_table = get_task_table_list(
-                &project_status.project_table_state,
-                Arc::clone(&database),
-                Color::White,
-                highlight_color,
-            );
-            let name_len = project_status.project_item.name.len();
-            let mut next_line_buffer = 0;
-            if name_len >= 25 {
-                next_line_buffer = 3;
-            }
-            rect.set_cursor(
-                chunks.add_project_with_projects[0].x + 1 + name_len as u16
-                    - ((name_len / 25) * 25) as u16
-                    + next_line_buffer,
-                chunks.add_project_with_projects[0].y + 1,
-            );
-            rect.render_widget(project_table, chunks.project_with_add_project[0]);
-            rect.render_widget(task_table, chunks.projects_or_tasks[1]);
+        let task_table = get_task_table_list(
+            tasks.get(offset..).unwrap_or_default(),
+            &marked,
+            sort_mode,
+            Color::White,
+            highlight_color,
+            area.width,
+            dates,
+        );
+        let mut state = TableState::default();
+        if focused {
+            state.select(Some(task_position.saturating_sub(offset)));
         }
+        rect.render_stateful_widget(task_table, area, &mut state);
     }
 }
 
@@ -171,15 +157,33 @@ pub fn cleanup(
     task_status.active_task_item = TaskItem::Empty;
 
     project_status.project_item = PostProject::default();
+    project_status.editing_id = None;
     project_status.active_project_item = ProjectItem::Empty;
 }
 
-pub fn render_menu_tabs(active_menu_item: MenuItem, config_color: Color) -> Tabs<'static> {
-    let menu_titles = vec!["Home", "Projects", "Tasks"];
+const MENU_TABS: [(&str, MenuItem); 3] = [
+    ("Home", MenuItem::Home),
+    ("Projects", MenuItem::Projects),
+    ("Tasks", MenuItem::Tasks),
+];
+
+/// Where `render_menu_tabs` draws each tab inside `area`.
+pub fn menu_tab_areas(area: Rect) -> Vec<(Rect, MenuItem)> {
+    // titles start after a space and are divided by " · "
+    let mut x = area.x + 2;
+    let mut areas = vec![];
+    for (title, item) in MENU_TABS {
+        let width = title.chars().count() as u16;
+        areas.push((Rect::new(x, area.y + 1, width, 1), item));
+        x += width + 3;
+    }
+    areas
+}
 
-    let menu: Vec<_> = menu_titles
+pub fn render_menu_tabs(active_menu_item: MenuItem, config_color: Color) -> Tabs<'static> {
+    let menu: Vec<_> = MENU_TABS
         .iter()
-        .map(|t| {
+        .map(|(t, _)| {
             Spans::from(Span::styled(
                 t.to_owned(),
                 Style::default().fg(Color::White),
@@ -207,7 +211,7 @@ pub fn render_menu_tabs(active_menu_item: MenuItem, config_color: Color) -> Tabs
 }
 
 pub fn render_key_tabs(config_color: Color) -> Tabs<'static> {
-    let key_titles = vec!["Add Task", "Post Project", "Delete", "Quit"];
+    let key_titles = ["Add Task", "Post Project", "Delete", "Export", "Quit", "? Help"];
     let keybinds: Vec<_> = key_titles
         .iter()
         .map(|t| {
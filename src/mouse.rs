@@ -0,0 +1,89 @@
+use crossterm::event::{MouseButton, MouseEvent, MouseEventKind};
+use tui::layout::Rect;
+
+use crate::{
+    app::{Action, App},
+    menu::MenuItem,
+    task::TaskItem,
+};
+
+/// Screen regions recorded while drawing, so that a click can be mapped back
+/// to what was drawn under it.
+#[derive(Default)]
+pub struct ClickMap {
+    pub menu_tabs: Vec<(Rect, MenuItem)>,
+    /// Project rows by project id.
+    pub projects: Vec<(Rect, String)>,
+    /// Task rows and board cards by task id.
+    pub tasks: Vec<(Rect, String)>,
+    pub task_fields: Vec<(Rect, TaskItem)>,
+}
+
+fn hit<T: Clone>(regions: &[(Rect, T)], column: u16, row: u16) -> Option<T> {
+    regions
+        .iter()
+        .find(|(area, _)| {
+            (area.left()..area.right()).contains(&column)
+                && (area.top()..area.bottom()).contains(&row)
+        })
+        .map(|(_, value)| value.clone())
+}
+
+/// Where rows `heights` tall are drawn inside the borders of `area`,
+/// starting with row `offset`, paired with their index.
+pub fn row_regions(area: Rect, heights: &[u16], offset: usize) -> Vec<(Rect, usize)> {
+    let bottom = area.bottom().saturating_sub(1);
+    let mut y = area.y + 1;
+    let mut regions = vec![];
+    for (i, &height) in heights.iter().enumerate().skip(offset) {
+        if y + height > bottom {
+            break;
+        }
+        regions.push((
+            Rect::new(area.x + 1, y, area.width.saturating_sub(2), height),
+            i,
+        ));
+        y += height;
+    }
+    regions
+}
+
+/// Maps the mouse wheel to moving the selection like `j` and `k`, and a left
+/// click to whatever was drawn under it.
+pub fn get_mouse_action(event: MouseEvent, app: &App) -> Option<Action> {
+    if app.help.is_some() {
+        return match event.kind {
+            MouseEventKind::ScrollDown => Some(Action::HelpScrollDown),
+            MouseEventKind::ScrollUp => Some(Action::HelpScrollUp),
+            _ => None,
+        };
+    }
+    if app.confirm.is_some() || app.popup.is_some() || app.calendar.is_some() {
+        return None;
+    }
+    let clicks = &app.click_map;
+    match (app.active_menu_item, event.kind) {
+        (MenuItem::Import, MouseEventKind::ScrollDown) => Some(Action::ScrollImportDown),
+        (MenuItem::Import, MouseEventKind::ScrollUp) => Some(Action::ScrollImportUp),
+        (MenuItem::Projects | MenuItem::Tasks, MouseEventKind::ScrollDown) => {
+            Some(Action::SelectNext)
+        }
+        (MenuItem::Projects | MenuItem::Tasks, MouseEventKind::ScrollUp) => {
+            Some(Action::SelectPrevious)
+        }
+        (MenuItem::AddTask, MouseEventKind::Down(MouseButton::Left)) => {
+            hit(&clicks.task_fields, event.column, event.row).map(Action::FocusField)
+        }
+        (
+            MenuItem::Home | MenuItem::Projects | MenuItem::Tasks,
+            MouseEventKind::Down(MouseButton::Left),
+        ) => {
+            let (column, row) = (event.column, event.row);
+            hit(&clicks.menu_tabs, column, row)
+                .map(Action::OpenMenu)
+                .or_else(|| hit(&clicks.projects, column, row).map(Action::ClickProject))
+                .or_else(|| hit(&clicks.tasks, column, row).map(Action::ClickTask))
+        }
+        _ => None,
+    }
+}
@@ -1,18 +1,12 @@
-use std::sync::{Arc, Mutex};
+use tui::{style::Color, widgets::TableState};
 
-use tui::{
-    style::{Color, Style},
-    text::{Span, Spans},
-    widgets::{ListItem, TableState},
-};
-
-use crate::{menu::Database, task::{TaskStatus, TaskItem, AddTaskHighlight}};
+use crate::task::{TaskStatus, TaskItem, AddTaskHighlight, TASK_FIELDS};
 
 
 
 pub fn navigate_down_projects(project_list_state: &mut TableState, project_amount: usize) {
     if let Some(selected) = project_list_state.selected() {
-        if selected >= project_amount - 1 {
+        if selected + 1 >= project_amount {
             project_list_state.select(Some(0));
         } else {
             project_list_state.select(Some(selected + 1));
@@ -25,46 +19,14 @@ pub fn navigate_up_projects(project_list_state: &mut TableState, project_amount:
         if selected > 0 {
             project_list_state.select(Some(selected - 1));
         } else {
-            project_list_state.select(Some(project_amount - 1));
+            project_list_state.select(Some(project_amount.saturating_sub(1)));
         }
     }
 }
 
-pub fn navigate_down_tasks(
-    database: Arc<Mutex<Database>>,
-    task_list_state: &mut TableState,
-    project_list_state: &TableState,
-) {
+pub fn navigate_down_tasks(task_list_state: &mut TableState, amount_tasks: usize) {
     if let Some(selected) = task_list_state.selected() {
-        let selected_project = database
-            .lock()
-            .unwrap()
-            .projects
-            .clone()
-            .get(
-                project_list_state
-                    .selected()
-                    .expect("there is always a selected project"),
-            )
-            .expect("exists")
-            .clone();
-
-        let task_items: Vec<_> = database
-            .lock()
-            .unwrap()
-            .tasks
-            .clone()
-            .iter()
-            .filter(|task| task.project_id == selected_project.id)
-            .map(|task| {
-                ListItem::new(Spans::from(vec![Span::styled(
-                    task.content.clone(),
-                    Style::default(),
-                )]))
-            })
-            .collect();
-        let amount_tasks = task_items.len();
-        if selected >= amount_tasks - 1 {
+        if selected + 1 >= amount_tasks {
             task_list_state.select(Some(0));
         } else {
             task_list_state.select(Some(selected + 1));
@@ -72,73 +34,58 @@ pub fn navigate_down_tasks(
     }
 }
 
-pub fn navigate_up_tasks(
-    database: Arc<Mutex<Database>>,
-    task_list_state: &mut TableState,
-    project_list_state: &TableState,
-) {
+pub fn navigate_up_tasks(task_list_state: &mut TableState, amount_tasks: usize) {
     if let Some(selected) = task_list_state.selected() {
-        if let Some(selected_p) = project_list_state.selected() {
-            let selected_project = database
-                .lock()
-                .unwrap()
-                .projects
-                .clone()
-                .get(selected_p)
-                .unwrap()
-                .clone();
-
-            let task_items: Vec<ListItem> = database
-                .lock()
-                .unwrap()
-                .tasks
-                .clone()
-                .iter()
-                .filter(|task| task.project_id == selected_project.id)
-                .map(|task| {
-                    ListItem::new(Spans::from(vec![Span::styled(
-                        task.content.clone(),
-                        Style::default(),
-                    )]))
-                })
-                .collect();
-            let amount_tasks = task_items.len();
-            if selected > 0 {
-                task_list_state.select(Some(selected - 1));
-            } else {
-                task_list_state.select(Some(amount_tasks - 1));
-            }
+        if selected > 0 {
+            task_list_state.select(Some(selected - 1));
+        } else {
+            task_list_state.select(Some(amount_tasks.saturating_sub(1)));
         }
     }
 }
 
-pub fn change_active_add_task_input_field(task_status: &mut TaskStatus, config_color: Color) {
-    match task_status.active_task_item {
-        TaskItem::Name => {
-            task_status.active_task_item = TaskItem::Desc;
-            task_status.add_task_highlight = AddTaskHighlight::default();
-            task_status.add_task_highlight.desc = config_color;
-        }
-        TaskItem::Desc => {
-            task_status.active_task_item = TaskItem::Label;
-            task_status.add_task_highlight = AddTaskHighlight::default();
-            task_status.add_task_highlight.label = config_color;
-        }
-        TaskItem::Label => {
-            task_status.active_task_item = TaskItem::Due;
-            task_status.add_task_highlight = AddTaskHighlight::default();
-            task_status.add_task_highlight.due = config_color;
-        }
-        TaskItem::Due => {
-            task_status.active_task_item = TaskItem::Prio;
-            task_status.add_task_highlight = AddTaskHighlight::default();
-            task_status.add_task_highlight.prio = config_color;
-        }
-        TaskItem::Prio => {
-            task_status.active_task_item = TaskItem::Name;
-            task_status.add_task_highlight = AddTaskHighlight::default();
-            task_status.add_task_highlight.name = config_color;
-        }
-        _ => {}
+/// The first row to draw so that `selected` is in view, moving as little
+/// as possible from `offset`. Rows are `heights` tall and `max_height` fit.
+pub fn scroll_offset(heights: &[u16], selected: usize, offset: usize, max_height: u16) -> usize {
+    if heights.is_empty() {
+        return 0;
+    }
+    let selected = selected.min(heights.len() - 1);
+    let mut offset = offset.min(selected);
+    let height = |offset: usize| heights[offset..=selected].iter().map(|&h| h as u32).sum::<u32>();
+    while offset < selected && height(offset) > max_height as u32 {
+        offset += 1;
+    }
+    offset
+}
+
+/// Moves the add task form focus `step` fields along `TASK_FIELDS`, wrapping
+/// around at either end.
+pub fn change_active_add_task_input_field(
+    task_status: &mut TaskStatus,
+    step: isize,
+    config_color: Color,
+) {
+    let current = TASK_FIELDS.iter().position(|item| *item == task_status.active_task_item);
+    if let Some(current) = current {
+        let next = (current as isize + step).rem_euclid(TASK_FIELDS.len() as isize);
+        focus_add_task_input_field(task_status, TASK_FIELDS[next as usize], config_color);
+    }
+}
+
+/// Focuses `item` of the add task form, as clicking on it does.
+pub fn focus_add_task_input_field(task_status: &mut TaskStatus, item: TaskItem, config_color: Color) {
+    task_status.active_task_item = item;
+    task_status.add_task_highlight = AddTaskHighlight::default();
+    let highlight = &mut task_status.add_task_highlight;
+    match item {
+        TaskItem::Name => highlight.name = config_color,
+        TaskItem::Desc => highlight.desc = config_color,
+        TaskItem::Label => highlight.label = config_color,
+        TaskItem::Due => highlight.due = config_color,
+        TaskItem::Deadline => highlight.deadline = config_color,
+        TaskItem::Duration => highlight.duration = config_color,
+        TaskItem::Prio => highlight.prio = config_color,
+        TaskItem::Empty => {}
     }
 }
@@ -0,0 +1,233 @@
+use std::{
+    fmt::Write as _,
+    io::{self, Write},
+    str::FromStr,
+};
+
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+    Tsv,
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(OutputFormat::Table),
+            "json" => Ok(OutputFormat::Json),
+            "tsv" => Ok(OutputFormat::Tsv),
+            _ => Err(format!("unknown format '{}', expected table, json or tsv", s)),
+        }
+    }
+}
+
+/// Looks up a possibly nested field such as `due.date`.
+pub fn lookup<'a>(value: &'a Value, column: &str) -> &'a Value {
+    column
+        .split('.')
+        .try_fold(value, |value, key| value.get(key))
+        .unwrap_or(&Value::Null)
+}
+
+pub fn cell_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Array(items) => items.iter().map(cell_text).collect::<Vec<_>>().join(","),
+        other => other.to_string(),
+    }
+}
+
+fn escape_tsv(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+pub fn render_rows<T: Serialize>(
+    items: &[T],
+    columns: &Option<Vec<String>>,
+    format: OutputFormat,
+) -> Result<String, serde_json::Error> {
+    let values = items
+        .iter()
+        .map(serde_json::to_value)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let columns = match (columns, format) {
+        (None, OutputFormat::Json) => return serde_json::to_string_pretty(&values),
+        (None, _) => vec![],
+        (Some(columns), _) => columns.clone(),
+    };
+
+    let mut out = String::new();
+    match format {
+        OutputFormat::Json => {
+            let selected: Vec<Value> = values
+                .iter()
+                .map(|value| {
+                    let object: Map<String, Value> = columns
+                        .iter()
+                        .map(|column| (column.clone(), lookup(value, column).clone()))
+                        .collect();
+                    Value::Object(object)
+                })
+                .collect();
+            out = serde_json::to_string_pretty(&selected)?;
+        }
+        OutputFormat::Tsv => {
+            let _ = writeln!(out, "{}", columns.join("\t"));
+            for value in &values {
+                let cells: Vec<String> = columns
+                    .iter()
+                    .map(|column| escape_tsv(&cell_text(lookup(value, column))))
+                    .collect();
+                let _ = writeln!(out, "{}", cells.join("\t"));
+            }
+        }
+        OutputFormat::Table => {
+            let rows: Vec<Vec<String>> = values
+                .iter()
+                .map(|value| {
+                    columns
+                        .iter()
+                        .map(|column| cell_text(lookup(value, column)).replace(['\n', '\t'], " "))
+                        .collect()
+                })
+                .collect();
+            let widths: Vec<usize> = columns
+                .iter()
+                .enumerate()
+                .map(|(i, column)| {
+                    rows.iter()
+                        .map(|row| row[i].chars().count())
+                        .chain(Some(column.chars().count()))
+                        .max()
+                        .unwrap_or(0)
+                })
+                .collect();
+
+            let header: Vec<String> = columns.iter().map(|c| c.to_uppercase()).collect();
+            for row in Some(&header).into_iter().chain(rows.iter()) {
+                let line: Vec<String> = row
+                    .iter()
+                    .zip(&widths)
+                    .map(|(cell, width)| {
+                        let padding = width - cell.chars().count();
+                        format!("{}{}", cell, " ".repeat(padding))
+                    })
+                    .collect();
+                let _ = writeln!(out, "{}", line.join("  ").trim_end());
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+pub fn print_rows<T: Serialize>(
+    items: &[T],
+    columns: &Option<Vec<String>>,
+    format: OutputFormat,
+) -> Result<(), anyhow::Error> {
+    let mut out = render_rows(items, columns, format)?;
+    if !out.ends_with('\n') {
+        out.push('\n');
+    }
+    io::stdout().write_all(out.as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn rows() -> Vec<Value> {
+        vec![
+            json!({
+                "id": "1",
+                "content": "Buy milk",
+                "due": { "date": "2026-10-20" },
+                "labels": ["shop", "home"],
+                "priority": 4,
+            }),
+            json!({
+                "id": "22",
+                "content": "Two\nlines\tand a tab",
+                "due": null,
+                "labels": [],
+                "priority": 1,
+            }),
+        ]
+    }
+
+    fn columns(columns: &[&str]) -> Option<Vec<String>> {
+        Some(columns.iter().map(|column| column.to_string()).collect())
+    }
+
+    #[test]
+    fn looks_up_nested_fields() {
+        let row = &rows()[0];
+        assert_eq!(lookup(row, "due.date"), &json!("2026-10-20"));
+        assert_eq!(lookup(row, "due.time"), &Value::Null);
+        assert_eq!(lookup(&rows()[1], "due.date"), &Value::Null);
+        assert_eq!(cell_text(lookup(row, "labels")), "shop,home");
+        assert_eq!(cell_text(lookup(row, "priority")), "4");
+    }
+
+    #[test]
+    fn renders_padded_tables() {
+        let out = render_rows(&rows(), &columns(&["id", "due.date", "labels"]), OutputFormat::Table);
+        let expected = "\
+ID  DUE.DATE    LABELS
+1   2026-10-20  shop,home
+22
+";
+        assert_eq!(out.unwrap(), expected);
+
+        let out = render_rows(&rows(), &columns(&["content", "id"]), OutputFormat::Table);
+        let expected = "\
+CONTENT              ID
+Buy milk             1
+Two lines and a tab  22
+";
+        assert_eq!(out.unwrap(), expected);
+    }
+
+    #[test]
+    fn renders_escaped_tsv() {
+        let out = render_rows(&rows(), &columns(&["id", "content", "due.date"]), OutputFormat::Tsv);
+        let expected = "\
+id\tcontent\tdue.date
+1\tBuy milk\t2026-10-20
+22\tTwo\\nlines\\tand a tab\t
+";
+        assert_eq!(out.unwrap(), expected);
+    }
+
+    #[test]
+    fn renders_json() {
+        let out = render_rows(&rows(), &columns(&["id", "due.date"]), OutputFormat::Json).unwrap();
+        let selected: Value = serde_json::from_str(&out).unwrap();
+        assert_eq!(
+            selected,
+            json!([
+                { "id": "1", "due.date": "2026-10-20" },
+                { "id": "22", "due.date": null },
+            ])
+        );
+
+        let out = render_rows(&rows(), &None, OutputFormat::Json).unwrap();
+        assert_eq!(serde_json::from_str::<Value>(&out).unwrap(), json!(rows()));
+    }
+}
@@ -0,0 +1,161 @@
+use std::cmp::Reverse;
+
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Clear, List, ListItem, ListState, Paragraph},
+    Frame,
+};
+
+use crate::{handler::create_advanced_block, popup::centered_rect};
+
+/// A popup list of choices that narrows down as the user types.
+pub struct Picker<T> {
+    pub title: String,
+    pub query: String,
+    items: Vec<(String, T)>,
+    selected: usize,
+    /// Offered as `<hint> "<query>"` when nothing matches the query.
+    custom_hint: Option<&'static str>,
+}
+
+/// Scores `text` against `query` when all characters of `query` appear in
+/// order, preferring consecutive matches and matches at word starts.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<usize> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = position + text[position..].iter().position(|&t| t == c)?;
+        score += 1;
+        if previous.is_some_and(|previous| previous + 1 == found) {
+            score += 4;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 2;
+        }
+        previous = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
+
+impl<T> Picker<T> {
+    pub fn new(title: impl Into<String>, items: Vec<(String, T)>) -> Picker<T> {
+        Picker {
+            title: title.into(),
+            query: String::new(),
+            items,
+            selected: 0,
+            custom_hint: None,
+        }
+    }
+
+    /// Lets the query itself be chosen when no item matches it.
+    pub fn with_custom(mut self, hint: &'static str) -> Picker<T> {
+        self.custom_hint = Some(hint);
+        self
+    }
+
+    fn offers_query(&self) -> bool {
+        self.custom_hint.is_some() && !self.query.trim().is_empty() && self.filtered().is_empty()
+    }
+
+    /// Items matching the query, best matches first.
+    pub fn filtered(&self) -> Vec<&(String, T)> {
+        let mut matches: Vec<(usize, usize, &(String, T))> = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(i, item)| fuzzy_score(&self.query, &item.0).map(|score| (score, i, item)))
+            .collect();
+        // ties go to the shorter label, then to the original order
+        matches.sort_by_key(|&(score, i, (label, _))| (Reverse(score), label.len(), i));
+        matches.into_iter().map(|(_, _, item)| item).collect()
+    }
+
+    pub fn push(&mut self, c: char) {
+        self.query.push(c);
+        self.selected = 0;
+    }
+
+    pub fn pop(&mut self) {
+        self.query.pop();
+        self.selected = 0;
+    }
+
+    pub fn next(&mut self) {
+        let count = self.filtered().len();
+        if count > 0 {
+            self.selected = (self.selected + 1) % count;
+        }
+    }
+
+    pub fn previous(&mut self) {
+        let count = self.filtered().len();
+        if count > 0 {
+            self.selected = (self.selected + count - 1) % count;
+        }
+    }
+
+    pub fn chosen(&self) -> Option<&T> {
+        self.filtered().get(self.selected).map(|item| &item.1)
+    }
+}
+
+impl Picker<String> {
+    /// The chosen item, or the query when it is offered instead.
+    pub fn chosen_or_query(&self) -> Option<String> {
+        if self.offers_query() {
+            return Some(self.query.trim().to_owned());
+        }
+        self.chosen().cloned()
+    }
+}
+
+pub fn render_picker<B: Backend, T>(
+    rect: &mut Frame<B>,
+    picker: &Picker<T>,
+    color: Color,
+    area: Rect,
+) {
+    let popup_area = centered_rect(50, 16, area);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)])
+        .split(popup_area);
+
+    let query = Paragraph::new(picker.query.clone()).block(create_advanced_block(
+        picker.title.clone(),
+        color,
+        Alignment::Left,
+    ));
+
+    let mut items: Vec<ListItem> = picker
+        .filtered()
+        .into_iter()
+        .map(|(label, _)| ListItem::new(Spans::from(Span::raw(label.clone()))))
+        .collect();
+    if let (true, Some(hint)) = (picker.offers_query(), picker.custom_hint) {
+        let label = format!("{} \"{}\"", hint, picker.query.trim());
+        items.push(ListItem::new(Spans::from(Span::raw(label))));
+    }
+    let empty = items.is_empty();
+    let list = List::new(items)
+        .block(create_advanced_block("", color, Alignment::Left))
+        .highlight_style(Style::default().fg(color).add_modifier(Modifier::BOLD))
+        .highlight_symbol(">");
+    let mut state = ListState::default();
+    state.select((!empty).then_some(picker.selected));
+
+    rect.render_widget(Clear, popup_area);
+    rect.render_widget(query, chunks[0]);
+    rect.render_stateful_widget(list, chunks[1], &mut state);
+    rect.set_cursor(
+        chunks[0].x + 1 + picker.query.chars().count() as u16,
+        chunks[0].y + 1,
+    );
+}
@@ -0,0 +1,60 @@
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::{
+    app::Confirm,
+    handler::create_advanced_block,
+};
+
+/// A rectangle of at most `width` x `height` in the middle of `area`.
+pub fn centered_rect(width: u16, height: u16, area: Rect) -> Rect {
+    let width = width.min(area.width);
+    let height = height.min(area.height);
+    Rect::new(
+        area.x + (area.width - width) / 2,
+        area.y + (area.height - height) / 2,
+        width,
+        height,
+    )
+}
+
+pub fn render_confirm<B: Backend>(
+    rect: &mut Frame<B>,
+    confirm: &Confirm,
+    color: Color,
+    area: Rect,
+) {
+    let question = match confirm {
+        Confirm::DeleteProject {
+            name, task_count, ..
+        } => vec![
+            Spans::from(format!("Delete project \"{}\"?", name)),
+            Spans::from(format!(
+                "Its {} tasks are deleted as well, this cannot be undone.",
+                task_count
+            )),
+        ],
+    };
+    let mut lines = question;
+    lines.push(Spans::from(""));
+    lines.push(Spans::from(vec![
+        Span::styled("y", Style::default().fg(color).add_modifier(Modifier::BOLD)),
+        Span::raw(" delete  "),
+        Span::styled("n", Style::default().fg(color).add_modifier(Modifier::BOLD)),
+        Span::raw(" cancel"),
+    ]));
+
+    let popup_area = centered_rect(50, lines.len() as u16 + 4, area);
+    let paragraph = Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true })
+        .block(create_advanced_block("Confirm", color, Alignment::Center));
+    rect.render_widget(Clear, popup_area);
+    rect.render_widget(paragraph, popup_area);
+}
@@ -0,0 +1,76 @@
+use tui::{
+    style::{Color, Style},
+    text::Span,
+};
+
+/// p4, the API's 1.
+pub const DEFAULT_PRIORITY: usize = 1;
+
+/// Flips 1 to 4 end for end. The API counts priorities the other way
+/// around from p1 to p4: p1, the most urgent, is 4 and p4 is 1. Flipping
+/// twice gives the number back, so this maps both ways.
+fn invert(priority: usize) -> usize {
+    5 - priority.clamp(1, 4)
+}
+
+/// The p1 to p4 number shown for the API `priority`.
+pub fn shown_priority(priority: usize) -> usize {
+    invert(priority)
+}
+
+/// The API priority for p1 to p4.
+pub fn api_priority(shown: usize) -> usize {
+    invert(shown)
+}
+
+pub fn priority_label(priority: usize) -> &'static str {
+    match shown_priority(priority) {
+        1 => "p1 Urgent",
+        2 => "p2 High",
+        3 => "p3 Medium",
+        _ => "p4 Normal",
+    }
+}
+
+/// The flag color Todoist uses, None for p4 which has no flag.
+pub fn priority_color(priority: usize) -> Option<Color> {
+    match shown_priority(priority) {
+        1 => Some(Color::Rgb(209, 69, 59)),
+        2 => Some(Color::Rgb(235, 137, 9)),
+        3 => Some(Color::Rgb(36, 111, 224)),
+        _ => None,
+    }
+}
+
+/// A colored flag, or a blank of the same width for p4.
+pub fn priority_flag(priority: usize) -> Span<'static> {
+    match priority_color(priority) {
+        Some(color) => Span::styled("⚑", Style::default().fg(color)),
+        None => Span::raw(" "),
+    }
+}
+
+/// Choices of the priority picker, p1 first, as API priorities.
+pub fn priority_choices() -> Vec<(String, usize)> {
+    (1..=4)
+        .map(|shown| {
+            let priority = api_priority(shown);
+            (priority_label(priority).to_owned(), priority)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_mapping_is_its_own_inverse() {
+        assert_eq!(shown_priority(4), 1);
+        assert_eq!(api_priority(1), 4);
+        for priority in 1..=4 {
+            assert_eq!(api_priority(shown_priority(priority)), priority);
+            assert_eq!(shown_priority(priority), api_priority(priority));
+        }
+    }
+}
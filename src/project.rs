@@ -1,21 +1,18 @@
-use std::sync::{Arc, Mutex};
-
 use tui::{
     backend::Backend,
     layout::{Alignment, Constraint, Rect},
     style::{Color, Modifier, Style},
-    widgets::{Row, Table, TableState},
+    text::Span,
+    widgets::{Cell, Row, Table, TableState},
     Frame,
 };
 
 use crate::{
-    api::PostProject,
-    handler::{create_advanced_block, create_basic_block, create_basic_paragraph},
-    menu::Database,
+    api::{PostProject, Project},
+    handler::{create_advanced_block, create_basic_block, create_basic_paragraph, input_tail},
+    database::Database,
 };
 
-use super::task::get_task_from_project_id;
-
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum ProjectItem {
     Empty,
@@ -26,6 +23,10 @@ pub struct ProjectStatus {
     pub project_table_state: TableState,
     pub active_project_item: ProjectItem,
     pub project_item: PostProject,
+    /// The project being renamed, when the form is not adding one.
+    pub editing_id: Option<String>,
+    /// The first project row drawn.
+    pub offset: usize,
 }
 
 impl Default for ProjectStatus {
@@ -34,26 +35,93 @@ impl Default for ProjectStatus {
             project_table_state: Default::default(),
             active_project_item: ProjectItem::Empty,
             project_item: Default::default(),
+            editing_id: None,
+            offset: 0,
+        }
+    }
+}
+
+impl ProjectStatus {
+    fn form_title(&self) -> &'static str {
+        match (&self.editing_id, &self.project_item.parent_id) {
+            (Some(_), _) => "Rename Project",
+            (None, Some(_)) => "Add Child Project",
+            (None, None) => "Add Project",
+        }
+    }
+}
+
+/// The colors Todoist accepts for projects, by name.
+pub const PROJECT_COLORS: [(&str, Color); 20] = [
+    ("berry_red", Color::Rgb(0xb8, 0x25, 0x6f)),
+    ("red", Color::Rgb(0xdb, 0x40, 0x35)),
+    ("orange", Color::Rgb(0xff, 0x99, 0x33)),
+    ("yellow", Color::Rgb(0xfa, 0xd0, 0x00)),
+    ("olive_green", Color::Rgb(0xaf, 0xb8, 0x3b)),
+    ("lime_green", Color::Rgb(0x7e, 0xcc, 0x49)),
+    ("green", Color::Rgb(0x29, 0x94, 0x38)),
+    ("mint_green", Color::Rgb(0x6a, 0xcc, 0xbc)),
+    ("teal", Color::Rgb(0x15, 0x8f, 0xad)),
+    ("sky_blue", Color::Rgb(0x14, 0xaa, 0xf5)),
+    ("light_blue", Color::Rgb(0x96, 0xc3, 0xeb)),
+    ("blue", Color::Rgb(0x40, 0x73, 0xff)),
+    ("grape", Color::Rgb(0x88, 0x4d, 0xff)),
+    ("violet", Color::Rgb(0xaf, 0x38, 0xeb)),
+    ("lavender", Color::Rgb(0xeb, 0x96, 0xeb)),
+    ("magenta", Color::Rgb(0xe0, 0x51, 0x94)),
+    ("salmon", Color::Rgb(0xff, 0x8d, 0x85)),
+    ("charcoal", Color::Rgb(0x80, 0x80, 0x80)),
+    ("grey", Color::Rgb(0xb8, 0xb8, 0xb8)),
+    ("taupe", Color::Rgb(0xcc, 0xac, 0x93)),
+];
+
+pub fn project_color(name: &str) -> Color {
+    PROJECT_COLORS
+        .iter()
+        .find(|(color, _)| *color == name)
+        .map_or(Color::Gray, |&(_, color)| color)
+}
+
+/// How many ancestors `project` has, which sets its indent in the list.
+fn project_depth(database: &Database, project: &Project) -> usize {
+    let mut depth = 0;
+    let mut parent = project.parent_id.as_deref();
+    while let Some(ancestor) = parent.and_then(|id| database.project(id)) {
+        depth += 1;
+        if depth > database.projects().len() {
+            break;
         }
+        parent = ancestor.parent_id.as_deref();
     }
+    depth
 }
 
+/// The project table, starting at project `offset` so that the app decides
+/// how far it is scrolled.
 pub fn get_project_table_list(
-    database: &Arc<Mutex<Database>>,
+    database: &Database,
+    offset: usize,
     selection_color: Color,
     highlight_color: Color,
-) -> Table {
+) -> Table<'_> {
     let projects_block = create_advanced_block("Projects", selection_color, Alignment::Center);
 
-    let tasks = database.lock().unwrap().tasks.clone();
-    let projects = database.lock().unwrap().projects.clone();
-
-    let project_items: Vec<_> = projects
+    let project_items: Vec<_> = database
+        .projects()
         .iter()
+        .skip(offset)
         .map(|project| {
+            let swatch = Span::styled("●", Style::default().fg(project_color(&project.color)));
+            // favorites are pinned at the top, away from their parents
+            let name = if project.is_favorite {
+                format!("★ {}", project.name)
+            } else {
+                format!("{}{}", "  ".repeat(project_depth(database, project)), project.name)
+            };
             Row::new(vec![
-                project.name.clone(),
-                get_task_from_project_id(project.id.clone(), &mut tasks.clone()),
+                Cell::from(swatch),
+                Cell::from(name),
+                Cell::from(database.project_task_count(&project.id).to_string()),
             ])
         })
         .collect();
@@ -67,27 +135,23 @@ pub fn get_project_table_list(
         )
         .column_spacing(1)
         .highlight_symbol(">")
-        .widths(&[Constraint::Percentage(89), Constraint::Percentage(5)]);
+        .widths(&[
+            Constraint::Length(1),
+            Constraint::Percentage(84),
+            Constraint::Percentage(5),
+        ]);
 
     project_table_list
 }
 
 pub fn render_project_item<B: Backend>(
     rect: &mut Frame<B>,
-    project_chunks: Vec<Rect>,
-    project_item: &PostProject,
+    area: Rect,
+    project_status: &ProjectStatus,
     config_color: Color,
 ) {
-    let name = create_basic_block("Add Project", config_color);
-
-    let task_name = project_item.name.clone();
-    let name_len = project_item.name.len();
-    let mut current_name = task_name.clone();
-    if name_len >= 25 {
-        let (_, second) = task_name.split_at(((name_len / 25) * 25) - 3);
-        current_name = second.to_string();
-    }
-    let name = create_basic_paragraph(current_name, name);
-
-    rect.render_widget(name, project_chunks[0]);
+    let block = create_basic_block(project_status.form_title(), config_color);
+    let name = input_tail(&project_status.project_item.name, area.width.saturating_sub(2));
+    rect.set_cursor(area.x + 1 + name.chars().count() as u16, area.y + 1);
+    rect.render_widget(create_basic_paragraph(name, block), area);
 }
@@ -0,0 +1,166 @@
+use std::{cmp::Reverse, collections::HashMap};
+
+use crate::{
+    api::{Project, Task},
+    database::Database,
+};
+
+/// How the tasks of a project are ordered in the task table.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum SortMode {
+    #[default]
+    Manual,
+    DueDate,
+    Priority,
+    Alphabetical,
+    DateAdded,
+}
+
+impl SortMode {
+    pub fn next(self) -> SortMode {
+        match self {
+            SortMode::Manual => SortMode::DueDate,
+            SortMode::DueDate => SortMode::Priority,
+            SortMode::Priority => SortMode::Alphabetical,
+            SortMode::Alphabetical => SortMode::DateAdded,
+            SortMode::DateAdded => SortMode::Manual,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortMode::Manual => "manual",
+            SortMode::DueDate => "due date",
+            SortMode::Priority => "priority",
+            SortMode::Alphabetical => "alphabetical",
+            SortMode::DateAdded => "date added",
+        }
+    }
+}
+
+pub fn sort_tasks(tasks: &mut [Task]) {
+    tasks.sort_by(|a, b| {
+        (&a.project_id, &a.section_id, a.order, &a.id).cmp(&(
+            &b.project_id,
+            &b.section_id,
+            b.order,
+            &b.id,
+        ))
+    });
+}
+
+/// Orders projects as the sidebar shows them: the inbox, then favorites,
+/// then every other project followed by its children.
+pub fn sort_projects(projects: &mut [Project]) {
+    let by_id: HashMap<&str, &Project> = projects.iter().map(|p| (p.id.as_str(), p)).collect();
+    let keys: HashMap<String, (u8, Vec<(usize, String)>)> = projects
+        .iter()
+        .map(|project| {
+            let group = match (project.is_inbox_project, project.is_favorite) {
+                (true, _) => 0,
+                (false, true) => 1,
+                (false, false) => 2,
+            };
+            let mut path = vec![(project.order, project.id.clone())];
+            if group == 2 {
+                let mut parent = project.parent_id.as_deref();
+                while let Some(ancestor) = parent.and_then(|id| by_id.get(id)) {
+                    if path.len() > by_id.len() {
+                        break;
+                    }
+                    path.push((ancestor.order, ancestor.id.clone()));
+                    parent = ancestor.parent_id.as_deref();
+                }
+                path.reverse();
+            }
+            (project.id.clone(), (group, path))
+        })
+        .collect();
+    projects.sort_by_cached_key(|project| keys[&project.id].clone());
+}
+
+fn due_key(task: &Task) -> Option<&str> {
+    let due = task.due.as_ref()?;
+    due.datetime.as_deref().or(due.date.as_deref())
+}
+
+/// `(order, id)` of `task` and its ancestors from the outermost one down,
+/// as subtasks are ordered among their siblings only.
+fn order_path<'a>(database: &'a Database, task: &'a Task) -> Vec<(usize, &'a str)> {
+    let mut path = vec![(task.order, task.id.as_str())];
+    let mut parent = task.parent_id.as_deref();
+    while let Some(ancestor) = parent.and_then(|id| database.task(id)) {
+        if path.len() > database.tasks().len() {
+            break;
+        }
+        path.push((ancestor.order, ancestor.id.as_str()));
+        parent = ancestor.parent_id.as_deref();
+    }
+    path.reverse();
+    path
+}
+
+/// Tasks of a project as shown in the task table. Manual order follows the
+/// sections and then each task's `order`, with subtasks right below their
+/// parent.
+pub fn sorted_project_tasks<'a>(
+    database: &'a Database,
+    project_id: &str,
+    mode: SortMode,
+) -> Vec<&'a Task> {
+    let mut tasks: Vec<&Task> = database.project_tasks(project_id).collect();
+    let section_order = |task: &Task| {
+        task.section_id
+            .as_deref()
+            .and_then(|id| database.section(id))
+            .map(|section| section.order)
+    };
+    match mode {
+        SortMode::Manual => {
+            tasks.sort_by_cached_key(|task| (section_order(task), order_path(database, task)))
+        }
+        SortMode::DueDate => tasks.sort_by_key(|task| (due_key(task).is_none(), due_key(task))),
+        SortMode::Priority => tasks.sort_by_key(|task| (Reverse(task.priority), task.order)),
+        SortMode::Alphabetical => tasks.sort_by_cached_key(|task| task.content.to_lowercase()),
+        SortMode::DateAdded => tasks.sort_by(|a, b| a.created_at.cmp(&b.created_at)),
+    }
+    tasks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::TaskContent;
+
+    fn task(id: &str, order: usize, parent_id: Option<&str>) -> Task {
+        let mut task = Task::temp(TaskContent::default(), "inbox".to_owned());
+        task.id = id.to_owned();
+        task.order = order;
+        task.parent_id = parent_id.map(str::to_owned);
+        task
+    }
+
+    #[test]
+    fn manual_order_keeps_subtasks_below_their_parent() {
+        // subtask orders count among their siblings, so they clash with the
+        // orders of top-level tasks
+        let tasks = vec![
+            task("b", 2, None),
+            task("b1", 1, Some("b")),
+            task("a2", 2, Some("a")),
+            task("a", 1, None),
+            task("a1", 1, Some("a")),
+            task("a11", 1, Some("a1")),
+            task("c", 3, None),
+        ];
+        let mut inbox = Project::name("Inbox");
+        inbox.id = "inbox".to_owned();
+        let database = Database::new(vec![inbox], vec![], tasks, vec![]);
+
+        let ids: Vec<&str> = sorted_project_tasks(&database, "inbox", SortMode::Manual)
+            .iter()
+            .map(|task| task.id.as_str())
+            .collect();
+        assert_eq!(ids, ["a", "a1", "a11", "a2", "b", "b1", "c"]);
+    }
+}
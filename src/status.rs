@@ -0,0 +1,112 @@
+use tui::{
+    backend::Backend,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::Paragraph,
+    Frame,
+};
+
+use chrono::{DateTime, Utc};
+
+use crate::{app::App, date::DateConfig};
+
+/// How the requests to Todoist are going.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyncState {
+    Idle,
+    Syncing,
+    Offline,
+    Error,
+}
+
+impl SyncState {
+    fn label(self) -> &'static str {
+        match self {
+            SyncState::Idle => "Synced",
+            SyncState::Syncing => "Syncing",
+            SyncState::Offline => "Offline",
+            SyncState::Error => "Error",
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            SyncState::Idle => Color::Green,
+            SyncState::Syncing => Color::Yellow,
+            SyncState::Offline => Color::DarkGray,
+            SyncState::Error => Color::Red,
+        }
+    }
+}
+
+/// The time of the last sync in the configured zone, like due times.
+fn last_sync_detail(last_sync: DateTime<Utc>, dates: &DateConfig) -> String {
+    let local = dates.zone.local_time(last_sync);
+    format!("last sync {}", local.format(&dates.time_format))
+}
+
+/// One line with the sync state, the last successful sync, pending
+/// requests and the latest message on the left, and the user on the right.
+pub fn render_status_bar<B: Backend>(rect: &mut Frame<B>, app: &App, color: Color, area: Rect) {
+    let state = app.sync_state();
+    let mut spans = vec![Span::styled(
+        format!(" ● {}", state.label()),
+        Style::default()
+            .fg(state.color())
+            .add_modifier(Modifier::BOLD),
+    )];
+    let mut details = vec![];
+    if let Some(last_sync) = app.last_sync {
+        details.push(last_sync_detail(last_sync, &app.dates));
+    }
+    if app.pending > 0 {
+        details.push(format!("{} pending", app.pending));
+    }
+    if let Some(toast) = &app.toast {
+        details.push(toast.message.clone());
+    } else if let Some(err) = &app.error {
+        details.push(err.message.clone());
+    }
+    for detail in details {
+        spans.push(Span::raw(" · "));
+        spans.push(Span::raw(detail));
+    }
+
+    let name = app.user.as_ref().map_or(String::new(), |user| {
+        if user.full_name.is_empty() {
+            format!("{} ", user.email)
+        } else {
+            format!("{} ", user.full_name)
+        }
+    });
+    let parts = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Min(0),
+            Constraint::Length(name.chars().count() as u16),
+        ])
+        .split(area);
+    rect.render_widget(Paragraph::new(Spans::from(spans)), parts[0]);
+    let user =
+        Paragraph::new(Span::styled(name, Style::default().fg(color))).alignment(Alignment::Right);
+    rect.render_widget(user, parts[1]);
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+    use crate::date::Zone;
+
+    #[test]
+    fn last_sync_is_shown_in_the_configured_zone() {
+        let dates = DateConfig {
+            zone: Zone::Named(chrono_tz::Asia::Tokyo),
+            ..Default::default()
+        };
+        let last_sync = Utc.with_ymd_and_hms(2024, 3, 1, 22, 5, 0).unwrap();
+        assert_eq!(last_sync_detail(last_sync, &dates), "last sync 07:05");
+    }
+}
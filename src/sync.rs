@@ -0,0 +1,213 @@
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use reqwest::header::AUTHORIZATION;
+use serde_derive::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    api::{check_status, Due, Task},
+    uuid::stable_uuid,
+};
+
+const SYNC_URL: &str = "https://api.todoist.com/sync/v9/sync";
+const ARCHIVED_PROJECTS_URL: &str = "https://api.todoist.com/sync/v9/projects/get_archived";
+
+/// Where a task lives: a project, optionally inside one of its sections or
+/// below another task.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Placement {
+    pub project_id: String,
+    pub section_id: Option<String>,
+    pub parent_id: Option<String>,
+}
+
+impl Placement {
+    pub fn of(task: &Task) -> Placement {
+        Placement {
+            project_id: task.project_id.clone(),
+            section_id: task.section_id.clone(),
+            parent_id: task.parent_id.clone(),
+        }
+    }
+}
+
+/// A command of the Todoist Sync API.
+#[derive(Serialize, Debug, Clone)]
+pub struct SyncCommand {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub uuid: String,
+    pub args: Value,
+}
+
+/// An archived project, which the REST API does not list.
+#[derive(Deserialize, Debug, Clone)]
+pub struct ArchivedProject {
+    pub id: String,
+    pub name: String,
+}
+
+/// The account the token belongs to.
+#[derive(Deserialize, Debug, Clone)]
+pub struct User {
+    pub full_name: String,
+    pub email: String,
+}
+
+#[derive(Deserialize, Debug)]
+struct UserResponse {
+    user: User,
+}
+
+#[derive(Deserialize, Debug)]
+struct SyncResponse {
+    #[serde(default)]
+    sync_status: HashMap<String, Value>,
+}
+
+fn command_uuid(kind: &str) -> String {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    stable_uuid(&format!("{}-{}-{}", kind, nanos, count))
+}
+
+impl SyncCommand {
+    pub fn new(kind: &str, args: Value) -> SyncCommand {
+        SyncCommand {
+            kind: kind.to_owned(),
+            uuid: command_uuid(kind),
+            args,
+        }
+    }
+
+    /// Moves a task, with its subtasks, below the parent, into the section
+    /// or else into the project of `to`.
+    pub fn item_move(task_id: &str, to: &Placement) -> SyncCommand {
+        let args = match (&to.parent_id, &to.section_id) {
+            (Some(parent_id), _) => json!({ "id": task_id, "parent_id": parent_id }),
+            (None, Some(section_id)) => json!({ "id": task_id, "section_id": section_id }),
+            (None, None) => json!({ "id": task_id, "project_id": to.project_id }),
+        };
+        SyncCommand::new("item_move", args)
+    }
+
+    pub fn item_delete(task_id: &str) -> SyncCommand {
+        SyncCommand::new("item_delete", json!({ "id": task_id }))
+    }
+
+    /// Completes a task, or moves a recurring task to its next date.
+    pub fn item_close(task_id: &str) -> SyncCommand {
+        SyncCommand::new("item_close", json!({ "id": task_id }))
+    }
+
+    pub fn item_uncomplete(task_id: &str) -> SyncCommand {
+        SyncCommand::new("item_uncomplete", json!({ "id": task_id }))
+    }
+
+    /// Updates the fields of a task given in `fields`.
+    pub fn item_update(task_id: &str, fields: Value) -> SyncCommand {
+        let mut args = json!({ "id": task_id });
+        if let (Some(args), Value::Object(fields)) = (args.as_object_mut(), fields) {
+            args.extend(fields);
+        }
+        SyncCommand::new("item_update", args)
+    }
+
+    /// Sets the `child_order` of sibling tasks.
+    pub fn item_reorder(orders: &[(String, usize)]) -> SyncCommand {
+        let items: Vec<Value> = orders
+            .iter()
+            .map(|(id, order)| json!({ "id": id, "child_order": order }))
+            .collect();
+        SyncCommand::new("item_reorder", json!({ "items": items }))
+    }
+
+    /// Archives a project with its child projects.
+    pub fn project_archive(project_id: &str) -> SyncCommand {
+        SyncCommand::new("project_archive", json!({ "id": project_id }))
+    }
+
+    pub fn project_unarchive(project_id: &str) -> SyncCommand {
+        SyncCommand::new("project_unarchive", json!({ "id": project_id }))
+    }
+
+    /// Sets the `child_order` of sibling projects.
+    pub fn project_reorder(orders: &[(String, usize)]) -> SyncCommand {
+        let projects: Vec<Value> = orders
+            .iter()
+            .map(|(id, order)| json!({ "id": id, "child_order": order }))
+            .collect();
+        SyncCommand::new("project_reorder", json!({ "projects": projects }))
+    }
+}
+
+/// The `due` argument that gives a task `due` again. Recurring dates and
+/// dates only set locally are sent as their string so the server parses them.
+pub fn due_args(due: Option<&Due>) -> Value {
+    let due = match due {
+        Some(due) => due,
+        None => return Value::Null,
+    };
+    let recurring = due.is_recurring.unwrap_or(false);
+    match (&due.date, &due.datetime, &due.string) {
+        (Some(date), None, _) if !recurring => json!({ "date": date }),
+        // the Sync API keeps date times in `date` as well
+        (Some(_), Some(datetime), _) if !recurring => match &due.timezone {
+            Some(timezone) => json!({ "date": datetime, "timezone": timezone }),
+            None => json!({ "date": datetime }),
+        },
+        (_, _, Some(string)) => json!({ "string": string }),
+        (_, Some(datetime), None) => json!({ "string": datetime }),
+        _ => Value::Null,
+    }
+}
+
+/// Sends `commands` in a single request. Fails with the first command the
+/// server rejected.
+pub async fn sync_commands(token: String, commands: Vec<SyncCommand>) -> Result<(), anyhow::Error> {
+    let response = reqwest::Client::new()
+        .post(SYNC_URL)
+        .header(AUTHORIZATION, format!("Bearer {}", token))
+        .form(&[("commands", serde_json::to_string(&commands)?)])
+        .send()
+        .await?;
+    let response: SyncResponse = check_status(response)?.json().await?;
+
+    for command in &commands {
+        match response.sync_status.get(&command.uuid) {
+            Some(Value::String(status)) if status == "ok" => {}
+            Some(error) => anyhow::bail!("{} failed: {}", command.kind, error),
+            None => anyhow::bail!("{} got no response", command.kind),
+        }
+    }
+    Ok(())
+}
+
+pub async fn get_user(token: String) -> Result<User, anyhow::Error> {
+    let response = reqwest::Client::new()
+        .post(SYNC_URL)
+        .header(AUTHORIZATION, format!("Bearer {}", token))
+        .form(&[("sync_token", "*"), ("resource_types", "[\"user\"]")])
+        .send()
+        .await?;
+    let response: UserResponse = check_status(response)?.json().await?;
+    Ok(response.user)
+}
+
+pub async fn get_archived_projects(token: String) -> Result<Vec<ArchivedProject>, anyhow::Error> {
+    let response = reqwest::Client::new()
+        .get(ARCHIVED_PROJECTS_URL)
+        .header(AUTHORIZATION, format!("Bearer {}", token))
+        .send()
+        .await?;
+    let projects = check_status(response)?.json().await?;
+    Ok(projects)
+}
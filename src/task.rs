@@ -1,5 +1,4 @@
-use std::sync::Arc;
-use std::sync::Mutex;
+use std::collections::HashSet;
 
 use tui::widgets::Paragraph;
 use tui::{
@@ -13,8 +12,13 @@ use tui::{
 
 use crate::{
     api::{Task, TaskContent},
-    handler::{create_advanced_block, create_basic_block, create_basic_paragraph},
-    menu::Database,
+    date::{deadline_label, due_label, format_duration, preview_due_string, DateConfig},
+    due_parser::{parse_deadline, parse_duration},
+    handler::{
+        create_advanced_block, create_basic_block, create_basic_paragraph, input_tail, wrap_text,
+    },
+    priority::{priority_flag, priority_label},
+    sort::SortMode,
 };
 
 #[derive(Copy, Clone, Debug)]
@@ -24,6 +28,8 @@ pub struct AddTaskHighlight {
     pub label: Color,
     pub prio: Color,
     pub due: Color,
+    pub deadline: Color,
+    pub duration: Color,
 }
 
 impl Default for AddTaskHighlight {
@@ -34,12 +40,16 @@ impl Default for AddTaskHighlight {
             label: Color::White,
             prio: Color::White,
             due: Color::White,
+            deadline: Color::White,
+            duration: Color::White,
         }
     }
 }
 
 pub struct TaskStatus {
     pub task_table_state: TableState,
+    /// The first task row drawn.
+    pub offset: usize,
     pub active_task_item: TaskItem,
     pub add_task_highlight: AddTaskHighlight,
     pub task_content: TaskContent,
@@ -49,6 +59,7 @@ impl Default for TaskStatus {
     fn default() -> Self {
         Self {
             task_table_state: TableState::default(),
+            offset: 0,
             active_task_item: TaskItem::Empty,
             add_task_highlight: AddTaskHighlight::default(),
             task_content: TaskContent::default(),
@@ -64,76 +75,110 @@ pub enum TaskItem {
     Prio,
     Label,
     Due,
+    Deadline,
+    Duration,
 }
 
-pub fn add_buffer_if_len(str_len: usize, next_line_buffer: &mut u16, buffer_at: usize) {
-    if str_len >= buffer_at {
-        *next_line_buffer = 3;
-    }
-}
+/// The fields of the add task form, top to bottom. Tab steps through them in
+/// this order.
+pub const TASK_FIELDS: [TaskItem; 7] = [
+    TaskItem::Name,
+    TaskItem::Desc,
+    TaskItem::Label,
+    TaskItem::Due,
+    TaskItem::Deadline,
+    TaskItem::Duration,
+    TaskItem::Prio,
+];
 
 pub fn render_active_task_input_widget<B: Backend>(
     rect: &mut Frame<B>,
     task_status: &TaskStatus,
-    left_right_bottom: Vec<Rect>,
+    dates: &DateConfig,
+    area: Rect,
 ) {
-    match task_status.active_task_item {
-        _ if task_status.active_task_item != TaskItem::Empty => {
-            let mut x = left_right_bottom[1].x + 1;
-            let mut y = left_right_bottom[1].y + 1;
-            let task_content = &task_status.task_content;
-            match task_status.active_task_item {
-                TaskItem::Name => {
-                    let name_len = task_content.content.len();
-                    let mut next_line_buffer = 0;
-                    add_buffer_if_len(name_len, &mut next_line_buffer, 40);
-                    x += name_len as u16 - ((name_len / 40) * 40) as u16 + next_line_buffer;
-                }
-                TaskItem::Desc => {
-                    let desc_len = task_content.description.len();
-                    let mut next_line_buffer = 0;
-                    add_buffer_if_len(desc_len, &mut next_line_buffer, 40);
-                    x += desc_len as u16 - ((desc_len / 40) * 40) as u16 + next_line_buffer;
-                    y += 3;
-                }
-                TaskItem::Label => {
-                    let label_len = task_content.labels.len();
-                    let mut next_line_buffer = 0;
-                    add_buffer_if_len(label_len, &mut next_line_buffer, 40);
-                    x += label_len as u16 - ((label_len / 40) * 40) as u16 + next_line_buffer;
-                    y += 6;
-                }
-                TaskItem::Due => {
-                    let due_len = task_content.due_string.len();
-                    let mut next_line_buffer = 0;
-                    add_buffer_if_len(due_len, &mut next_line_buffer, 40);
-                    x += due_len as u16 - ((due_len / 40) * 40) as u16 + next_line_buffer;
-                    y += 9;
-                }
-                TaskItem::Prio => {
-                    x += task_content.priority.len() as u16;
-                    y += 12;
-                }
-                _ => {}
-            }
-            rect.set_cursor(x, y);
-            render_add_task_input_fields(
-                rect,
-                left_right_bottom,
-                task_status.add_task_highlight,
-                &mut task_status.task_content.clone(),
-            );
-        }
-        _ => {}
+    let fields = task_form_fields(area);
+    let task_content = &task_status.task_content;
+    let item = task_status.active_task_item;
+    let input = match item {
+        TaskItem::Name => Some(&task_content.content),
+        TaskItem::Desc => Some(&task_content.description),
+        TaskItem::Label => Some(&task_content.labels),
+        TaskItem::Due => Some(&task_content.due_string),
+        TaskItem::Deadline => Some(&task_content.deadline),
+        TaskItem::Duration => Some(&task_content.duration),
+        // picked from a list, so there is no cursor
+        TaskItem::Prio => None,
+        TaskItem::Empty => return,
+    };
+    let position = TASK_FIELDS.iter().position(|field| *field == item);
+    if let (Some(text), Some(position)) = (input, position) {
+        let field = fields[position];
+        let visible = input_tail(text, field.width.saturating_sub(2)).chars().count() as u16;
+        rect.set_cursor(field.x + 1 + visible, field.y + 1);
+    }
+    let highlight = task_status.add_task_highlight;
+    render_add_task_input_fields(rect, &fields, highlight, task_content, dates);
+}
+
+/// The title of the due date field, with what the typed string means.
+/// Flags due strings the local parser does not understand in red, since
+/// Todoist may not either.
+fn due_title(due_string: &str, dates: &DateConfig) -> Spans<'static> {
+    if due_string.trim().is_empty() {
+        return Spans::from("Due date · Down for a calendar");
+    }
+    match preview_due_string(due_string, dates.zone.now(), dates) {
+        Some(preview) => Spans::from(format!("Due date · {}", preview)),
+        None => Spans::from(vec![
+            Span::raw("Due date · "),
+            Span::styled("not understood", Style::default().fg(Color::Red)),
+        ]),
+    }
+}
+
+fn deadline_title(deadline: &str, dates: &DateConfig) -> Spans<'static> {
+    if deadline.trim().is_empty() {
+        return Spans::from("Deadline");
+    }
+    match parse_deadline(deadline, dates.zone.now()) {
+        Some(date) => Spans::from(format!("Deadline · {}", date.format("%a %b %-d %Y"))),
+        None => Spans::from(vec![
+            Span::raw("Deadline · "),
+            Span::styled("not a single day", Style::default().fg(Color::Red)),
+        ]),
     }
 }
 
+fn duration_title(duration: &str) -> Spans<'static> {
+    if duration.trim().is_empty() {
+        return Spans::from("Duration · like 30m, 1h30m or 2d");
+    }
+    match parse_duration(duration) {
+        Some(duration) => Spans::from(format!("Duration · {}", format_duration(duration))),
+        None => Spans::from(vec![
+            Span::raw("Duration · "),
+            Span::styled("not understood", Style::default().fg(Color::Red)),
+        ]),
+    }
+}
+
+/// The boxes of the add task form, in the order the fields are rendered.
+pub fn task_form_fields(area: Rect) -> Vec<Rect> {
+    Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3); TASK_FIELDS.len()])
+        .split(area)
+}
+
 pub struct Blocks {
     content: Block<'static>,
     description: Block<'static>,
     labels: Block<'static>,
     prio: Block<'static>,
     due: Block<'static>,
+    deadline: Block<'static>,
+    duration: Block<'static>,
 }
 
 impl Blocks {
@@ -143,6 +188,8 @@ impl Blocks {
         let label = create_basic_block("Labels", highlight.label);
         let prio = create_basic_block("Priority", highlight.prio);
         let due = create_basic_block("Due date", highlight.due);
+        let deadline = create_basic_block("Deadline", highlight.deadline);
+        let duration = create_basic_block("Duration", highlight.duration);
 
         Blocks {
             content: name,
@@ -150,151 +197,154 @@ impl Blocks {
             labels: label,
             prio,
             due,
+            deadline,
+            duration,
         }
     }
 }
 
-pub fn split_if_has_len(str_len: usize, split_at: usize, current: &mut String, task: String) {
-    if str_len >= split_at {
-        let (_, second) = task.split_at(((str_len / split_at) * split_at) - 3);
-        *current = second.to_string();
-    }
-}
-
-pub fn construct_input_paragraph(task_content: String, blocks_content: Block) -> Paragraph {
-    let task_name = task_content.clone();
-    let name_len = task_content.len();
-    let mut current_name = task_name.clone();
-    split_if_has_len(name_len, 40, &mut current_name, task_name);
-    create_basic_paragraph(current_name, blocks_content)
+pub fn construct_input_paragraph<'a>(text: &str, width: u16, block: Block<'a>) -> Paragraph<'a> {
+    create_basic_paragraph(input_tail(text, width.saturating_sub(2)), block)
 }
 
 pub fn render_add_task_input_fields<B: Backend>(
     rect: &mut Frame<B>,
-    project_chunks: Vec<Rect>,
+    fields: &[Rect],
     highlight: AddTaskHighlight,
-    task_content: &mut TaskContent,
+    task_content: &TaskContent,
+    dates: &DateConfig,
 ) {
-    let task_width_full = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(100)].as_ref())
-        .split(project_chunks[1]);
+    let blocks: Blocks = Blocks::create_add_task_blocks(&highlight);
+    let width = fields[0].width;
 
-    let constraints = [Constraint::Length(3); 6];
-    let desc_width = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints(constraints)
-        .split(task_width_full[0]);
+    let name = construct_input_paragraph(&task_content.content, width, blocks.content);
 
-    let blocks: Blocks = Blocks::create_add_task_blocks(&highlight);
+    let desc = construct_input_paragraph(&task_content.description, width, blocks.description);
 
-    let name = construct_input_paragraph(task_content.content.clone(), blocks.content);
+    let label = construct_input_paragraph(&task_content.labels, width, blocks.labels);
 
-    let desc = construct_input_paragraph(task_content.description.clone(), blocks.description);
+    let due_block = blocks.due.title(due_title(&task_content.due_string, dates));
+    let due = construct_input_paragraph(&task_content.due_string, width, due_block);
 
-    let label = construct_input_paragraph(task_content.labels.clone(), blocks.labels);
+    let deadline_block = blocks.deadline.title(deadline_title(&task_content.deadline, dates));
+    let deadline = construct_input_paragraph(&task_content.deadline, width, deadline_block);
 
-    let due = construct_input_paragraph(task_content.due_string.clone(), blocks.due);
+    let duration_block = blocks.duration.title(duration_title(&task_content.duration));
+    let duration = construct_input_paragraph(&task_content.duration, width, duration_block);
 
-    let prio = create_basic_paragraph(task_content.priority.clone(), blocks.prio);
+    let prio = Paragraph::new(Spans::from(vec![
+        priority_flag(task_content.priority),
+        Span::raw(format!(" {}", priority_label(task_content.priority))),
+    ]))
+    .block(blocks.prio);
 
-    rect.render_widget(name, desc_width[0]);
-    rect.render_widget(desc, desc_width[1]);
-    rect.render_widget(label, desc_width[2]);
-    rect.render_widget(due, desc_width[3]);
-    rect.render_widget(prio, desc_width[4]);
+    rect.render_widget(name, fields[0]);
+    rect.render_widget(desc, fields[1]);
+    rect.render_widget(label, fields[2]);
+    rect.render_widget(due, fields[3]);
+    rect.render_widget(deadline, fields[4]);
+    rect.render_widget(duration, fields[5]);
+    rect.render_widget(prio, fields[6]);
 }
 
-pub fn get_task_from_project_id(project_id: String, task_list: &mut Vec<Task>) -> String {
-    let mut counter = 0;
-    (0..task_list.len()).for_each(|i| {
-        if project_id == task_list[i].project_id {
-            counter += 1;
+/// Room for text in a task table `width` columns wide, without the
+/// borders, the highlight symbol and the mark and flag columns with their
+/// spacing.
+fn text_width(width: u16) -> usize {
+    width.saturating_sub(7) as usize
+}
+
+/// The due date, duration and deadline of `task`, the ones it has.
+pub fn task_details(task: &Task, dates: &DateConfig) -> Vec<Span<'static>> {
+    let mut details = vec![];
+    if let Some((due, style)) = task.due.as_ref().and_then(|due| due_label(due, dates)) {
+        details.push(Span::styled(due, style));
+    }
+    if let Some(duration) = task.duration {
+        details.push(Span::raw(format_duration(duration)));
+    }
+    if let Some(deadline) = &task.deadline {
+        let (deadline, style) = deadline_label(deadline, dates);
+        details.push(Span::styled(deadline, style));
+    }
+    let mut spans = vec![];
+    for (i, detail) in details.into_iter().enumerate() {
+        if i > 0 {
+            spans.push(Span::raw(" · "));
         }
-    });
-    counter.to_string()
+        spans.push(detail);
+    }
+    spans
+}
+
+fn task_lines(
+    task: &Task,
+    text_width: usize,
+    style: Style,
+    dates: &DateConfig,
+) -> Vec<Spans<'static>> {
+    let mut lines = vec![];
+    for line in wrap_text(&task.content, text_width) {
+        lines.push(Spans::from(Span::styled(line, style)));
+    }
+    for line in wrap_text(&task.description, text_width) {
+        lines.push(Spans::from(line));
+    }
+    if !task.labels.is_empty() {
+        lines.push(Spans::from(task.labels.join(", ")));
+    }
+    let details = task_details(task, dates);
+    if !details.is_empty() {
+        lines.push(Spans::from(details));
+    }
+    lines
+}
+
+/// Heights of the rows `get_task_table_list` draws for `tasks`, a blank
+/// line below each task included.
+pub fn task_row_heights(tasks: &[&Task], width: u16, dates: &DateConfig) -> Vec<u16> {
+    let text_width = text_width(width);
+    tasks
+        .iter()
+        .map(|task| task_lines(task, text_width, Style::default(), dates).len() as u16 + 1)
+        .collect()
 }
 
 pub fn get_task_table_list(
-    project_table_state: &TableState,
-    database: Arc<Mutex<Database>>,
+    tasks: &[&Task],
+    marked: &HashSet<String>,
+    sort_mode: SortMode,
     selection_color: Color,
     highlight_color: Color,
+    width: u16,
+    dates: &DateConfig,
 ) -> Table<'static> {
-    let task_block = create_advanced_block("Tasks", selection_color, Alignment::Left);
-
-    let projects = database.lock().unwrap().projects.clone();
-    let tasks = database.lock().unwrap().tasks.clone();
-
-    let selected_project = projects
-        .get(
-            project_table_state
-                .selected()
-                .expect("there is always a selected project"),
-        )
-        .expect("exists")
-        .clone();
+    let text_width = text_width(width);
+    let mut title = match sort_mode {
+        SortMode::Manual => "Tasks".to_owned(),
+        mode => format!("Tasks · {}", mode.label()),
+    };
+    if !marked.is_empty() {
+        title = format!("{} · {} marked", title, marked.len());
+    }
+    let task_block = create_advanced_block(title, selection_color, Alignment::Left);
 
     let task_rows: Vec<_> = tasks
         .iter()
-        .filter(|task| task.project_id == selected_project.id)
         .map(|task| {
             let style = Style::default()
                 .add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
                 .fg(highlight_color);
-            let empty = Cell::from("");
-
-            let mut updated_row = vec![];
-            let mut height = 2;
-
-            updated_row.push(Spans::from(Span::styled(task.content.clone(), style)));
-            
-            let desc_len = task.description.len();
-            let mut c_desc = task.description.clone();
-            if desc_len > 0 && desc_len < 38 {
-                height += 1;
-                updated_row.push(Spans::from(task.description.clone()));
-            }
-            if desc_len != 0 && desc_len >= 38 {
-                for _ in 1..=(desc_len / 38) {
-                    let end_split = c_desc.split_off(38);
-                    if end_split.len() <= 3 {
-                        c_desc.push_str(&end_split);
-                        updated_row.push(Spans::from(c_desc.clone()));
-                        c_desc.clear();
-                    } else {
-                        updated_row.push(Spans::from(c_desc.clone()));
-                        c_desc = end_split;
-                    }
-                    height += 1;
-                }
-                if !c_desc.is_empty() {
-                    height += 1;
-                    updated_row.push(Spans::from(c_desc));
-                }
-            }
-
-            if !task.labels.is_empty() {
-                height += 1;
-                updated_row.push(Spans::from(task.labels.join(", ")));
-            }
-
-            match &task.due {
-                Some(due) => match &due.datetime {
-                    Some(datetime) => {
-                        height += 1;
-                        if datetime.chars().into_iter().nth(10) == Some('T') {
-                            updated_row.push(Spans::from(datetime.replace('T', " ")));
-                        } else {
-                            updated_row.push(Spans::from(datetime.to_owned()));
-                        }
-                    }
-                    None => {}
-                },
-                None => {}
+            let mark = if marked.contains(&task.id) {
+                Cell::from(Span::styled("*", Style::default().fg(highlight_color)))
+            } else {
+                Cell::from("")
             };
 
-            Row::new(vec![empty, Cell::from(updated_row)]).height(height)
+            let lines = task_lines(task, text_width, style, dates);
+            let height = lines.len() as u16 + 1;
+            let flag = Cell::from(priority_flag(task.priority));
+            Row::new(vec![mark, flag, Cell::from(lines)]).height(height)
         })
         .collect();
 
@@ -303,7 +353,11 @@ pub fn get_task_table_list(
         .highlight_style(Style::default().add_modifier(Modifier::BOLD))
         .column_spacing(1)
         .highlight_symbol(">")
-        .widths(&[Constraint::Max(2), Constraint::Percentage(100)]);
+        .widths(&[
+            Constraint::Max(1),
+            Constraint::Length(1),
+            Constraint::Percentage(100),
+        ]);
 
     task_list
 }
@@ -0,0 +1,923 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use chrono::{DateTime, NaiveDateTime, NaiveTime, Utc};
+use serde_derive::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    api::{
+        close_task, delete_task, post_task, update_task, Due, Project, Task, TaskContent,
+        TaskUpdate,
+    },
+    date::{parse_due, DueAt, Zone},
+    sync::{sync_commands, Placement, SyncCommand},
+    uuid::stable_uuid,
+};
+
+pub const DEFAULT_MAP_FILE: &str = "taskwarrior-sync.json";
+
+/// How Taskwarrior writes dates, always in UTC.
+const TW_DATE_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// A task as printed by `task export`. Attributes this bridge does not know
+/// about are kept in `extra` so they survive a round trip through `task import`.
+#[derive(Deserialize, Serialize, Debug, Clone, Default)]
+pub struct TwTask {
+    pub uuid: String,
+    pub description: String,
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub priority: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<String>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl TwTask {
+    fn is_open(&self) -> bool {
+        self.status == "pending" || self.status == "waiting"
+    }
+}
+
+/// The fields both sides understand, normalized so they can be compared.
+#[derive(Deserialize, Serialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct SharedFields {
+    pub content: String,
+    pub priority: usize,
+    pub labels: Vec<String>,
+    pub project: String,
+    /// UTC as `YYYYMMDDTHHMMSSZ`, all-day dates at local midnight.
+    pub due: Option<String>,
+}
+
+/// A Todoist due date the way Taskwarrior stores dates. Floating times and
+/// all-day dates are in the timezone of `zone`.
+fn taskwarrior_due(due: &Due, zone: Zone) -> Option<String> {
+    let utc = match due.datetime.as_deref().map(DateTime::parse_from_rfc3339) {
+        Some(Ok(datetime)) => datetime.naive_utc().and_utc(),
+        _ => {
+            let local = match parse_due(due, zone)? {
+                DueAt::Day(date) => date.and_time(NaiveTime::MIN),
+                DueAt::Time(datetime) => datetime,
+            };
+            zone.utc(local)?
+        }
+    };
+    Some(utc.format(TW_DATE_FORMAT).to_string())
+}
+
+/// The Todoist project a Taskwarrior project name stands for, the inbox for
+/// none.
+fn project_named<'a>(projects: &'a [Project], name: &str) -> Option<&'a Project> {
+    if name.is_empty() {
+        projects.iter().find(|project| project.is_inbox_project)
+    } else {
+        projects.iter().find(|project| project.name == name)
+    }
+}
+
+impl SharedFields {
+    pub fn from_todoist(task: &Task, projects: &[Project], zone: Zone) -> SharedFields {
+        let mut labels = task.labels.clone();
+        labels.sort();
+        SharedFields {
+            content: task.content.clone(),
+            priority: task.priority.clamp(1, 4),
+            labels,
+            project: projects
+                .iter()
+                .find(|project| project.id == task.project_id)
+                .map(|project| project.name.clone())
+                .unwrap_or_default(),
+            due: task.due.as_ref().and_then(|due| taskwarrior_due(due, zone)),
+        }
+    }
+
+    pub fn from_taskwarrior(task: &TwTask) -> SharedFields {
+        let mut labels = task.tags.clone();
+        labels.sort();
+        SharedFields {
+            content: task.description.clone(),
+            priority: match task.priority.as_deref() {
+                Some("H") => 4,
+                Some("M") => 3,
+                Some("L") => 2,
+                _ => 1,
+            },
+            labels,
+            project: task.project.clone().unwrap_or_default(),
+            due: task.due.clone(),
+        }
+    }
+
+    /// Writes these fields onto `task`, keeping its other attributes.
+    pub fn apply_to_taskwarrior(&self, task: &mut TwTask) {
+        task.description = self.content.clone();
+        task.priority = match self.priority {
+            4 => Some("H".to_owned()),
+            3 => Some("M".to_owned()),
+            2 => Some("L".to_owned()),
+            _ => None,
+        };
+        task.tags = self.labels.clone();
+        task.project = Some(self.project.clone()).filter(|project| !project.is_empty());
+        task.due = self.due.clone();
+    }
+
+    /// The update setting these fields in Todoist, where dues at local
+    /// midnight in `zone` become all-day dates.
+    pub fn to_update(&self, zone: Zone) -> TaskUpdate {
+        let mut update = TaskUpdate {
+            content: Some(self.content.clone()),
+            labels: Some(self.labels.clone()),
+            priority: Some(self.priority),
+            ..Default::default()
+        };
+        let due = self
+            .due
+            .as_deref()
+            .and_then(|due| NaiveDateTime::parse_from_str(due, TW_DATE_FORMAT).ok());
+        match due {
+            Some(utc) => {
+                let local = zone.local_time(utc.and_utc());
+                if local.time() == NaiveTime::MIN {
+                    update.due_date = Some(local.format("%Y-%m-%d").to_string());
+                } else {
+                    update.due_datetime = Some(utc.format("%Y-%m-%dT%H:%M:%SZ").to_string());
+                }
+            }
+            None => update.due_string = Some("no date".to_owned()),
+        }
+        update
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct SyncEntry {
+    pub todoist_id: String,
+    pub uuid: String,
+    /// What both sides looked like after the last sync.
+    pub last: SharedFields,
+}
+
+/// Correlates Todoist ids with Taskwarrior UUIDs between runs.
+#[derive(Default, Deserialize, Serialize, Debug, Clone)]
+pub struct SyncMap {
+    pub entries: Vec<SyncEntry>,
+}
+
+impl SyncMap {
+    pub fn load(path: &Path) -> Result<SyncMap, anyhow::Error> {
+        match fs::read_to_string(path) {
+            Ok(contents) => Ok(serde_json::from_str(&contents)?),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(SyncMap::default()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), anyhow::Error> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum SyncAction {
+    CreateInTaskwarrior {
+        todoist_id: String,
+        task: TwTask,
+        shared: SharedFields,
+    },
+    UpdateTaskwarrior {
+        todoist_id: String,
+        task: TwTask,
+        shared: SharedFields,
+    },
+    CompleteInTaskwarrior {
+        task: TwTask,
+    },
+    CreateInTodoist {
+        uuid: String,
+        content: TaskContent,
+        project_id: String,
+        shared: SharedFields,
+    },
+    UpdateTodoist {
+        todoist_id: String,
+        uuid: String,
+        shared: SharedFields,
+        /// The project to move the task to, when it changed.
+        project_id: Option<String>,
+    },
+    /// A task was put into a Taskwarrior project Todoist does not have, so
+    /// it stays where it is in Todoist.
+    UnknownProject {
+        content: String,
+        project: String,
+    },
+    CloseInTodoist {
+        todoist_id: String,
+        uuid: String,
+    },
+    DeleteInTodoist {
+        todoist_id: String,
+        uuid: String,
+    },
+    Forget {
+        todoist_id: String,
+    },
+    /// Both sides were changed the same way, so only the sync map needs the
+    /// new values.
+    Record {
+        todoist_id: String,
+        shared: SharedFields,
+    },
+    Conflict {
+        todoist_id: String,
+        uuid: String,
+        todoist: SharedFields,
+        taskwarrior: SharedFields,
+    },
+}
+
+impl SyncAction {
+    pub fn describe(&self) -> String {
+        match self {
+            SyncAction::CreateInTaskwarrior { shared, .. } => {
+                format!("taskwarrior: create '{}'", shared.content)
+            }
+            SyncAction::UpdateTaskwarrior { shared, .. } => {
+                format!("taskwarrior: update '{}'", shared.content)
+            }
+            SyncAction::CompleteInTaskwarrior { task } => {
+                format!("taskwarrior: complete '{}'", task.description)
+            }
+            SyncAction::CreateInTodoist { shared, .. } => {
+                format!("todoist: create '{}'", shared.content)
+            }
+            SyncAction::UpdateTodoist {
+                shared,
+                project_id: Some(_),
+                ..
+            } => format!(
+                "todoist: update '{}' and move it to '{}'",
+                shared.content, shared.project
+            ),
+            SyncAction::UpdateTodoist { shared, .. } => {
+                format!("todoist: update '{}'", shared.content)
+            }
+            SyncAction::UnknownProject { content, project } => format!(
+                "warning: '{}' was not moved, todoist has no project '{}'",
+                content, project
+            ),
+            SyncAction::CloseInTodoist { todoist_id, .. } => {
+                format!("todoist: complete task {}", todoist_id)
+            }
+            SyncAction::DeleteInTodoist { todoist_id, .. } => {
+                format!("todoist: delete task {}", todoist_id)
+            }
+            SyncAction::Forget { todoist_id } => format!("forget finished task {}", todoist_id),
+            SyncAction::Record { shared, .. } => {
+                format!(
+                    "record '{}', changed the same way on both sides",
+                    shared.content
+                )
+            }
+            SyncAction::Conflict {
+                todoist,
+                taskwarrior,
+                ..
+            } => format!(
+                "conflict: '{}' in todoist and '{}' in taskwarrior both changed, skipped",
+                todoist.content, taskwarrior.content
+            ),
+        }
+    }
+}
+
+/// Stable UUID for a Todoist id, so that even without the sync map the same
+/// task is never created twice.
+pub fn uuid_for_todoist_id(todoist_id: &str) -> String {
+    stable_uuid(todoist_id)
+}
+
+fn now_utc() -> String {
+    Utc::now().format(TW_DATE_FORMAT).to_string()
+}
+
+/// Compares both sides with the state of the last sync and decides what to
+/// do. Nothing is changed here.
+pub fn plan_sync(
+    todoist_tasks: &[Task],
+    projects: &[Project],
+    tw_tasks: &[TwTask],
+    map: &SyncMap,
+    zone: Zone,
+) -> Vec<SyncAction> {
+    let todoist_by_id: HashMap<&str, &Task> =
+        todoist_tasks.iter().map(|t| (t.id.as_str(), t)).collect();
+    let tw_by_uuid: HashMap<&str, &TwTask> =
+        tw_tasks.iter().map(|t| (t.uuid.as_str(), t)).collect();
+    let mapped_ids: HashSet<&str> = map.entries.iter().map(|e| e.todoist_id.as_str()).collect();
+    let mapped_uuids: HashSet<&str> = map.entries.iter().map(|e| e.uuid.as_str()).collect();
+    let mut actions = vec![];
+
+    for entry in &map.entries {
+        let todoist = todoist_by_id.get(entry.todoist_id.as_str());
+        let tw = tw_by_uuid.get(entry.uuid.as_str());
+        let todoist_id = entry.todoist_id.clone();
+        let uuid = entry.uuid.clone();
+
+        match (todoist, tw) {
+            (None, Some(tw)) if tw.is_open() => actions.push(SyncAction::CompleteInTaskwarrior {
+                task: (*tw).clone(),
+            }),
+            (None, _) => actions.push(SyncAction::Forget { todoist_id }),
+            (Some(_), Some(tw)) if tw.status == "completed" => {
+                actions.push(SyncAction::CloseInTodoist { todoist_id, uuid })
+            }
+            (Some(_), Some(tw)) if tw.status == "deleted" => {
+                actions.push(SyncAction::DeleteInTodoist { todoist_id, uuid })
+            }
+            (Some(todoist), tw) => {
+                let todoist_shared = SharedFields::from_todoist(todoist, projects, zone);
+                let tw_shared = tw.map(|tw| SharedFields::from_taskwarrior(tw));
+                let todoist_changed = todoist_shared != entry.last;
+                let tw_changed = tw_shared
+                    .as_ref()
+                    .is_some_and(|shared| *shared != entry.last);
+
+                match tw {
+                    None => {
+                        // removed from the Taskwarrior database, create it again
+                        let mut task = new_tw_task(&uuid);
+                        todoist_shared.apply_to_taskwarrior(&mut task);
+                        actions.push(SyncAction::CreateInTaskwarrior {
+                            todoist_id,
+                            task,
+                            shared: todoist_shared,
+                        });
+                    }
+                    Some(tw) => match (todoist_changed, tw_changed) {
+                        (true, true) if Some(&todoist_shared) != tw_shared.as_ref() => actions
+                            .push(SyncAction::Conflict {
+                                todoist_id,
+                                uuid,
+                                todoist: todoist_shared,
+                                taskwarrior: tw_shared.unwrap_or_default(),
+                            }),
+                        (true, true) => actions.push(SyncAction::Record {
+                            todoist_id,
+                            shared: todoist_shared,
+                        }),
+                        (true, false) => {
+                            let mut task = (*tw).clone();
+                            todoist_shared.apply_to_taskwarrior(&mut task);
+                            actions.push(SyncAction::UpdateTaskwarrior {
+                                todoist_id,
+                                task,
+                                shared: todoist_shared,
+                            });
+                        }
+                        (false, true) => {
+                            let mut shared = tw_shared.unwrap_or_default();
+                            let mut project_id = None;
+                            if shared.project != todoist_shared.project {
+                                match project_named(projects, &shared.project) {
+                                    Some(project) if project.name == todoist_shared.project => {
+                                        shared.project = project.name.clone()
+                                    }
+                                    Some(project) => {
+                                        project_id = Some(project.id.clone());
+                                        shared.project = project.name.clone();
+                                    }
+                                    None => {
+                                        actions.push(SyncAction::UnknownProject {
+                                            content: shared.content.clone(),
+                                            project: shared.project.clone(),
+                                        });
+                                        shared.project = todoist_shared.project.clone();
+                                    }
+                                }
+                            }
+                            // the Taskwarrior project stays out of the sync map
+                            // until Todoist has it too
+                            if shared != entry.last {
+                                actions.push(SyncAction::UpdateTodoist {
+                                    todoist_id,
+                                    uuid,
+                                    shared,
+                                    project_id,
+                                });
+                            }
+                        }
+                        _ => {}
+                    },
+                }
+            }
+        }
+    }
+
+    for task in todoist_tasks {
+        if mapped_ids.contains(task.id.as_str()) {
+            continue;
+        }
+        let uuid = uuid_for_todoist_id(&task.id);
+        let shared = SharedFields::from_todoist(task, projects, zone);
+        let mut tw_task = tw_by_uuid
+            .get(uuid.as_str())
+            .map(|tw| (*tw).clone())
+            .unwrap_or_else(|| new_tw_task(&uuid));
+        shared.apply_to_taskwarrior(&mut tw_task);
+        actions.push(SyncAction::CreateInTaskwarrior {
+            todoist_id: task.id.clone(),
+            task: tw_task,
+            shared,
+        });
+    }
+
+    let generated: HashSet<String> = todoist_tasks
+        .iter()
+        .map(|task| uuid_for_todoist_id(&task.id))
+        .collect();
+    for tw in tw_tasks {
+        if !tw.is_open() || mapped_uuids.contains(tw.uuid.as_str()) || generated.contains(&tw.uuid)
+        {
+            continue;
+        }
+        let shared = SharedFields::from_taskwarrior(tw);
+        let project_id = project_named(projects, &shared.project)
+            .or_else(|| project_named(projects, ""))
+            .map(|project| project.id.clone())
+            .unwrap_or_default();
+        actions.push(SyncAction::CreateInTodoist {
+            uuid: tw.uuid.clone(),
+            content: TaskContent {
+                content: shared.content.clone(),
+                description: String::new(),
+                labels: shared.labels.join(","),
+                priority: shared.priority,
+                due_string: String::new(),
+                deadline: String::new(),
+                duration: String::new(),
+            },
+            project_id,
+            shared,
+        });
+    }
+
+    actions
+}
+
+fn new_tw_task(uuid: &str) -> TwTask {
+    TwTask {
+        uuid: uuid.to_owned(),
+        status: "pending".to_owned(),
+        ..Default::default()
+    }
+}
+
+/// Runs the `task` binary, optionally against a separate data directory.
+pub struct Taskwarrior {
+    pub data_dir: Option<PathBuf>,
+    /// The taskrc to read instead of `~/.taskrc`.
+    pub taskrc: Option<PathBuf>,
+    /// Where all-day dates start, local midnight for Taskwarrior.
+    pub zone: Zone,
+}
+
+impl Taskwarrior {
+    fn command(&self) -> Command {
+        let mut command = Command::new("task");
+        command.args(["rc.confirmation=off", "rc.hooks=off", "rc.verbose=nothing"]);
+        if let Some(data_dir) = &self.data_dir {
+            command.env("TASKDATA", data_dir);
+        }
+        if let Some(taskrc) = &self.taskrc {
+            command.env("TASKRC", taskrc);
+        }
+        command
+    }
+
+    pub fn export(&self) -> Result<Vec<TwTask>, anyhow::Error> {
+        let output = self.command().arg("export").output()?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "task export failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(serde_json::from_slice(&output.stdout)?)
+    }
+
+    pub fn import(&self, tasks: &[TwTask]) -> Result<(), anyhow::Error> {
+        if tasks.is_empty() {
+            return Ok(());
+        }
+        let tasks: Vec<TwTask> = tasks
+            .iter()
+            .cloned()
+            .map(|mut task| {
+                // computed by Taskwarrior, not attributes
+                task.extra.remove("id");
+                task.extra.remove("urgency");
+                task
+            })
+            .collect();
+
+        let mut child = self
+            .command()
+            .args(["import", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(&serde_json::to_vec(&tasks)?)?;
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            anyhow::bail!(
+                "task import failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Carries out `actions` and records the outcome in `map`. Taskwarrior
+/// changes are written with a single `task import`, also when a Todoist
+/// request fails halfway, so `map` always matches both sides.
+pub async fn apply_sync(
+    token: String,
+    taskwarrior: &Taskwarrior,
+    actions: &[SyncAction],
+    map: &mut SyncMap,
+) -> Result<(), anyhow::Error> {
+    let mut imports = vec![];
+    let mut entries: HashMap<String, SyncEntry> = map
+        .entries
+        .drain(..)
+        .map(|entry| (entry.todoist_id.clone(), entry))
+        .collect();
+
+    let result = apply_actions(token, actions, taskwarrior.zone, &mut imports, &mut entries).await;
+
+    let imported = taskwarrior.import(&imports);
+    map.entries = entries.into_values().collect();
+    map.entries.sort_by(|a, b| a.todoist_id.cmp(&b.todoist_id));
+    result.and(imported)
+}
+
+async fn apply_actions(
+    token: String,
+    actions: &[SyncAction],
+    zone: Zone,
+    imports: &mut Vec<TwTask>,
+    entries: &mut HashMap<String, SyncEntry>,
+) -> Result<(), anyhow::Error> {
+    for action in actions {
+        match action {
+            SyncAction::CreateInTaskwarrior {
+                todoist_id,
+                task,
+                shared,
+            }
+            | SyncAction::UpdateTaskwarrior {
+                todoist_id,
+                task,
+                shared,
+            } => {
+                imports.push(task.clone());
+                entries.insert(
+                    todoist_id.clone(),
+                    SyncEntry {
+                        todoist_id: todoist_id.clone(),
+                        uuid: task.uuid.clone(),
+                        last: shared.clone(),
+                    },
+                );
+            }
+            SyncAction::CompleteInTaskwarrior { task } => {
+                let mut task = task.clone();
+                task.status = "completed".to_owned();
+                task.end = Some(now_utc());
+                entries.retain(|_, entry| entry.uuid != task.uuid);
+                imports.push(task);
+            }
+            SyncAction::CreateInTodoist {
+                uuid,
+                content,
+                project_id,
+                shared,
+            } => {
+                let task = Task::temp(content.clone(), project_id.clone());
+                let created = post_task(token.clone(), task).await?;
+                if shared.due.is_some() {
+                    update_task(token.clone(), created.id.clone(), shared.to_update(zone)).await?;
+                }
+                entries.insert(
+                    created.id.clone(),
+                    SyncEntry {
+                        todoist_id: created.id,
+                        uuid: uuid.clone(),
+                        last: shared.clone(),
+                    },
+                );
+            }
+            SyncAction::UpdateTodoist {
+                todoist_id,
+                shared,
+                project_id,
+                ..
+            } => {
+                update_task(token.clone(), todoist_id.clone(), shared.to_update(zone)).await?;
+                if let Some(project_id) = project_id {
+                    let to = Placement {
+                        project_id: project_id.clone(),
+                        section_id: None,
+                        parent_id: None,
+                    };
+                    sync_commands(token.clone(), vec![SyncCommand::item_move(todoist_id, &to)])
+                        .await?;
+                }
+                if let Some(entry) = entries.get_mut(todoist_id) {
+                    entry.last = shared.clone();
+                }
+            }
+            SyncAction::CloseInTodoist { todoist_id, .. } => {
+                close_task(token.clone(), todoist_id.clone()).await?;
+                entries.remove(todoist_id);
+            }
+            SyncAction::DeleteInTodoist { todoist_id, .. } => {
+                delete_task(token.clone(), todoist_id.clone()).await?;
+                entries.remove(todoist_id);
+            }
+            SyncAction::Forget { todoist_id } => {
+                entries.remove(todoist_id);
+            }
+            SyncAction::Record { todoist_id, shared } => {
+                if let Some(entry) = entries.get_mut(todoist_id) {
+                    entry.last = shared.clone();
+                }
+            }
+            SyncAction::Conflict { .. } | SyncAction::UnknownProject { .. } => {}
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono_tz::Europe::Berlin;
+
+    use super::*;
+
+    const BERLIN: Zone = Zone::Named(Berlin);
+
+    fn projects() -> Vec<Project> {
+        let mut inbox = Project::name("Inbox");
+        inbox.id = "1".to_owned();
+        inbox.is_inbox_project = true;
+        let mut work = Project::name("Work");
+        work.id = "2".to_owned();
+        vec![inbox, work]
+    }
+
+    fn due(date: &str, datetime: Option<&str>) -> Due {
+        Due {
+            date: Some(date.to_owned()),
+            is_recurring: Some(false),
+            datetime: datetime.map(str::to_owned),
+            timezone: None,
+            string: None,
+        }
+    }
+
+    fn todoist_task(id: &str, content: &str, due: Option<Due>) -> Task {
+        let content = TaskContent {
+            content: content.to_owned(),
+            ..Default::default()
+        };
+        let mut task = Task::temp(content, "1".to_owned());
+        task.id = id.to_owned();
+        task.due = due;
+        task
+    }
+
+    /// The sync map and Taskwarrior task right after `task` was synced.
+    fn synced(task: &Task) -> (SyncMap, TwTask) {
+        let shared = SharedFields::from_todoist(task, &projects(), BERLIN);
+        let uuid = uuid_for_todoist_id(&task.id);
+        let mut tw = new_tw_task(&uuid);
+        shared.apply_to_taskwarrior(&mut tw);
+        let entry = SyncEntry {
+            todoist_id: task.id.clone(),
+            uuid,
+            last: shared,
+        };
+        (
+            SyncMap {
+                entries: vec![entry],
+            },
+            tw,
+        )
+    }
+
+    fn tw_due(due: &Due) -> Option<String> {
+        let task = todoist_task("7", "Pay rent", Some(due.clone()));
+        SharedFields::from_todoist(&task, &projects(), BERLIN).due
+    }
+
+    #[test]
+    fn all_day_dates_are_local_midnight() {
+        let due = tw_due(&due("2026-10-20", None));
+        assert_eq!(due.as_deref(), Some("20261019T220000Z"));
+
+        let shared = SharedFields {
+            due,
+            ..Default::default()
+        };
+        let update = shared.to_update(BERLIN);
+        assert_eq!(update.due_date.as_deref(), Some("2026-10-20"));
+        assert_eq!(update.due_datetime, None);
+    }
+
+    #[test]
+    fn floating_times_are_local_and_utc_times_kept() {
+        let floating = due("2026-10-20T09:00:00", Some("2026-10-20T09:00:00"));
+        assert_eq!(tw_due(&floating).as_deref(), Some("20261020T070000Z"));
+        let utc = due("2026-10-20", Some("2026-10-20T09:00:00Z"));
+        assert_eq!(tw_due(&utc).as_deref(), Some("20261020T090000Z"));
+
+        let shared = SharedFields {
+            due: Some("20261020T070000Z".to_owned()),
+            ..Default::default()
+        };
+        let update = shared.to_update(BERLIN);
+        assert_eq!(update.due_date, None);
+        assert_eq!(update.due_datetime.as_deref(), Some("2026-10-20T07:00:00Z"));
+    }
+
+    #[test]
+    fn unchanged_tasks_need_nothing() {
+        let task = todoist_task("7", "Pay rent", Some(due("2026-10-20", None)));
+        let (map, tw) = synced(&task);
+        let actions = plan_sync(&[task], &projects(), &[tw], &map, BERLIN);
+        assert!(actions.is_empty(), "{:?}", actions);
+    }
+
+    #[tokio::test]
+    async fn the_same_change_on_both_sides_is_recorded() {
+        let task = todoist_task("7", "Pay rent", Some(due("2026-10-20", None)));
+        let (map, mut tw) = synced(&task);
+        let mut task = task;
+        task.content = "Pay the rent".to_owned();
+        tw.description = "Pay the rent".to_owned();
+
+        let actions = plan_sync(
+            std::slice::from_ref(&task),
+            &projects(),
+            &[tw.clone()],
+            &map,
+            BERLIN,
+        );
+        let shared = match actions.as_slice() {
+            [SyncAction::Record { todoist_id, shared }] if todoist_id == "7" => shared.clone(),
+            actions => panic!("{:?}", actions),
+        };
+        assert_eq!(shared.content, "Pay the rent");
+
+        let mut entries: HashMap<String, SyncEntry> = map
+            .entries
+            .into_iter()
+            .map(|entry| (entry.todoist_id.clone(), entry))
+            .collect();
+        let mut imports = vec![];
+        apply_actions(String::new(), &actions, BERLIN, &mut imports, &mut entries)
+            .await
+            .unwrap();
+        assert!(imports.is_empty());
+        assert_eq!(entries["7"].last, shared);
+
+        // the next run finds nothing to do
+        let map = SyncMap {
+            entries: entries.into_values().collect(),
+        };
+        assert!(plan_sync(&[task], &projects(), &[tw], &map, BERLIN).is_empty());
+    }
+
+    #[test]
+    fn new_todoist_tasks_are_created_in_taskwarrior() {
+        let task = todoist_task("7", "Pay rent", Some(due("2026-10-20", None)));
+        let actions = plan_sync(&[task], &projects(), &[], &SyncMap::default(), BERLIN);
+        match actions.as_slice() {
+            [SyncAction::CreateInTaskwarrior { task, .. }] => {
+                assert_eq!(task.description, "Pay rent");
+                assert_eq!(task.project.as_deref(), Some("Inbox"));
+                assert_eq!(task.due.as_deref(), Some("20261019T220000Z"));
+            }
+            actions => panic!("{:?}", actions),
+        }
+    }
+
+    #[test]
+    fn project_changes_in_taskwarrior_move_the_task() {
+        let task = todoist_task("7", "Pay rent", None);
+        let (map, mut tw) = synced(&task);
+        tw.project = Some("Work".to_owned());
+        let actions = plan_sync(&[task], &projects(), &[tw], &map, BERLIN);
+        match actions.as_slice() {
+            [SyncAction::UpdateTodoist {
+                shared, project_id, ..
+            }] => {
+                assert_eq!(project_id.as_deref(), Some("2"));
+                assert_eq!(shared.project, "Work");
+            }
+            actions => panic!("{:?}", actions),
+        }
+    }
+
+    #[test]
+    fn unknown_projects_are_reported_and_not_recorded() {
+        let task = todoist_task("7", "Pay rent", None);
+        let (map, mut tw) = synced(&task);
+        tw.project = Some("Garden".to_owned());
+        let tasks = [task];
+        let actions = plan_sync(&tasks, &projects(), std::slice::from_ref(&tw), &map, BERLIN);
+        assert!(
+            matches!(actions.as_slice(), [SyncAction::UnknownProject { project, .. }] if project == "Garden"),
+            "{:?}",
+            actions
+        );
+
+        tw.description = "Pay the rent".to_owned();
+        let actions = plan_sync(&tasks, &projects(), &[tw], &map, BERLIN);
+        match actions.as_slice() {
+            [SyncAction::UnknownProject { .. }, SyncAction::UpdateTodoist {
+                shared, project_id, ..
+            }] => {
+                assert_eq!(project_id, &None);
+                assert_eq!(shared.project, "Inbox");
+                assert_eq!(shared.content, "Pay the rent");
+            }
+            actions => panic!("{:?}", actions),
+        }
+    }
+
+    #[test]
+    fn no_project_in_taskwarrior_is_the_inbox() {
+        let task = todoist_task("7", "Pay rent", None);
+        let (map, mut tw) = synced(&task);
+        tw.project = None;
+        let actions = plan_sync(&[task], &projects(), &[tw], &map, BERLIN);
+        assert!(actions.is_empty(), "{:?}", actions);
+    }
+
+    #[tokio::test]
+    #[ignore = "needs the task binary"]
+    async fn applies_to_a_taskwarrior_data_dir() {
+        let dir = std::env::temp_dir().join(format!("todoist-tui-tw-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let taskrc = dir.join("taskrc");
+        fs::write(&taskrc, "").unwrap();
+        let taskwarrior = Taskwarrior {
+            data_dir: Some(dir.clone()),
+            taskrc: Some(taskrc),
+            zone: BERLIN,
+        };
+
+        let task = todoist_task("7", "Pay rent", Some(due("2026-10-20", None)));
+        let tasks = [task];
+        let mut map = SyncMap::default();
+        let actions = plan_sync(&tasks, &projects(), &[], &map, BERLIN);
+        // only Taskwarrior is changed, so no token is needed
+        apply_sync(String::new(), &taskwarrior, &actions, &mut map)
+            .await
+            .unwrap();
+
+        let tw_tasks = taskwarrior.export().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(tw_tasks.len(), 1);
+        assert_eq!(tw_tasks[0].description, "Pay rent");
+        assert_eq!(tw_tasks[0].due.as_deref(), Some("20261019T220000Z"));
+        assert_eq!(map.entries.len(), 1);
+        let actions = plan_sync(&tasks, &projects(), &tw_tasks, &map, BERLIN);
+        assert!(actions.is_empty(), "{:?}", actions);
+    }
+}